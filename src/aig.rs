@@ -0,0 +1,829 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+
+use crate::algebra::BooleanAlgebra;
+use crate::circuit::{Circuit, Gate};
+use crate::cnf::Literal;
+use crate::expression::{Expression, Symbol, SYMBOL_RANGE};
+use crate::solver::Solver;
+
+/// Words of random input vectors simulated to find candidate equivalences
+const SIMULATION_WORDS: usize = 4;
+
+/// Reference to a node, possibly complemented, packed as
+/// `node << 1 | complemented`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Edge(u32);
+
+impl Edge {
+    pub const FALSE: Edge = Edge(0);
+    pub const TRUE: Edge = Edge(1);
+
+    pub fn new(node: usize, complemented: bool) -> Self {
+        Self((node as u32) << 1 | complemented as u32)
+    }
+
+    pub fn node(self) -> usize {
+        (self.0 >> 1) as usize
+    }
+
+    pub fn is_complemented(self) -> bool {
+        self.0 & 1 != 0
+    }
+
+    /// Literal of the node's solver variable, complemented like the edge
+    fn literal(self) -> Literal {
+        Literal::new(self.node() as u32, !self.is_complemented())
+    }
+}
+
+impl core::ops::Not for Edge {
+    type Output = Edge;
+
+    fn not(self) -> Self::Output {
+        Self(self.0 ^ 1)
+    }
+}
+
+impl core::ops::BitXor<bool> for Edge {
+    type Output = Edge;
+
+    /// Complemented if `complement` is set
+    fn bitxor(self, complement: bool) -> Self::Output {
+        Self(self.0 ^ complement as u32)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Node {
+    /// Node `0`, false through a regular edge
+    Constant,
+    /// The `n`th input of the graph
+    Input(usize),
+    And(Edge, Edge),
+}
+
+/// And-inverter graph: two-input AND nodes read through possibly
+/// complemented edges
+///
+/// Nodes only read nodes built before them, and each AND of two edges is
+/// built once.
+#[derive(Clone, Debug)]
+pub struct Aig {
+    nodes: Vec<Node>,
+    inputs: Vec<usize>,
+    outputs: Vec<Edge>,
+    built: HashMap<(Edge, Edge), Edge>,
+}
+
+impl Default for Aig {
+    fn default() -> Self {
+        Self {
+            nodes: vec![Node::Constant],
+            inputs: Vec::new(),
+            outputs: Vec::new(),
+            built: HashMap::new(),
+        }
+    }
+}
+
+impl Aig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn input(&mut self) -> Edge {
+        self.nodes.push(Node::Input(self.inputs.len()));
+        self.inputs.push(self.nodes.len() - 1);
+        Edge::new(self.nodes.len() - 1, false)
+    }
+
+    /// Edge computing `a & b`, simplified against constants and equal or
+    /// opposite operands
+    pub fn and(&mut self, a: Edge, b: Edge) -> Edge {
+        let (a, b) = (a.min(b), a.max(b));
+        if a == Edge::FALSE || a == !b {
+            return Edge::FALSE;
+        }
+        if a == Edge::TRUE || a == b {
+            return b;
+        }
+        if let Some(&edge) = self.built.get(&(a, b)) {
+            return edge;
+        }
+        self.nodes.push(Node::And(a, b));
+        let edge = Edge::new(self.nodes.len() - 1, false);
+        self.built.insert((a, b), edge);
+        edge
+    }
+
+    pub fn or(&mut self, a: Edge, b: Edge) -> Edge {
+        !self.and(!a, !b)
+    }
+
+    pub fn xor(&mut self, a: Edge, b: Edge) -> Edge {
+        let (left, right) = (self.and(a, !b), self.and(!a, b));
+        self.or(left, right)
+    }
+
+    /// `then` if `select` is set, `otherwise` if not
+    pub fn mux(&mut self, select: Edge, then: Edge, otherwise: Edge) -> Edge {
+        let (left, right) = (self.and(select, then), self.and(!select, otherwise));
+        self.or(left, right)
+    }
+
+    pub fn output(&mut self, edge: Edge) {
+        debug_assert!(edge.node() < self.nodes.len());
+        self.outputs.push(edge);
+    }
+
+    pub fn nodes(&self) -> &[Node] {
+        &self.nodes
+    }
+
+    pub fn inputs(&self) -> impl Iterator<Item = Edge> + '_ {
+        self.inputs.iter().map(|&node| Edge::new(node, false))
+    }
+
+    pub fn outputs(&self) -> &[Edge] {
+        &self.outputs
+    }
+
+    pub fn and_count(&self) -> usize {
+        self.nodes
+            .iter()
+            .filter(|node| matches!(node, Node::And(..)))
+            .count()
+    }
+
+    /// Number of AND nodes on the path of each node from the inputs
+    fn levels(&self) -> Vec<usize> {
+        let mut levels: Vec<usize> = Vec::with_capacity(self.nodes.len());
+        for node in &self.nodes {
+            levels.push(match node {
+                Node::And(a, b) => levels[a.node()].max(levels[b.node()]) + 1,
+                _ => 0,
+            });
+        }
+        levels
+    }
+
+    /// Most AND nodes on a path from an input to an output
+    pub fn depth(&self) -> usize {
+        let levels = self.levels();
+        self.outputs
+            .iter()
+            .map(|edge| levels[edge.node()])
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Outputs for the given inputs, in any Boolean algebra
+    ///
+    /// Each bit of a `u64` simulates its own input vector.
+    pub fn simulate<A: BooleanAlgebra + Clone>(&self, inputs: &[A]) -> Vec<A> {
+        assert_eq!(inputs.len(), self.inputs.len(), "Wrong number of inputs");
+        let mut values: Vec<A> = Vec::with_capacity(self.nodes.len());
+        let value = |values: &[A], edge: Edge| match edge.is_complemented() {
+            true => values[edge.node()].not(),
+            false => values[edge.node()].clone(),
+        };
+        for node in &self.nodes {
+            values.push(match *node {
+                Node::Constant => A::constant(false),
+                Node::Input(n) => inputs[n].clone(),
+                Node::And(a, b) => value(&values, a).and(&value(&values, b)),
+            });
+        }
+        self.outputs
+            .iter()
+            .map(|&edge| value(&values, edge))
+            .collect()
+    }
+
+    /// Graph computing each expression, input `n` standing for the `n`th
+    /// symbol from `A`
+    pub fn from_expressions<'a>(expressions: impl IntoIterator<Item = &'a Expression>) -> Self {
+        fn build(aig: &mut Aig, expr: &Expression) -> Edge {
+            use Expression::*;
+            let mut binary = |a: &Expression, b: &Expression| (build(aig, a), build(aig, b));
+            match expr {
+                &Var(symbol) => Edge::new(aig.inputs[(symbol - b'A') as usize], false),
+                &Val(value) => Edge::FALSE ^ value,
+                Not(x) => !build(aig, x),
+                And(a, b) => {
+                    let (a, b) = binary(a, b);
+                    aig.and(a, b)
+                }
+                Or(a, b) => {
+                    let (a, b) = binary(a, b);
+                    aig.or(a, b)
+                }
+                Xor(a, b) => {
+                    let (a, b) = binary(a, b);
+                    aig.xor(a, b)
+                }
+                Implies(a, b) => {
+                    let (a, b) = binary(a, b);
+                    !aig.and(a, !b)
+                }
+                Equivalent(a, b) => {
+                    let (a, b) = binary(a, b);
+                    !aig.xor(a, b)
+                }
+            }
+        }
+
+        let expressions: Vec<&Expression> = expressions.into_iter().collect();
+        let last = expressions
+            .iter()
+            .flat_map(|expr| expr.symbols())
+            .max()
+            .map_or(0, |symbol: Symbol| symbol - b'A' + 1);
+        let mut aig = Aig::new();
+        for _ in 0..last {
+            aig.input();
+        }
+        for expr in expressions {
+            let edge = build(&mut aig, expr);
+            aig.output(edge);
+        }
+        aig
+    }
+
+    /// Expression of each output, `None` if there are more inputs than
+    /// symbols
+    pub fn to_expressions(&self) -> Option<Vec<Expression>> {
+        if self.inputs.len() > SYMBOL_RANGE.len() {
+            return None;
+        }
+        let inputs: Vec<Expression> = SYMBOL_RANGE
+            .take(self.inputs.len())
+            .map(Expression::Var)
+            .collect();
+        Some(self.simulate(&inputs))
+    }
+
+    /// Copy of the nodes the outputs depend on, each AND node being rebuilt
+    /// by `and` from the new edges of its operands
+    ///
+    /// Every input is kept so that they are numbered the same.
+    fn rebuild(&self, mut and: impl FnMut(&mut Aig, Edge, Edge) -> Edge) -> Self {
+        let mut live = vec![false; self.nodes.len()];
+        for edge in &self.outputs {
+            live[edge.node()] = true;
+        }
+        for node in (0..self.nodes.len()).rev() {
+            if let (true, Node::And(a, b)) = (live[node], self.nodes[node]) {
+                live[a.node()] = true;
+                live[b.node()] = true;
+            }
+        }
+
+        let mut aig = Aig::new();
+        let mut edges = vec![Edge::FALSE; self.nodes.len()];
+        let edge = |edges: &[Edge], edge: Edge| edges[edge.node()] ^ edge.is_complemented();
+        for &node in &self.inputs {
+            edges[node] = aig.input();
+        }
+        for (node, &gate) in self.nodes.iter().enumerate() {
+            if let (true, Node::And(a, b)) = (live[node], gate) {
+                edges[node] = and(&mut aig, edge(&edges, a), edge(&edges, b));
+            }
+        }
+        for &output in &self.outputs {
+            aig.output(edge(&edges, output));
+        }
+        aig
+    }
+
+    /// Equivalent graph without the nodes no output depends on
+    pub fn cleanup(&self) -> Self {
+        self.rebuild(Aig::and)
+    }
+
+    /// Equivalent graph with each AND node simplified against the AND
+    /// nodes it reads
+    ///
+    /// The rules are those of two-level minimization: contradiction
+    /// `(a & b) & !a = 0`, idempotence `(a & b) & a = a & b`, subsumption
+    /// `!(a & b) & !a = !a`, substitution `!(a & b) & a = a & !b` and
+    /// resolution `!(a & b) & !(a & !b) = !a`.
+    pub fn rewrite(&self) -> Self {
+        self.rebuild(Aig::rewritten_and).cleanup()
+    }
+
+    fn rewritten_and(&mut self, a: Edge, b: Edge) -> Edge {
+        let operands = |aig: &Aig, edge: Edge| match aig.nodes[edge.node()] {
+            Node::And(x, y) => Some([x, y]),
+            _ => None,
+        };
+        for (p, q) in [(a, b), (b, a)] {
+            let Some([x, y]) = operands(self, p) else {
+                continue;
+            };
+            let q_operands = operands(self, q);
+            match p.is_complemented() {
+                false => {
+                    if q == !x || q == !y {
+                        return Edge::FALSE;
+                    }
+                    if q == x || q == y {
+                        return p;
+                    }
+                    if let (false, Some(q_operands)) = (q.is_complemented(), q_operands) {
+                        if q_operands.iter().any(|&u| u == !x || u == !y) {
+                            return Edge::FALSE;
+                        }
+                    }
+                }
+                true => {
+                    if q == !x || q == !y {
+                        return q;
+                    }
+                    if q == x {
+                        return self.rewritten_and(q, !y);
+                    }
+                    if q == y {
+                        return self.rewritten_and(q, !x);
+                    }
+                    match (q.is_complemented(), q_operands) {
+                        (false, Some(q_operands))
+                            if q_operands.iter().any(|&u| u == !x || u == !y) =>
+                        {
+                            return q;
+                        }
+                        (true, Some([u, v])) => {
+                            for (shared, other) in [(x, y), (y, x)] {
+                                if (shared == u && other == !v) || (shared == v && other == !u) {
+                                    return !shared;
+                                }
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+        self.and(a, b)
+    }
+
+    /// Equivalent graph of least depth for its conjunctions: chains of AND
+    /// nodes read through regular edges are rebuilt as balanced trees
+    pub fn balance(&self) -> Self {
+        let mut fanouts = vec![0usize; self.nodes.len()];
+        for node in &self.nodes {
+            if let Node::And(a, b) = node {
+                fanouts[a.node()] += 1;
+                fanouts[b.node()] += 1;
+            }
+        }
+        // Roots of the chains, the other nodes being merged into them
+        let mut roots: Vec<bool> = fanouts.iter().map(|&fanout| fanout != 1).collect();
+        for node in &self.nodes {
+            if let Node::And(a, b) = node {
+                for edge in [a, b] {
+                    roots[edge.node()] |= edge.is_complemented();
+                }
+            }
+        }
+        for edge in &self.outputs {
+            roots[edge.node()] = true;
+        }
+
+        let mut aig = Aig::new();
+        let mut edges = vec![Edge::FALSE; self.nodes.len()];
+        for &node in &self.inputs {
+            edges[node] = aig.input();
+        }
+        let mut levels: Vec<usize> = vec![0; aig.nodes.len()];
+        for node in 0..self.nodes.len() {
+            if !roots[node] || !matches!(self.nodes[node], Node::And(..)) {
+                continue;
+            }
+            let mut leaves = Vec::new();
+            let mut pending = vec![Edge::new(node, false)];
+            while let Some(edge) = pending.pop() {
+                match self.nodes[edge.node()] {
+                    Node::And(a, b) if edge.node() == node || !roots[edge.node()] => {
+                        pending.extend([a, b]);
+                    }
+                    _ => leaves.push(edges[edge.node()] ^ edge.is_complemented()),
+                }
+            }
+            // Pair the two shallowest operands until one is left
+            let mut heap: BinaryHeap<Reverse<(usize, Edge)>> = leaves
+                .into_iter()
+                .map(|edge| Reverse((levels[edge.node()], edge)))
+                .collect();
+            while heap.len() > 1 {
+                let Reverse((_, a)) = heap.pop().unwrap();
+                let Reverse((_, b)) = heap.pop().unwrap();
+                let edge = aig.and(a, b);
+                if edge.node() >= levels.len() {
+                    levels.push(levels[a.node()].max(levels[b.node()]) + 1);
+                }
+                heap.push(Reverse((levels[edge.node()], edge)));
+            }
+            let Reverse((_, edge)) = heap.pop().unwrap();
+            edges[node] = edge;
+        }
+        for &output in &self.outputs {
+            aig.output(edges[output.node()] ^ output.is_complemented());
+        }
+        aig.cleanup()
+    }
+
+    /// Equivalent graph with the nodes proven equal, or opposite, merged
+    ///
+    /// Random simulation sorts nodes into candidate classes, and the SAT
+    /// solver proves each node against the earlier members of its class.
+    pub fn sweep(&self) -> Self {
+        let mut random = XorShift(0x9e37_79b9_7f4a_7c15);
+        let patterns: Vec<[u64; SIMULATION_WORDS]> = self
+            .inputs
+            .iter()
+            .map(|_| core::array::from_fn(|_| random.next()))
+            .collect();
+
+        // Signature of each new node, and the nodes of each signature up to
+        // complement
+        let mut signatures: Vec<[u64; SIMULATION_WORDS]> = Vec::new();
+        let mut classes: HashMap<[u64; SIMULATION_WORDS], Vec<usize>> = HashMap::new();
+        classes.insert([0; SIMULATION_WORDS], vec![0]);
+        self.rebuild(|aig, a, b| {
+            let edge = aig.and(a, b);
+            if edge.node() < signatures.len() {
+                return edge;
+            }
+            while signatures.len() < aig.nodes.len() {
+                let signature = match aig.nodes[signatures.len()] {
+                    Node::Constant => [0; SIMULATION_WORDS],
+                    Node::Input(n) => patterns[n],
+                    Node::And(a, b) => {
+                        let word = |edge: Edge, i: usize| {
+                            signatures[edge.node()][i]
+                                ^ 0u64.wrapping_sub(edge.is_complemented() as u64)
+                        };
+                        core::array::from_fn(|i| word(a, i) & word(b, i))
+                    }
+                };
+                signatures.push(signature);
+            }
+
+            let signature = signatures[edge.node()];
+            let phase = signature[0] & 1 != 0;
+            let class = classes
+                .entry(signature.map(|word| word ^ 0u64.wrapping_sub(phase as u64)))
+                .or_default();
+            for &candidate in class.iter() {
+                let candidate = Edge::new(candidate, false);
+                let other_phase = signatures[candidate.node()][0] & 1 != 0;
+                let candidate = candidate ^ (phase != other_phase);
+                if aig.proves_equal(edge, candidate) {
+                    let Node::And(a, b) = aig.nodes[edge.node()] else {
+                        unreachable!()
+                    };
+                    aig.built.insert((a, b), candidate);
+                    return candidate;
+                }
+            }
+            class.push(edge.node());
+            edge
+        })
+        .cleanup()
+    }
+
+    /// Clauses stating that each node of the cones of `edges` is the AND of
+    /// its operands
+    fn cone_solver(&self, edges: &[Edge]) -> Solver {
+        let mut solver = Solver::new();
+        let mut visited = vec![false; self.nodes.len()];
+        let mut pending: Vec<usize> = edges.iter().map(|edge| edge.node()).collect();
+        while let Some(node) = pending.pop() {
+            if std::mem::replace(&mut visited[node], true) {
+                continue;
+            }
+            let output = Edge::new(node, false).literal();
+            match self.nodes[node] {
+                Node::Constant => solver.add_clause([!output]),
+                Node::Input(_) => {}
+                Node::And(a, b) => {
+                    solver.add_clause([!output, a.literal()]);
+                    solver.add_clause([!output, b.literal()]);
+                    solver.add_clause([output, !a.literal(), !b.literal()]);
+                    pending.extend([a.node(), b.node()]);
+                }
+            }
+        }
+        solver
+    }
+
+    fn proves_equal(&self, a: Edge, b: Edge) -> bool {
+        let mut solver = self.cone_solver(&[a, b]);
+        solver
+            .solve_with_assumptions(&[a.literal(), !b.literal()])
+            .is_none()
+            && solver
+                .solve_with_assumptions(&[!a.literal(), b.literal()])
+                .is_none()
+    }
+
+    /// Graph with a single output, set exactly when some output of `self`
+    /// differs from the same output of `other` for the same inputs
+    ///
+    /// Both graphs share their inputs, the one with fewer having its last
+    /// ones unused.
+    pub fn miter(&self, other: &Aig) -> Aig {
+        assert_eq!(
+            self.outputs.len(),
+            other.outputs.len(),
+            "Different numbers of outputs"
+        );
+        let mut miter = Aig::new();
+        for _ in 0..self.inputs.len().max(other.inputs.len()) {
+            miter.input();
+        }
+        let mut copy = |aig: &Aig| {
+            let mut edges = vec![Edge::FALSE; aig.nodes.len()];
+            let edge = |edges: &[Edge], edge: Edge| edges[edge.node()] ^ edge.is_complemented();
+            for (node, &gate) in aig.nodes.iter().enumerate() {
+                edges[node] = match gate {
+                    Node::Constant => Edge::FALSE,
+                    Node::Input(n) => Edge::new(miter.inputs[n], false),
+                    Node::And(a, b) => miter.and(edge(&edges, a), edge(&edges, b)),
+                };
+            }
+            aig.outputs
+                .iter()
+                .map(|&output| edge(&edges, output))
+                .collect::<Vec<_>>()
+        };
+        let (left, right) = (copy(self), copy(other));
+        let mut differ = Edge::FALSE;
+        for (a, b) in left.into_iter().zip(right) {
+            let xor = miter.xor(a, b);
+            differ = miter.or(differ, xor);
+        }
+        miter.output(differ);
+        miter
+    }
+
+    /// Inputs for which some output differs from the same output of
+    /// `other`, `None` if the graphs are equivalent
+    pub fn counterexample(&self, other: &Aig) -> Option<Vec<bool>> {
+        let miter = self.miter(other).sweep();
+        let differ = miter.outputs[0];
+        if differ == Edge::FALSE {
+            return None;
+        }
+        let model = miter
+            .cone_solver(&[differ])
+            .solve_with_assumptions(&[differ.literal()])?;
+        Some(
+            miter
+                .inputs
+                .iter()
+                .map(|&node| model.get(node).copied().unwrap_or(false))
+                .collect(),
+        )
+    }
+
+    /// Whether both graphs compute the same outputs for every input
+    pub fn is_equivalent(&self, other: &Aig) -> bool {
+        self.counterexample(other).is_none()
+    }
+}
+
+/// Graph of the circuit, with the same inputs and outputs in order
+impl From<&Circuit> for Aig {
+    fn from(circuit: &Circuit) -> Self {
+        let mut aig = Aig::new();
+        let mut edges: Vec<Edge> = Vec::with_capacity(circuit.gates().len());
+        for gate in circuit.gates() {
+            let edge = match *gate {
+                Gate::Input(_) => aig.input(),
+                Gate::Constant(value) => Edge::FALSE ^ value,
+                Gate::Not(a) => !edges[a],
+                Gate::And(a, b) => aig.and(edges[a], edges[b]),
+                Gate::Or(a, b) => aig.or(edges[a], edges[b]),
+                Gate::Xor(a, b) => aig.xor(edges[a], edges[b]),
+                Gate::Mux {
+                    select,
+                    then,
+                    otherwise,
+                } => aig.mux(edges[select], edges[then], edges[otherwise]),
+            };
+            edges.push(edge);
+        }
+        for (_, node) in circuit.outputs() {
+            aig.output(edges[node]);
+        }
+        aig
+    }
+}
+
+/// Graph of a single expression, input `n` standing for the `n`th symbol
+/// from `A`
+impl From<&Expression> for Aig {
+    fn from(expression: &Expression) -> Self {
+        Aig::from_expressions([expression])
+    }
+}
+
+/// Marsaglia's xorshift, enough to spread simulation vectors
+struct XorShift(u64);
+
+impl XorShift {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Aig, Edge};
+    use crate::arithmetic::{symbolic, Adder, Multiplier};
+    use crate::circuit::Circuit;
+    use crate::expression::Expression;
+
+    fn parse(formula: &str) -> Expression {
+        formula.parse().unwrap()
+    }
+
+    /// Whether the graph computes the expression, `A` being its first input
+    fn computes(aig: &Aig, expression: &Expression) -> bool {
+        let inputs = aig.inputs().count();
+        (0..1u32 << inputs).all(|row| {
+            let values: Vec<bool> = (0..inputs).map(|i| row >> i & 1 != 0).collect();
+            let value =
+                expression.interpret(&|symbol| values.get((symbol - b'A') as usize).copied());
+            aig.simulate(&values) == [value.unwrap()]
+        })
+    }
+
+    #[test]
+    fn structural_hashing() {
+        let mut aig = Aig::new();
+        let [a, b] = [aig.input(), aig.input()];
+        let and = aig.and(a, b);
+        assert_eq!(aig.and(b, a), and);
+        assert_eq!(aig.and(a, !a), Edge::FALSE);
+        assert_eq!(aig.and(a, Edge::TRUE), a);
+        assert_eq!(aig.and(a, a), a);
+        assert_eq!(aig.or(!b, !a), !and);
+        assert_eq!(aig.and_count(), 1);
+
+        // Both sides of the XOR are the same node
+        let aig = Aig::from(&parse("AB&BA&^"));
+        assert_eq!(aig.outputs(), [Edge::FALSE]);
+        assert_eq!(Aig::from(&parse("AB^BA^=")).outputs(), [Edge::TRUE]);
+    }
+
+    #[test]
+    fn simulate() {
+        for formula in ["AB&C|", "AB>C=", "A0^", "AD^", "1"] {
+            let expression = parse(formula);
+            let aig = Aig::from(&expression);
+            assert!(computes(&aig, &expression), "{formula}");
+            let back = &aig.to_expressions().unwrap()[0];
+            assert!(computes(&aig, back), "{formula}");
+        }
+
+        let aig = Aig::from(&parse("AB^C^"));
+        let lanes = [0xaa, 0xcc, 0xf0u64];
+        assert_eq!(aig.simulate(&lanes), [0x96]);
+        assert_eq!(aig.and_count(), 6);
+        assert_eq!(aig.depth(), 4);
+    }
+
+    #[test]
+    fn circuit() {
+        let mut circuit = Circuit::new();
+        let [a, b, c] = ["a", "b", "c"].map(|name| circuit.input(name));
+        let half = circuit.xor(a, b);
+        let sum = circuit.xor(half, c);
+        let both = circuit.and(a, b);
+        let carry = circuit.mux(half, c, both);
+        circuit.output("sum", sum);
+        circuit.output("carry", carry);
+
+        let aig = Aig::from(&circuit);
+        for row in 0..8u32 {
+            let inputs: Vec<bool> = (0..3).map(|i| row >> i & 1 != 0).collect();
+            assert_eq!(aig.simulate(&inputs), circuit.simulate(&inputs));
+        }
+        let expected = Aig::from_expressions(&[parse("AB^C^"), parse("AB&AC&|BC&|")]);
+        assert!(aig.is_equivalent(&expected));
+    }
+
+    #[test]
+    fn rewrite() {
+        for (formula, ands) in [
+            ("AB&A!&", 0),
+            ("AB&B!C&&", 0),
+            ("AB&B&", 1),
+            ("AB&!A!&", 0),
+            ("AB&!A&", 1),
+            ("AB&!A!B&&", 1),
+            ("AB&!AB!&!&", 0),
+        ] {
+            let expression = parse(formula);
+            let aig = Aig::from(&expression);
+            let rewritten = aig.rewrite();
+            assert_eq!(rewritten.and_count(), ands, "{formula}");
+            assert!(computes(&rewritten, &expression), "{formula}");
+        }
+    }
+
+    #[test]
+    fn balance() {
+        let chain = parse("AB&C&D&E&F&G&H&");
+        let aig = Aig::from(&chain);
+        assert_eq!(aig.depth(), 7);
+        let balanced = aig.balance();
+        assert_eq!(balanced.depth(), 3);
+        assert_eq!(balanced.and_count(), 7);
+        assert!(computes(&balanced, &chain));
+
+        // Complemented nodes end chains, which are balanced on their own
+        let expression = parse("AB&C&D&!E&F&G&H&");
+        let aig = Aig::from(&expression);
+        let balanced = aig.balance();
+        assert!(balanced.depth() < aig.depth());
+        assert!(computes(&balanced, &expression));
+    }
+
+    #[test]
+    fn sweep() {
+        // XOR as a sum of products, and as a product of sums
+        let formulas = ["AB^", "AB|AB&!&", "AB&A!B!&|!", "AB|AB&!&C&"];
+        let expressions: Vec<Expression> = formulas.iter().map(|f| parse(f)).collect();
+        let aig = Aig::from_expressions(&expressions);
+        let swept = aig.sweep();
+        let outputs = swept.outputs();
+        assert!(outputs[0] == outputs[1] && outputs[1] == outputs[2]);
+        assert_eq!(swept.and_count(), 4);
+        for (i, expression) in expressions.iter().enumerate() {
+            let mut single = swept.clone();
+            single.outputs = vec![outputs[i]];
+            assert!(computes(&single, expression), "{}", formulas[i]);
+        }
+
+        // Carries of two adder architectures are merged
+        let (a, b) = (symbolic::variables(b'A', 6), symbolic::variables(b'G', 6));
+        let ripple = symbolic::add_with(&a, &b, Adder::RippleCarry).value;
+        let prefix = symbolic::add_with(&a, &b, Adder::KoggeStone).value;
+        let aig = Aig::from_expressions(ripple.iter().chain(&prefix));
+        let swept = aig.sweep();
+        assert_eq!(swept.outputs()[..6], swept.outputs()[6..]);
+        assert!(swept.and_count() < aig.and_count());
+    }
+
+    #[test]
+    fn equivalence() {
+        for formula in [
+            "AB=!",
+            "AB>C=D^",
+            "AB|C&D!E>^",
+            "ABCD^^^EF=|",
+            "AB&C|D>E=F!G&|H^",
+        ] {
+            let expression = parse(formula);
+            let aig = Aig::from(&expression);
+            for rewritten in [
+                expression.negation_normal(),
+                expression.conjonctive_normal(),
+            ] {
+                assert!(aig.is_equivalent(&Aig::from(&rewritten)), "{formula}");
+            }
+            assert!(aig.is_equivalent(&aig.rewrite().balance()), "{formula}");
+        }
+
+        let [a, b] = [parse("AB|C&"), parse("AC&B|")];
+        let inputs = Aig::from(&a).counterexample(&Aig::from(&b)).unwrap();
+        let value = |e: &Expression| e.interpret(&|s| inputs.get((s - b'A') as usize).copied());
+        assert_ne!(value(&a), value(&b));
+        assert!(!Aig::from(&parse("A")).is_equivalent(&Aig::from(&parse("B"))));
+
+        // Arithmetic circuits agree across architectures
+        let (a, b) = (symbolic::variables(b'A', 8), symbolic::variables(b'I', 8));
+        let adders = [Adder::RippleCarry, Adder::CarrySelect, Adder::BrentKung]
+            .map(|adder| Aig::from_expressions(&symbolic::add_with(&a, &b, adder).value));
+        assert!(adders[0].is_equivalent(&adders[1]));
+        assert!(adders[0].is_equivalent(&adders[2]));
+        let commuted = Aig::from_expressions(&symbolic::add(&b, &a).value);
+        assert!(adders[0].is_equivalent(&commuted));
+
+        let (a, b) = (symbolic::variables(b'A', 4), symbolic::variables(b'E', 4));
+        let product = |multiplier| {
+            Aig::from_expressions(&symbolic::mul_with(&a, &b, multiplier, Adder::RippleCarry).value)
+        };
+        assert!(product(Multiplier::ShiftAdd).is_equivalent(&product(Multiplier::Dadda)));
+        let wrong = Aig::from_expressions(&symbolic::add(&a, &b).value);
+        assert!(!product(Multiplier::Array).is_equivalent(&wrong));
+    }
+}
@@ -0,0 +1,196 @@
+use crate::expression::Expression;
+
+/// Carrier an [`Expression`] can be interpreted in
+///
+/// Only `and`, `or`, `not` and the constants are required, the other
+/// connectives default to their usual definitions.
+pub trait BooleanAlgebra: Sized {
+    fn constant(value: bool) -> Self;
+
+    fn and(&self, other: &Self) -> Self;
+
+    fn or(&self, other: &Self) -> Self;
+
+    fn not(&self) -> Self;
+
+    fn xor(&self, other: &Self) -> Self {
+        self.and(&other.not()).or(&self.not().and(other))
+    }
+
+    fn implies(&self, other: &Self) -> Self {
+        self.not().or(other)
+    }
+
+    fn equivalent(&self, other: &Self) -> Self {
+        self.xor(other).not()
+    }
+}
+
+impl BooleanAlgebra for bool {
+    fn constant(value: bool) -> Self {
+        value
+    }
+
+    fn and(&self, other: &Self) -> Self {
+        *self && *other
+    }
+
+    fn or(&self, other: &Self) -> Self {
+        *self || *other
+    }
+
+    fn not(&self) -> Self {
+        !*self
+    }
+
+    fn xor(&self, other: &Self) -> Self {
+        *self ^ *other
+    }
+}
+
+/// Bitvectors, one independent Boolean per bit
+macro_rules! impl_bitvector {
+    ($($type:ty),*) => {
+        $(
+            impl BooleanAlgebra for $type {
+                fn constant(value: bool) -> Self {
+                    match value {
+                        true => <$type>::MAX,
+                        false => 0,
+                    }
+                }
+
+                fn and(&self, other: &Self) -> Self {
+                    self & other
+                }
+
+                fn or(&self, other: &Self) -> Self {
+                    self | other
+                }
+
+                fn not(&self) -> Self {
+                    !self
+                }
+
+                fn xor(&self, other: &Self) -> Self {
+                    self ^ other
+                }
+            }
+        )*
+    };
+}
+
+impl_bitvector!(u8, u16, u32, u64, u128);
+
+/// Formulas, building circuits symbolically
+///
+/// Constant operands are folded away, so that gates fed with constants do
+/// not clutter the result.
+impl BooleanAlgebra for Expression {
+    fn constant(value: bool) -> Self {
+        Expression::Val(value)
+    }
+
+    fn and(&self, other: &Self) -> Self {
+        match (self, other) {
+            (Expression::Val(false), _) | (_, Expression::Val(false)) => Expression::Val(false),
+            (Expression::Val(true), x) | (x, Expression::Val(true)) => x.clone(),
+            (a, b) => Expression::and(a.clone(), b.clone()),
+        }
+    }
+
+    fn or(&self, other: &Self) -> Self {
+        match (self, other) {
+            (Expression::Val(true), _) | (_, Expression::Val(true)) => Expression::Val(true),
+            (Expression::Val(false), x) | (x, Expression::Val(false)) => x.clone(),
+            (a, b) => Expression::or(a.clone(), b.clone()),
+        }
+    }
+
+    fn not(&self) -> Self {
+        match self {
+            Expression::Val(value) => Expression::Val(!value),
+            x => Expression::not(x.clone()),
+        }
+    }
+
+    fn xor(&self, other: &Self) -> Self {
+        match (self, other) {
+            (Expression::Val(false), x) | (x, Expression::Val(false)) => x.clone(),
+            (Expression::Val(true), x) | (x, Expression::Val(true)) => x.not(),
+            (a, b) => Expression::xor(a.clone(), b.clone()),
+        }
+    }
+
+    fn implies(&self, other: &Self) -> Self {
+        match (self, other) {
+            (Expression::Val(_), _) | (_, Expression::Val(_)) => self.not().or(other),
+            (a, b) => Expression::implies(a.clone(), b.clone()),
+        }
+    }
+
+    fn equivalent(&self, other: &Self) -> Self {
+        match (self, other) {
+            (Expression::Val(_), _) | (_, Expression::Val(_)) => self.xor(other).not(),
+            (a, b) => Expression::equivalent(a.clone(), b.clone()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::expression::Expression;
+
+    #[test]
+    fn bool() {
+        for formula in ["AB&C|", "AB^!", "AB>C=", "A1&0|"] {
+            let expression: Expression = formula.parse().unwrap();
+            for env in expression.envs() {
+                let value = expression.interpret(&|symbol| env.get(symbol));
+                assert_eq!(value, Some(expression.eval(env)), "{formula}");
+            }
+        }
+        let expression: Expression = "AB&".parse().unwrap();
+        assert_eq!(expression.interpret(&|_| None::<bool>), None);
+    }
+
+    #[test]
+    fn expression() {
+        // Substituting formulas for symbols
+        let expression: Expression = "AB&C>".parse().unwrap();
+        let substitution = |symbol| match symbol {
+            b'A' => "DE|".parse().ok(),
+            b'B' => Some(Expression::Val(true)),
+            _ => Some(Expression::Var(symbol)),
+        };
+        let substituted = expression.interpret(&substitution).unwrap();
+        assert_eq!(substituted.to_string(), "DE|C>");
+
+        for (formula, folded) in [
+            ("A0&B|", "B"),
+            ("A1^", "A!"),
+            ("01>", "1"),
+            ("A0=", "A!"),
+            ("AB=", "AB="),
+        ] {
+            let expression: Expression = formula.parse().unwrap();
+            let value = expression.interpret(&|symbol| Some(Expression::Var(symbol)));
+            assert_eq!(value.unwrap().to_string(), folded, "{formula}");
+        }
+    }
+
+    #[test]
+    fn bitvector() {
+        // Each bit of the carrier evaluates one row of the truth table
+        const COLUMNS: [u8; 3] = [0b11110000, 0b11001100, 0b10101010];
+        for formula in ["AB&C|", "AB^!C|", "AB>C=", "ABC^^"] {
+            let expression: Expression = formula.parse().unwrap();
+            let table = expression
+                .interpret(&|symbol| COLUMNS.get((symbol - b'A') as usize).copied())
+                .unwrap();
+            for (row, env) in expression.envs().enumerate() {
+                assert_eq!(table >> row & 1 != 0, expression.eval(env), "{formula}");
+            }
+        }
+    }
+}
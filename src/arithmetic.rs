@@ -1,32 +1,377 @@
-pub fn adder(a: u32, b: u32) -> u32 {
-    let mut result = 0;
-    let mut c = 0;
-    for i in 0..u32::BITS {
-        let a = (a >> i) & 1;
-        let b = (b >> i) & 1;
-        result |= (a ^ b ^ c) << i;
-        c = (a & b) | (b & c) | (a & c);
+use crate::algebra::BooleanAlgebra;
+
+pub mod architecture;
+pub mod coding;
+pub mod float;
+pub mod gray;
+pub mod symbolic;
+pub mod word;
+
+pub use architecture::{add_with, mul_with, Adder, Cost, Multiplier};
+pub use gray::{gray_decode, gray_encode};
+pub use word::{BitVector, Word};
+
+/// Result of a gate-level operation with its status flags, as `B` bits
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Flagged<W, B = bool> {
+    pub value: W,
+    /// Unsigned overflow: carry out of a sum, borrow of a difference, or
+    /// product not fitting the width
+    pub carry: B,
+    /// Signed overflow, the two's complement result not fitting the width
+    pub overflow: B,
+}
+
+impl Flagged<Vec<bool>> {
+    fn into_word<W: Word>(self) -> Flagged<W> {
+        Flagged {
+            value: W::from_bits(&self.value),
+            carry: self.carry,
+            overflow: self.overflow,
+        }
     }
-    result
 }
 
-pub fn subber(a: u32, b: u32) -> u32 {
-    !adder(!a, b)
+/// Sum and carry out of three bits
+fn full_adder<B: BooleanAlgebra>(a: &B, b: &B, c: &B) -> (B, B) {
+    // Carried when both bits are set, or when one is and so is `c`
+    let half = a.xor(b);
+    let carry = a.and(b).or(&half.and(c));
+    (half.xor(c), carry)
+}
+
+/// Ripple-carry sum of bit vectors of the same width, least significant
+/// bit first, with the carries into and out of the last position
+fn ripple_add<B: BooleanAlgebra + Clone>(a: &[B], b: &[B], mut carry: B) -> (Vec<B>, B, B) {
+    debug_assert_eq!(a.len(), b.len());
+    let mut sum = Vec::with_capacity(a.len());
+    let mut last = carry.clone();
+    for (a, b) in a.iter().zip(b) {
+        let (bit, next) = full_adder(a, b, &carry);
+        sum.push(bit);
+        last = core::mem::replace(&mut carry, next);
+    }
+    (sum, last, carry)
+}
+
+/// Difference of bit vectors as `a + !b + 1`, with the carries into and
+/// out of the last position, the latter being set when nothing is borrowed
+fn ripple_sub<B: BooleanAlgebra + Clone>(a: &[B], b: &[B]) -> (Vec<B>, B, B) {
+    let b: Vec<B> = b.iter().map(B::not).collect();
+    ripple_add(a, &b, B::constant(true))
+}
+
+/// Full double-width product, adding the masked `b` shifted by `i` for
+/// each bit `i` of `a` with the `add` circuit
+fn shift_add_mul<B: BooleanAlgebra + Clone>(
+    a: &[B],
+    b: &[B],
+    add: impl Fn(&[B], &[B], B) -> (Vec<B>, B, B),
+) -> Vec<B> {
+    let width = a.len();
+    let zero = B::constant(false);
+    let mut product = vec![zero.clone(); 2 * width];
+    for (i, a) in a.iter().enumerate() {
+        // Lower bits are final, and higher ones still zero
+        let partial: Vec<B> = b.iter().map(|b| b.and(a)).collect();
+        let (sum, _, carry) = add(&product[i..i + width], &partial, zero.clone());
+        product.splice(i..i + width, sum);
+        product[i + width] = carry;
+    }
+    product
+}
+
+/// Whether any of the bits is set
+fn any<B: BooleanAlgebra>(bits: &[B]) -> B {
+    bits.iter().fold(B::constant(false), |any, bit| any.or(bit))
+}
+
+fn check_widths<W: Word>(a: &W, b: &W) {
+    assert_eq!(a.width(), b.width(), "Operands of different widths");
+}
+
+/// Flags of a sum, from the carries into and out of its last position
+fn sum_flags<B: BooleanAlgebra>((sum, last, carry): (Vec<B>, B, B)) -> Flagged<Vec<B>, B> {
+    Flagged {
+        value: sum,
+        overflow: last.xor(&carry),
+        carry,
+    }
+}
+
+/// Flags of a difference computed as a sum, whose carry out is set when
+/// nothing is borrowed
+fn difference_flags<B: BooleanAlgebra>(sum: (Vec<B>, B, B)) -> Flagged<Vec<B>, B> {
+    let flags = sum_flags(sum);
+    Flagged {
+        carry: flags.carry.not(),
+        ..flags
+    }
+}
+
+/// Ripple-carry sum
+pub fn add<W: Word>(a: W, b: W) -> Flagged<W> {
+    check_widths(&a, &b);
+    sum_flags(ripple_add(&a.to_bits(), &b.to_bits(), false)).into_word()
+}
+
+/// Ripple-carry difference
+pub fn sub<W: Word>(a: W, b: W) -> Flagged<W> {
+    check_widths(&a, &b);
+    difference_flags(ripple_sub(&a.to_bits(), &b.to_bits())).into_word()
 }
 
-pub fn multiplier(mut a: u32, b: u32) -> u32 {
-    let mut result = 0;
-    for i in 0..u32::BITS {
-        if a & 1 == 1 {
-            result = adder(result, b << i)
+/// Flags of the double-width product of `a` and `b`, truncated to their
+/// width
+fn product_flags<B: BooleanAlgebra + Clone>(
+    a: &[B],
+    b: &[B],
+    mut product: Vec<B>,
+) -> Flagged<Vec<B>, B> {
+    let width = a.len();
+    let high = product.split_off(width);
+
+    // The signed high half subtracts each operand when the other is
+    // negative, and must then only repeat the sign of the low half
+    let overflow = match width {
+        0 => B::constant(false),
+        _ => {
+            let mask =
+                |bits: &[B], sign: &B| -> Vec<B> { bits.iter().map(|bit| bit.and(sign)).collect() };
+            let signed = ripple_sub(&high, &mask(b, &a[width - 1])).0;
+            let signed = ripple_sub(&signed, &mask(a, &b[width - 1])).0;
+            let sign = &product[width - 1];
+            any(&signed.iter().map(|bit| bit.xor(sign)).collect::<Vec<_>>())
         }
-        a >>= 1;
+    };
+    Flagged {
+        value: product,
+        carry: any(&high),
+        overflow,
+    }
+}
+
+/// Shift-and-add product, truncated to the width of the operands
+pub fn mul<W: Word>(a: W, b: W) -> Flagged<W> {
+    check_widths(&a, &b);
+    let (a, b) = (a.to_bits(), b.to_bits());
+    product_flags(&a, &b, shift_add_mul(&a, &b, ripple_add)).into_word()
+}
+
+/// `then` if `select` is set, `otherwise` if not
+fn mux<B: BooleanAlgebra>(select: &B, then: &B, otherwise: &B) -> B {
+    select.and(then).or(&select.not().and(otherwise))
+}
+
+/// Restoring division, subtracting `b` from each partial remainder and
+/// keeping the difference only when nothing was borrowed
+fn restoring_div<B: BooleanAlgebra + Clone>(a: &[B], b: &[B]) -> (Vec<B>, Vec<B>) {
+    let width = a.len();
+    let zero = B::constant(false);
+    // One more bit, as shifted remainders reach twice the divisor
+    let mut divisor = b.to_vec();
+    divisor.push(zero.clone());
+    let mut remainder = vec![zero; width + 1];
+    let mut quotient = Vec::with_capacity(width);
+    for bit in a.iter().rev() {
+        remainder.pop();
+        remainder.insert(0, bit.clone());
+        let (difference, _, fits) = ripple_sub(&remainder, &divisor);
+        remainder = (0..=width)
+            .map(|i| mux(&fits, &difference[i], &remainder[i]))
+            .collect();
+        quotient.insert(0, fits);
+    }
+    remainder.pop();
+    (quotient, remainder)
+}
+
+/// Non-restoring division, adding `b` back on the next step rather than
+/// restoring negative partial remainders, and once at the end
+fn nonrestoring_div<B: BooleanAlgebra + Clone>(a: &[B], b: &[B]) -> (Vec<B>, Vec<B>) {
+    let width = a.len();
+    let zero = B::constant(false);
+    // Two more bits for the sign of remainders in `[-2b, 2b[`
+    let mut divisor = b.to_vec();
+    divisor.extend([zero.clone(), zero.clone()]);
+    let inverse: Vec<B> = divisor.iter().map(B::not).collect();
+    let mut remainder = vec![zero.clone(); width + 2];
+    let mut quotient = Vec::with_capacity(width);
+    for bit in a.iter().rev() {
+        let negative = remainder[width + 1].clone();
+        remainder.pop();
+        remainder.insert(0, bit.clone());
+        // Subtract as `+ !b + 1` when positive, add when negative
+        let operand: Vec<B> = (0..width + 2)
+            .map(|i| mux(&negative, &divisor[i], &inverse[i]))
+            .collect();
+        remainder = ripple_add(&remainder, &operand, negative.not()).0;
+        quotient.insert(0, remainder[width + 1].not());
+    }
+    let negative = remainder[width + 1].clone();
+    let correction: Vec<B> = divisor.iter().map(|bit| bit.and(&negative)).collect();
+    let mut remainder = ripple_add(&remainder, &correction, zero).0;
+    remainder.truncate(width);
+    (quotient, remainder)
+}
+
+/// Divider circuit, from the bits of the operands to those of the quotient
+/// and remainder
+type Divider = fn(&[bool], &[bool]) -> (Vec<bool>, Vec<bool>);
+
+fn divide<W: Word>(a: W, b: W, divider: Divider) -> Option<(W, W)> {
+    check_widths(&a, &b);
+    let b = b.to_bits();
+    if !any(&b) {
+        return None;
+    }
+    let (quotient, remainder) = divider(&a.to_bits(), &b);
+    Some((W::from_bits(&quotient), W::from_bits(&remainder)))
+}
+
+/// Quotient and remainder by restoring division, `None` when dividing by
+/// zero
+pub fn div_restoring<W: Word>(a: W, b: W) -> Option<(W, W)> {
+    divide(a, b, restoring_div)
+}
+
+/// Quotient and remainder by non-restoring division, `None` when dividing
+/// by zero
+pub fn div_nonrestoring<W: Word>(a: W, b: W) -> Option<(W, W)> {
+    divide(a, b, nonrestoring_div)
+}
+
+/// Two's complement negation, as `0 - a`
+///
+/// Only the most negative value overflows, and everything but zero borrows.
+pub fn neg<W: Word>(a: W) -> Flagged<W> {
+    let a = a.to_bits();
+    difference_flags(ripple_sub(&vec![false; a.len()], &a)).into_word()
+}
+
+/// Booth's product of two's complement values, truncated to their width
+fn booth_mul<B: BooleanAlgebra + Clone>(a: &[B], b: &[B]) -> Vec<B> {
+    let width = a.len();
+    let zero = B::constant(false);
+    // Accumulator one bit wider than `b`, so that `-b` never overflows
+    let mut multiplicand = b.to_vec();
+    multiplicand.extend(b.last().cloned());
+    let mut accumulator = vec![zero.clone(); width + 1];
+    let mut multiplier = a.to_vec();
+    let mut previous = zero;
+    for _ in 0..width {
+        // Add `b` at the end of a run of ones, subtract it at its start
+        let current = multiplier[0].clone();
+        let enable = current.xor(&previous);
+        let subtract = current.and(&previous.not());
+        let operand: Vec<B> = multiplicand
+            .iter()
+            .map(|bit| bit.and(&enable).xor(&subtract))
+            .collect();
+        accumulator = ripple_add(&accumulator, &operand, subtract).0;
+
+        // Arithmetic shift of the accumulator, multiplier and previous bit
+        previous = multiplier.remove(0);
+        multiplier.push(accumulator.remove(0));
+        accumulator.push(accumulator[width - 1].clone());
+    }
+    multiplier.extend(accumulator);
+    multiplier
+}
+
+/// Flags of a signed product truncated to `width` bits, which fits when
+/// the higher bits only repeat the sign of the lower ones
+fn signed_product_flags<B: BooleanAlgebra + Clone>(
+    mut product: Vec<B>,
+    width: usize,
+) -> Flagged<Vec<B>, B> {
+    let high = product.split_off(width);
+    let overflow = match product.last() {
+        None => B::constant(false),
+        Some(sign) => any(&high.iter().map(|bit| bit.xor(sign)).collect::<Vec<_>>()),
+    };
+    Flagged {
+        value: product,
+        carry: overflow.clone(),
+        overflow,
+    }
+}
+
+/// Signed product by Booth's algorithm, flagging products outside the
+/// two's complement range of the width in both `carry` and `overflow`
+pub fn mul_booth<W: Word>(a: W, b: W) -> Flagged<W> {
+    check_widths(&a, &b);
+    signed_product_flags(booth_mul(&a.to_bits(), &b.to_bits()), a.width()).into_word()
+}
+
+fn shift_left<B: BooleanAlgebra + Clone>(bits: &[B], amount: usize) -> Flagged<Vec<B>, B> {
+    let width = bits.len();
+    let zero = B::constant(false);
+    let value: Vec<B> = (0..width)
+        .map(|i| {
+            i.checked_sub(amount)
+                .map_or(zero.clone(), |j| bits[j].clone())
+        })
+        .collect();
+    let lost = &bits[width.saturating_sub(amount)..];
+    let sign = bits.last().cloned().unwrap_or(zero.clone());
+    // Every bit shifted out and the new sign must repeat the old sign
+    let changed: Vec<B> = lost
+        .iter()
+        .chain(value.last())
+        .map(|bit| bit.xor(&sign))
+        .collect();
+    let carry = match amount {
+        1.. if amount <= width => bits[width - amount].clone(),
+        _ => zero,
+    };
+    Flagged {
+        value,
+        carry,
+        overflow: any(&changed),
+    }
+}
+
+/// Shift towards the most significant bit, `carry` being the last bit
+/// shifted out and `overflow` set when the value does not fit anymore
+pub fn shift_left_arithmetic<W: Word>(a: W, amount: usize) -> Flagged<W> {
+    shift_left(&a.to_bits(), amount).into_word()
+}
+
+fn shift_right<B: BooleanAlgebra + Clone>(bits: &[B], amount: usize) -> Flagged<Vec<B>, B> {
+    let zero = B::constant(false);
+    let sign = bits.last().cloned().unwrap_or(zero.clone());
+    let bit = |i: usize| bits.get(i).cloned().unwrap_or(sign.clone());
+    let value: Vec<B> = (0..bits.len())
+        .map(|i| bit(i.saturating_add(amount)))
+        .collect();
+    Flagged {
+        value,
+        carry: amount.checked_sub(1).map_or(zero.clone(), bit),
+        overflow: zero,
     }
-    result
+}
+
+/// Shift towards the least significant bit, repeating the sign, `carry`
+/// being the last bit shifted out
+pub fn shift_right_arithmetic<W: Word>(a: W, amount: usize) -> Flagged<W> {
+    shift_right(&a.to_bits(), amount).into_word()
+}
+
+pub fn adder(a: u32, b: u32) -> u32 {
+    add(a, b).value
+}
+
+pub fn subber(a: u32, b: u32) -> u32 {
+    sub(a, b).value
+}
+
+pub fn multiplier(a: u32, b: u32) -> u32 {
+    mul(a, b).value
 }
 
 pub fn gray_code(a: u32) -> u32 {
-    a ^ (a >> 1)
+    gray_encode(a)
 }
 
 #[cfg(test)]
@@ -69,6 +414,145 @@ mod tests {
         );
     }
 
+    #[test]
+    fn flags() {
+        use super::{add, mul, sub, Flagged};
+
+        for a in 0..=u8::MAX {
+            for b in 0..=u8::MAX {
+                let (sa, sb) = (a as i8, b as i8);
+                let expected = |(value, carry), overflow: Option<i8>| Flagged {
+                    value,
+                    carry,
+                    overflow: overflow.is_none(),
+                };
+                assert_eq!(
+                    add(a, b),
+                    expected(a.overflowing_add(b), sa.checked_add(sb))
+                );
+                assert_eq!(
+                    sub(a, b),
+                    expected(a.overflowing_sub(b), sa.checked_sub(sb))
+                );
+                assert_eq!(
+                    mul(a, b),
+                    expected(a.overflowing_mul(b), sa.checked_mul(sb))
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn widths() {
+        use super::{add, mul, sub, BitVector};
+
+        let values = [
+            0,
+            1,
+            2,
+            3,
+            u64::MAX as u128,
+            1 << 100,
+            u128::MAX - 1,
+            u128::MAX,
+        ];
+        for a in values {
+            for b in values {
+                assert_eq!(add(a, b).value, a.wrapping_add(b));
+                assert_eq!(add(a, b).carry, a.checked_add(b).is_none());
+                assert_eq!(sub(a, b).value, a.wrapping_sub(b));
+                assert_eq!(mul(a, b).value, a.wrapping_mul(b));
+                assert_eq!(mul(a, b).carry, a.checked_mul(b).is_none());
+                assert_eq!(
+                    add(a as u16, b as u16).value,
+                    (a as u16).wrapping_add(b as u16)
+                );
+                assert_eq!(
+                    mul(a as u64, b as u64).value,
+                    (a as u64).wrapping_mul(b as u64)
+                );
+
+                // Wider vectors hold the full result
+                let wide = |value| BitVector::from_u128(value, 130);
+                let sum = add(wide(a), wide(b)).value;
+                assert_eq!(sum.to_u128(), a.checked_add(b), "{a} + {b}");
+                assert!(!sub(wide(a), wide(b)).carry || a < b);
+            }
+        }
+
+        let max = BitVector::from(vec![true; 200]);
+        let one = BitVector::from_u128(1, 200);
+        let sum = add(max.clone(), one.clone());
+        assert_eq!(sum.value, BitVector::new(200));
+        assert!(sum.carry && !sum.overflow);
+        assert_eq!(mul(max.clone(), max).value, one);
+    }
+
+    #[test]
+    fn division() {
+        use super::{div_nonrestoring, div_restoring};
+
+        for a in 0..=u8::MAX {
+            for b in 0..=u8::MAX {
+                let expected = (b != 0).then(|| (a / b, a % b));
+                assert_eq!(div_restoring(a, b), expected, "{a} / {b}");
+                assert_eq!(div_nonrestoring(a, b), expected, "{a} / {b}");
+            }
+        }
+        let (a, b) = (u128::MAX - 12345, 1 << 70);
+        assert_eq!(div_restoring(a, b), Some((a / b, a % b)));
+        assert_eq!(div_nonrestoring(a, b), Some((a / b, a % b)));
+    }
+
+    #[test]
+    fn signed() {
+        use super::{mul_booth, neg, shift_left_arithmetic, shift_right_arithmetic, Flagged};
+
+        for a in 0..=u8::MAX {
+            let signed = a as i8;
+            let (value, overflow) = signed.overflowing_neg();
+            let expected = Flagged {
+                value: value as u8,
+                carry: a != 0,
+                overflow,
+            };
+            assert_eq!(neg(a), expected);
+
+            for b in 0..=u8::MAX {
+                let (value, overflow) = signed.overflowing_mul(b as i8);
+                let expected = Flagged {
+                    value: value as u8,
+                    carry: overflow,
+                    overflow,
+                };
+                assert_eq!(mul_booth(a, b), expected, "{signed} * {}", b as i8);
+            }
+
+            for amount in 0..10 {
+                let wide = (signed as i32) << amount;
+                let left = shift_left_arithmetic(a, amount);
+                assert_eq!(left.value, (wide as i8) as u8, "{signed} << {amount}");
+                assert_eq!(
+                    left.overflow,
+                    i8::try_from(wide).is_err(),
+                    "{signed} << {amount}"
+                );
+                assert_eq!(left.carry, amount > 0 && wide >> 8 & 1 != 0 && amount <= 8);
+
+                let right = shift_right_arithmetic(a, amount);
+                assert_eq!(
+                    right.value,
+                    (signed >> amount.min(7)) as u8,
+                    "{signed} >> {amount}"
+                );
+                let last = (signed as i32) >> (amount.max(1) - 1) & 1 != 0;
+                assert_eq!(right.carry, amount > 0 && last);
+            }
+        }
+        assert_eq!(mul_booth(u128::MAX, u128::MAX).value, 1);
+        assert!(mul_booth(1u128 << 126, 2).overflow);
+    }
+
     #[test]
     fn gray_code() {
         // Some simple multiplication
@@ -76,7 +560,7 @@ mod tests {
             let ga = super::gray_code(a);
             let gb = super::gray_code(a + 1);
             let diff = ga ^ gb;
-            assert_eq!(diff.count_ones(),  1, "{ga:b} {gb:b}");
+            assert_eq!(diff.count_ones(), 1, "{ga:b} {gb:b}");
         }
 
         // Examples from subject
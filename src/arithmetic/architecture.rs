@@ -0,0 +1,556 @@
+use std::cell::Cell;
+use std::rc::Rc;
+
+use super::{
+    check_widths, full_adder, mux, product_flags, ripple_add, shift_add_mul, sum_flags, Flagged,
+    Word,
+};
+use crate::algebra::BooleanAlgebra;
+
+/// Bits per block of the carry-lookahead and carry-select adders
+const BLOCK: usize = 4;
+
+/// Adder circuits
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Adder {
+    /// Chain of full adders, `O(n)` gates and depth
+    RippleCarry,
+    /// Chain of 4-bit blocks, each computing all its carries at once from
+    /// the generate and propagate signals of its bits
+    CarryLookahead,
+    /// Chain of 4-bit blocks summed for both incoming carries, the right
+    /// sum being selected once the carry is known
+    CarrySelect,
+    /// Parallel prefix of minimal `log n` depth, with `O(n·log n)` gates
+    KoggeStone,
+    /// Parallel prefix of `O(n)` gates, with `2·log n` depth
+    BrentKung,
+}
+
+/// Multiplier circuits, all ending with an [`Adder`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Multiplier {
+    /// Partial products added one after the other
+    ShiftAdd,
+    /// Rows of full adders passing their carries to the next row rather
+    /// than along their own
+    Array,
+    /// Columns of partial product bits reduced by as many full and half
+    /// adders as possible at each stage
+    Wallace,
+    /// Columns reduced by as few adders as possible at each stage, down to
+    /// the heights of Dadda's sequence
+    Dadda,
+}
+
+/// Size of a circuit
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Cost {
+    /// Two-input gates and inverters
+    pub gates: usize,
+    /// Most gates on a path from an input to an output
+    pub depth: usize,
+}
+
+/// Wire of a circuit being built, counting the gates it takes
+///
+/// Constants are folded into the gates they feed, so that circuits are not
+/// charged for adding zeros.
+#[derive(Clone)]
+enum Signal {
+    Constant(bool),
+    Wire {
+        depth: usize,
+        gates: Rc<Cell<usize>>,
+    },
+}
+
+impl Signal {
+    fn depth(&self) -> usize {
+        match self {
+            Self::Constant(_) => 0,
+            Self::Wire { depth, .. } => *depth,
+        }
+    }
+
+    fn gate(&self, other: &Self) -> Self {
+        match (self, other) {
+            (Self::Wire { depth: a, gates }, Self::Wire { depth: b, .. }) => {
+                gates.set(gates.get() + 1);
+                Self::Wire {
+                    depth: a.max(b) + 1,
+                    gates: gates.clone(),
+                }
+            }
+            _ => unreachable!("constants are folded"),
+        }
+    }
+}
+
+impl BooleanAlgebra for Signal {
+    fn constant(value: bool) -> Self {
+        Self::Constant(value)
+    }
+
+    fn and(&self, other: &Self) -> Self {
+        match (self, other) {
+            (Self::Constant(false), _) | (_, Self::Constant(false)) => Self::Constant(false),
+            (Self::Constant(true), x) | (x, Self::Constant(true)) => x.clone(),
+            _ => self.gate(other),
+        }
+    }
+
+    fn or(&self, other: &Self) -> Self {
+        match (self, other) {
+            (Self::Constant(true), _) | (_, Self::Constant(true)) => Self::Constant(true),
+            (Self::Constant(false), x) | (x, Self::Constant(false)) => x.clone(),
+            _ => self.gate(other),
+        }
+    }
+
+    fn not(&self) -> Self {
+        match self {
+            Self::Constant(value) => Self::Constant(!value),
+            _ => self.gate(self),
+        }
+    }
+
+    fn xor(&self, other: &Self) -> Self {
+        match (self, other) {
+            (Self::Constant(false), x) | (x, Self::Constant(false)) => x.clone(),
+            (Self::Constant(true), x) | (x, Self::Constant(true)) => x.not(),
+            _ => self.gate(other),
+        }
+    }
+}
+
+/// Cost of a circuit of two `width`-bit operands
+fn measure(width: usize, circuit: impl FnOnce(&[Signal], &[Signal]) -> Vec<Signal>) -> Cost {
+    let gates = Rc::new(Cell::new(0));
+    let inputs = || -> Vec<Signal> {
+        (0..width)
+            .map(|_| Signal::Wire {
+                depth: 0,
+                gates: gates.clone(),
+            })
+            .collect()
+    };
+    let outputs = circuit(&inputs(), &inputs());
+    Cost {
+        gates: gates.get(),
+        depth: outputs.iter().map(Signal::depth).max().unwrap_or(0),
+    }
+}
+
+/// Balanced tree of a gate over the bits
+fn reduce<B: Clone>(bits: &[B], gate: fn(&B, &B) -> B) -> B {
+    match bits {
+        [bit] => bit.clone(),
+        _ => {
+            let (low, high) = bits.split_at(bits.len() / 2);
+            gate(&reduce(low, gate), &reduce(high, gate))
+        }
+    }
+}
+
+/// Carries of the blocks computed from the generate and propagate signals,
+/// the carry out of bit `i` being generated by some bit `j` and propagated
+/// by all those in between
+fn lookahead_add<B: BooleanAlgebra + Clone>(a: &[B], b: &[B], carry: B) -> (Vec<B>, B, B) {
+    let generate: Vec<B> = a.iter().zip(b).map(|(a, b)| a.and(b)).collect();
+    let propagate: Vec<B> = a.iter().zip(b).map(|(a, b)| a.xor(b)).collect();
+    let mut carries = vec![carry];
+    for start in (0..a.len()).step_by(BLOCK) {
+        let incoming = carries[start].clone();
+        for i in start..(start + BLOCK).min(a.len()) {
+            let mut terms = Vec::with_capacity(i - start + 2);
+            for j in start..=i {
+                let mut term = propagate[j + 1..=i].to_vec();
+                term.push(generate[j].clone());
+                terms.push(reduce(&term, B::and));
+            }
+            let mut term = propagate[start..=i].to_vec();
+            term.push(incoming.clone());
+            terms.push(reduce(&term, B::and));
+            carries.push(reduce(&terms, B::or));
+        }
+    }
+    let sum = propagate
+        .iter()
+        .zip(&carries)
+        .map(|(p, c)| p.xor(c))
+        .collect();
+    let carry = carries.pop().unwrap();
+    let last = carries.pop().unwrap_or_else(|| carry.clone());
+    (sum, last, carry)
+}
+
+/// Blocks after the first summed for both carries, then selected
+fn select_add<B: BooleanAlgebra + Clone>(a: &[B], b: &[B], mut carry: B) -> (Vec<B>, B, B) {
+    let mut sum = Vec::with_capacity(a.len());
+    let mut last = carry.clone();
+    for start in (0..a.len()).step_by(BLOCK) {
+        let block = start..(start + BLOCK).min(a.len());
+        let (a, b) = (&a[block.clone()], &b[block]);
+        if start == 0 {
+            let (bits, into, out) = ripple_add(a, b, carry);
+            sum.extend(bits);
+            (last, carry) = (into, out);
+            continue;
+        }
+        let (zero, one) = (
+            ripple_add(a, b, B::constant(false)),
+            ripple_add(a, b, B::constant(true)),
+        );
+        sum.extend(zero.0.iter().zip(&one.0).map(|(z, o)| mux(&carry, o, z)));
+        last = mux(&carry, &one.1, &zero.1);
+        carry = mux(&carry, &one.2, &zero.2);
+    }
+    (sum, last, carry)
+}
+
+/// Pairs `(i, j)` combining the span ending at bit `i` with the one ending
+/// at bit `j` right below, by level of the prefix network
+type Network = fn(usize) -> Vec<Vec<(usize, usize)>>;
+
+fn kogge_stone(width: usize) -> Vec<Vec<(usize, usize)>> {
+    let distances = (0..).map(|k| 1 << k).take_while(|&d| d < width);
+    distances
+        .map(|d| (d..width).map(|i| (i, i - d)).collect())
+        .collect()
+}
+
+/// Spans doubling up to the powers of two, then filled in between
+fn brent_kung(width: usize) -> Vec<Vec<(usize, usize)>> {
+    let distances: Vec<usize> = (0..)
+        .map(|k| 1 << k)
+        .take_while(|&d| 2 * d - 1 < width)
+        .collect();
+    let up = distances.iter().map(|&d| {
+        (2 * d - 1..width)
+            .step_by(2 * d)
+            .map(|i| (i, i - d))
+            .collect()
+    });
+    let down = distances.iter().rev().map(|&d| {
+        (3 * d - 1..width)
+            .step_by(2 * d)
+            .map(|i| (i, i - d))
+            .collect()
+    });
+    up.chain(down).collect()
+}
+
+/// Carries as the generate signals of the spans from bit 0, grown by the
+/// network from single bits
+fn prefix_add<B: BooleanAlgebra + Clone>(
+    a: &[B],
+    b: &[B],
+    carry: B,
+    network: Network,
+) -> (Vec<B>, B, B) {
+    let propagate: Vec<B> = a.iter().zip(b).map(|(a, b)| a.xor(b)).collect();
+    let mut generate: Vec<B> = a.iter().zip(b).map(|(a, b)| a.and(b)).collect();
+    if let Some(first) = generate.first_mut() {
+        *first = first.or(&propagate[0].and(&carry));
+    }
+    // Spans from bit 0 need no propagate signal anymore
+    let mut spans: Vec<Option<B>> = propagate.iter().cloned().map(Some).collect();
+    if let Some(first) = spans.first_mut() {
+        *first = None;
+    }
+
+    for level in network(a.len()) {
+        let (g, p) = (generate.clone(), spans.clone());
+        for (i, j) in level {
+            let through = p[i].as_ref().expect("span already from bit 0");
+            generate[i] = g[i].or(&through.and(&g[j]));
+            spans[i] = p[j].as_ref().map(|p| through.and(p));
+        }
+    }
+
+    let mut carries = vec![carry];
+    carries.extend(generate);
+    let sum = propagate
+        .iter()
+        .zip(&carries)
+        .map(|(p, c)| p.xor(c))
+        .collect();
+    let carry = carries.pop().unwrap();
+    let last = carries.pop().unwrap_or_else(|| carry.clone());
+    (sum, last, carry)
+}
+
+impl Adder {
+    pub const ALL: [Self; 5] = [
+        Self::RippleCarry,
+        Self::CarryLookahead,
+        Self::CarrySelect,
+        Self::KoggeStone,
+        Self::BrentKung,
+    ];
+
+    /// Sum with the carries into and out of the last position
+    pub(super) fn sum<B: BooleanAlgebra + Clone>(
+        self,
+        a: &[B],
+        b: &[B],
+        carry: B,
+    ) -> (Vec<B>, B, B) {
+        debug_assert_eq!(a.len(), b.len());
+        match self {
+            Self::RippleCarry => ripple_add(a, b, carry),
+            Self::CarryLookahead => lookahead_add(a, b, carry),
+            Self::CarrySelect => select_add(a, b, carry),
+            Self::KoggeStone => prefix_add(a, b, carry, kogge_stone),
+            Self::BrentKung => prefix_add(a, b, carry, brent_kung),
+        }
+    }
+
+    /// Cost of the sum and carry out of `width`-bit operands
+    pub fn cost(self, width: usize) -> Cost {
+        measure(width, |a, b| {
+            let (mut sum, _, carry) = self.sum(a, b, Signal::constant(false));
+            sum.push(carry);
+            sum
+        })
+    }
+}
+
+/// Sum by the given adder
+pub fn add_with<W: Word>(a: W, b: W, adder: Adder) -> Flagged<W> {
+    check_widths(&a, &b);
+    sum_flags(adder.sum(&a.to_bits(), &b.to_bits(), false)).into_word()
+}
+
+/// Sum and carry out of each bit, the latter going to the next position
+fn half_adder<B: BooleanAlgebra>(a: &B, b: &B) -> (B, B) {
+    (a.xor(b), a.and(b))
+}
+
+/// Carry-save rows, positions below the row being final
+fn array_mul<B: BooleanAlgebra + Clone>(a: &[B], b: &[B], adder: Adder) -> Vec<B> {
+    let width = a.len();
+    let zero = B::constant(false);
+    let mut sum = vec![zero.clone(); 2 * width];
+    let mut carries = vec![zero.clone(); 2 * width + 1];
+    for (i, a) in a.iter().enumerate() {
+        let mut next = vec![zero.clone(); 2 * width + 1];
+        for (j, b) in b.iter().enumerate() {
+            let (bit, carry) = full_adder(&sum[i + j], &carries[i + j], &b.and(a));
+            sum[i + j] = bit;
+            next[i + j + 1] = carry;
+        }
+        carries = next;
+    }
+    let (high, _, _) = adder.sum(&sum[width..], &carries[width..2 * width], zero);
+    sum.splice(width.., high);
+    sum
+}
+
+/// Partial product bits by weight
+fn columns<B: BooleanAlgebra + Clone>(a: &[B], b: &[B]) -> Vec<Vec<B>> {
+    let mut columns = vec![Vec::new(); 2 * a.len()];
+    for (i, a) in a.iter().enumerate() {
+        for (j, b) in b.iter().enumerate() {
+            columns[i + j].push(a.and(b));
+        }
+    }
+    columns
+}
+
+/// Final sum of columns of at most two bits
+fn resolve<B: BooleanAlgebra + Clone>(
+    mut columns: Vec<Vec<B>>,
+    width: usize,
+    adder: Adder,
+) -> Vec<B> {
+    // Carries out of the top weight are always zero
+    columns.truncate(2 * width);
+    let zero = B::constant(false);
+    let row = |k: usize| -> Vec<B> {
+        let bit = |column: &Vec<B>| column.get(k).cloned().unwrap_or(zero.clone());
+        columns.iter().map(bit).collect()
+    };
+    adder.sum(&row(0), &row(1), zero.clone()).0
+}
+
+/// Put a bit in a column, adding one above the top weight if needed
+fn deposit<B>(columns: &mut Vec<Vec<B>>, weight: usize, bit: B) {
+    if weight == columns.len() {
+        columns.push(Vec::new());
+    }
+    columns[weight].push(bit);
+}
+
+fn wallace_mul<B: BooleanAlgebra + Clone>(a: &[B], b: &[B], adder: Adder) -> Vec<B> {
+    let mut columns = columns(a, b);
+    while columns.iter().any(|column| column.len() > 2) {
+        let mut next = vec![Vec::new(); columns.len()];
+        for (weight, column) in columns.iter().enumerate() {
+            for bits in column.chunks(3) {
+                let (sum, carry) = match bits {
+                    [a, b, c] => full_adder(a, b, c),
+                    [a, b] => half_adder(a, b),
+                    _ => {
+                        next[weight].push(bits[0].clone());
+                        continue;
+                    }
+                };
+                next[weight].push(sum);
+                deposit(&mut next, weight + 1, carry);
+            }
+        }
+        columns = next;
+    }
+    resolve(columns, a.len(), adder)
+}
+
+fn dadda_mul<B: BooleanAlgebra + Clone>(a: &[B], b: &[B], adder: Adder) -> Vec<B> {
+    let mut columns = columns(a, b);
+    let height = columns.iter().map(Vec::len).max().unwrap_or(0);
+    let targets: Vec<usize> = core::iter::successors(Some(2), |d| Some(d * 3 / 2))
+        .take_while(|&d| d < height)
+        .collect();
+    for &target in targets.iter().rev() {
+        let mut weight = 0;
+        while weight < columns.len() {
+            // Carries from below count towards the height
+            while columns[weight].len() > target {
+                let column = &mut columns[weight];
+                let (sum, carry) = match column.len() - target {
+                    1 => {
+                        let bits: Vec<B> = column.drain(..2).collect();
+                        half_adder(&bits[0], &bits[1])
+                    }
+                    _ => {
+                        let bits: Vec<B> = column.drain(..3).collect();
+                        full_adder(&bits[0], &bits[1], &bits[2])
+                    }
+                };
+                column.push(sum);
+                deposit(&mut columns, weight + 1, carry);
+            }
+            weight += 1;
+        }
+    }
+    resolve(columns, a.len(), adder)
+}
+
+impl Multiplier {
+    pub const ALL: [Self; 4] = [Self::ShiftAdd, Self::Array, Self::Wallace, Self::Dadda];
+
+    /// Full double-width product, summed by `adder`
+    pub(super) fn product<B: BooleanAlgebra + Clone>(
+        self,
+        a: &[B],
+        b: &[B],
+        adder: Adder,
+    ) -> Vec<B> {
+        debug_assert_eq!(a.len(), b.len());
+        match self {
+            Self::ShiftAdd => shift_add_mul(a, b, |a, b, carry| adder.sum(a, b, carry)),
+            Self::Array => array_mul(a, b, adder),
+            Self::Wallace => wallace_mul(a, b, adder),
+            Self::Dadda => dadda_mul(a, b, adder),
+        }
+    }
+
+    /// Cost of the double-width product of `width`-bit operands
+    pub fn cost(self, width: usize, adder: Adder) -> Cost {
+        measure(width, |a, b| self.product(a, b, adder))
+    }
+}
+
+/// Product by the given multiplier and adder, truncated to the width of the
+/// operands
+pub fn mul_with<W: Word>(a: W, b: W, multiplier: Multiplier, adder: Adder) -> Flagged<W> {
+    check_widths(&a, &b);
+    let (a, b) = (a.to_bits(), b.to_bits());
+    product_flags(&a, &b, multiplier.product(&a, &b, adder)).into_word()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{add_with, mul_with, Adder, Cost, Multiplier};
+    use crate::arithmetic::{add, mul, BitVector};
+
+    #[test]
+    fn adders() {
+        for adder in Adder::ALL {
+            for a in 0..=u8::MAX {
+                for b in 0..=u8::MAX {
+                    assert_eq!(add_with(a, b, adder), add(a, b), "{adder:?} {a} + {b}");
+                }
+            }
+            for width in [0, 1, 2, 3, 5, 13, 64, 100] {
+                let a = BitVector::from_u128(u128::MAX / 3, width);
+                let b = BitVector::from_u128(0x1234_5678_9abc_def0_1234_5678_9abc_def0, width);
+                assert_eq!(
+                    add_with(a.clone(), b.clone(), adder),
+                    add(a, b),
+                    "{adder:?} {width}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn multipliers() {
+        for (i, multiplier) in Multiplier::ALL.into_iter().enumerate() {
+            let adder = Adder::ALL[Adder::ALL.len() - 1 - i];
+            for a in 0..=u8::MAX {
+                for b in 0..=u8::MAX {
+                    let product = mul_with(a, b, multiplier, adder);
+                    assert_eq!(product, mul(a, b), "{multiplier:?} {a} * {b}");
+                }
+            }
+            for adder in Adder::ALL {
+                for width in [0, 1, 2, 3, 5, 13, 40] {
+                    let a = BitVector::from_u128(u128::MAX / 3, width);
+                    let b = BitVector::from_u128(0x1234_5678_9abc_def0_1234_5678_9abc_def0, width);
+                    assert_eq!(
+                        mul_with(a.clone(), b.clone(), multiplier, adder),
+                        mul(a, b),
+                        "{multiplier:?} {adder:?} {width}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn costs() {
+        // A half adder then full adders of five gates, each carry two gates
+        // below the previous one
+        assert_eq!(
+            Adder::RippleCarry.cost(8),
+            Cost {
+                gates: 37,
+                depth: 15
+            }
+        );
+        assert_eq!(Adder::RippleCarry.cost(0), Cost { gates: 0, depth: 0 });
+
+        let adders = Adder::ALL.map(|adder| adder.cost(64));
+        let [ripple, lookahead, select, kogge_stone, brent_kung] = adders;
+        for cost in [lookahead, select, kogge_stone, brent_kung] {
+            assert!(cost.depth < ripple.depth, "{cost:?}");
+            assert!(cost.gates > ripple.gates, "{cost:?}");
+        }
+        assert!(adders.iter().all(|cost| cost.depth >= kogge_stone.depth));
+        assert!(brent_kung.gates < kogge_stone.gates);
+        assert!(brent_kung.depth > kogge_stone.depth);
+
+        // Trees only pay off with a fast final adder
+        let cost = |multiplier: Multiplier| multiplier.cost(16, Adder::KoggeStone);
+        let [shift_add, array, wallace, dadda] = Multiplier::ALL.map(cost);
+        assert!(array.depth < shift_add.depth);
+        assert!(wallace.depth < array.depth && dadda.depth < array.depth);
+        assert!(dadda.gates < wallace.gates);
+        assert_eq!(
+            Multiplier::ShiftAdd.cost(16, Adder::RippleCarry).gates,
+            Multiplier::Dadda.cost(16, Adder::RippleCarry).gates
+        );
+    }
+}
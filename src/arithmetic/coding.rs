@@ -0,0 +1,332 @@
+use crate::algebra::BooleanAlgebra;
+
+use super::{BitVector, Word};
+
+/// XOR of the bits, set when an odd number of them are
+fn xor_all<B: BooleanAlgebra>(bits: &[B]) -> B {
+    bits.iter()
+        .fold(B::constant(false), |parity, bit| parity.xor(bit))
+}
+
+/// Even parity bit of the word, set when it has an odd number of ones
+pub fn parity<W: Word>(a: &W) -> bool {
+    xor_all(&a.to_bits())
+}
+
+/// Whether the word and its even parity bit have an even number of ones
+pub fn check_parity<W: Word>(a: &W, parity: bool) -> bool {
+    !xor_all(&a.to_bits()).xor(&parity)
+}
+
+/// Positions of the data bits in a Hamming(7,4) codeword, the others being
+/// the parity bits at the powers of two
+const DATA_POSITIONS: [usize; 4] = [3, 5, 6, 7];
+
+/// Bits at the positions from 1 to 7 having `mask` in common
+fn covered<B: Clone>(code: &[B], mask: usize) -> Vec<B> {
+    (1..=7)
+        .filter(|position| position & mask != 0)
+        .map(|position| code[position - 1].clone())
+        .collect()
+}
+
+/// Hamming(7,4) codeword of four data bits, the parity bit at position `p`
+/// covering the positions with bit `p` set
+fn hamming_bits<B: BooleanAlgebra + Clone>(data: &[B]) -> Vec<B> {
+    let mut code = vec![B::constant(false); 7];
+    for (bit, &position) in data.iter().zip(&DATA_POSITIONS) {
+        code[position - 1] = bit.clone();
+    }
+    for parity in [1, 2, 4] {
+        code[parity - 1] = xor_all(&covered(&code, parity));
+    }
+    code
+}
+
+/// Position of a single flipped bit of a Hamming(7,4) codeword, zero if
+/// none, from its least significant bit
+fn syndrome_bits<B: BooleanAlgebra + Clone>(code: &[B]) -> Vec<B> {
+    [1, 2, 4]
+        .map(|parity| xor_all(&covered(code, parity)))
+        .to_vec()
+}
+
+/// Codeword with the bit at the syndrome's position flipped, each position
+/// decoding the syndrome on its own
+fn correct_bits<B: BooleanAlgebra + Clone>(code: &[B], syndrome: &[B]) -> Vec<B> {
+    (1..=7)
+        .map(|position: usize| {
+            let selected =
+                syndrome
+                    .iter()
+                    .enumerate()
+                    .fold(B::constant(true), |selected, (i, bit)| {
+                        match position >> i & 1 != 0 {
+                            true => selected.and(bit),
+                            false => selected.and(&bit.not()),
+                        }
+                    });
+            code[position - 1].xor(&selected)
+        })
+        .collect()
+}
+
+fn low_bits(a: u8, width: usize) -> Vec<bool> {
+    (0..width).map(|i| a >> i & 1 != 0).collect()
+}
+
+fn from_low_bits(bits: &[bool]) -> u8 {
+    bits.iter().rev().fold(0, |a, &bit| a << 1 | bit as u8)
+}
+
+/// Data recovered from a codeword
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Decoded {
+    Valid(u8),
+    /// Data of the codeword with its `bit`th bit, from the least
+    /// significant one, flipped back
+    Corrected {
+        data: u8,
+        bit: u32,
+    },
+    /// Two flipped bits, detected but not correctable
+    Uncorrectable,
+}
+
+/// Hamming(7,4) codeword of the low nibble, bit `i` being position `i + 1`
+pub fn hamming_encode(data: u8) -> u8 {
+    assert!(data < 1 << 4, "Data wider than a nibble");
+    from_low_bits(&hamming_bits(&low_bits(data, 4)))
+}
+
+/// Data of a Hamming(7,4) codeword, correcting a single flipped bit
+///
+/// Two flipped bits are miscorrected into another codeword.
+pub fn hamming_decode(code: u8) -> Decoded {
+    assert!(code < 1 << 7, "Codeword wider than 7 bits");
+    let bits = low_bits(code, 7);
+    let syndrome = syndrome_bits(&bits);
+    let corrected = correct_bits(&bits, &syndrome);
+    let data = from_low_bits(&DATA_POSITIONS.map(|position| corrected[position - 1]));
+    match from_low_bits(&syndrome) {
+        0 => Decoded::Valid(data),
+        position => Decoded::Corrected {
+            data,
+            bit: position as u32 - 1,
+        },
+    }
+}
+
+/// Single error correcting, double error detecting codeword of the low
+/// nibble: its Hamming(7,4) codeword with an overall parity bit on top
+pub fn secded_encode(data: u8) -> u8 {
+    let code = hamming_encode(data);
+    code | (parity(&code) as u8) << 7
+}
+
+/// Data of a SECDED codeword, correcting a single flipped bit and
+/// detecting two
+pub fn secded_decode(code: u8) -> Decoded {
+    let odd = parity(&code);
+    match (hamming_decode(code & 0x7f), odd) {
+        (decoded @ Decoded::Valid(_), false) => decoded,
+        // The overall parity bit itself flipped
+        (Decoded::Valid(data), true) => Decoded::Corrected { data, bit: 7 },
+        (decoded @ Decoded::Corrected { .. }, true) => decoded,
+        (_, false) => Decoded::Uncorrectable,
+        (Decoded::Uncorrectable, true) => unreachable!(),
+    }
+}
+
+/// Register of a CRC after shifting in a message bit, the bit shifted out
+/// XOR the message bit feeding back into the taps of the polynomial
+fn crc_step<B: BooleanAlgebra + Clone>(register: &[B], taps: &[bool], bit: &B) -> Vec<B> {
+    let feedback = register.last().unwrap().xor(bit);
+    (0..register.len())
+        .map(|i| {
+            let shifted = match i {
+                0 => B::constant(false),
+                _ => register[i - 1].clone(),
+            };
+            match taps[i] {
+                true => shifted.xor(&feedback),
+                false => shifted,
+            }
+        })
+        .collect()
+}
+
+/// Cyclic redundancy check, with the parameters of the Rocksoft model
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Crc {
+    /// Degree of the polynomial, from 1 to 64
+    pub width: u32,
+    /// Polynomial without its leading term
+    pub polynomial: u64,
+    pub init: u64,
+    /// Whether bytes enter from their least significant bit
+    pub reflect_in: bool,
+    /// Whether the register is reversed before the final XOR
+    pub reflect_out: bool,
+    pub xor_out: u64,
+}
+
+impl Crc {
+    /// CRC-8/SMBUS
+    pub const CRC8: Crc = Crc {
+        width: 8,
+        polynomial: 0x07,
+        init: 0,
+        reflect_in: false,
+        reflect_out: false,
+        xor_out: 0,
+    };
+
+    /// CRC-16/ARC
+    pub const CRC16: Crc = Crc {
+        width: 16,
+        polynomial: 0x8005,
+        init: 0,
+        reflect_in: true,
+        reflect_out: true,
+        xor_out: 0,
+    };
+
+    /// CRC-16/IBM-3740, also known as CRC-16/CCITT-FALSE
+    pub const CRC16_CCITT: Crc = Crc {
+        width: 16,
+        polynomial: 0x1021,
+        init: 0xffff,
+        reflect_in: false,
+        reflect_out: false,
+        xor_out: 0,
+    };
+
+    /// CRC-32/ISO-HDLC, the CRC of Ethernet and zip
+    pub const CRC32: Crc = Crc {
+        width: 32,
+        polynomial: 0x04c1_1db7,
+        init: 0xffff_ffff,
+        reflect_in: true,
+        reflect_out: true,
+        xor_out: 0xffff_ffff,
+    };
+
+    pub fn checksum(&self, data: &[u8]) -> u64 {
+        assert!((1..=64).contains(&self.width), "CRC width out of range");
+        let width = self.width as usize;
+        let bits = |value: u64| BitVector::from_u128(value as u128, width).to_bits();
+        let taps = bits(self.polynomial);
+        let mut register = bits(self.init);
+        for byte in data {
+            for i in 0..8 {
+                let bit = match self.reflect_in {
+                    true => byte >> i & 1 != 0,
+                    false => byte >> (7 - i) & 1 != 0,
+                };
+                register = crc_step(&register, &taps, &bit);
+            }
+        }
+        if self.reflect_out {
+            register.reverse();
+        }
+        let value = BitVector::from(register).to_u128().unwrap() as u64;
+        value ^ self.xor_out & u64::MAX >> (64 - width)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Crc, Decoded};
+
+    #[test]
+    fn parity() {
+        for a in 0..=u16::MAX {
+            let parity = super::parity(&a);
+            assert_eq!(parity, a.count_ones() % 2 == 1);
+            assert!(super::check_parity(&a, parity));
+            assert!(!super::check_parity(&(a ^ 1 << (a % 16)), parity));
+        }
+    }
+
+    #[test]
+    fn hamming() {
+        let codes: Vec<u8> = (0..16).map(super::hamming_encode).collect();
+        for (data, &code) in codes.iter().enumerate() {
+            assert_eq!(super::hamming_decode(code), Decoded::Valid(data as u8));
+            for bit in 0..7 {
+                let decoded = super::hamming_decode(code ^ 1 << bit);
+                assert_eq!(
+                    decoded,
+                    Decoded::Corrected {
+                        data: data as u8,
+                        bit
+                    }
+                );
+            }
+            for &other in &codes[..data] {
+                assert!((code ^ other).count_ones() >= 3);
+            }
+        }
+        // Parity bits at positions 1, 2 and 4
+        assert_eq!(super::hamming_encode(0b1011), 0b1010101);
+    }
+
+    #[test]
+    fn secded() {
+        for data in 0..16 {
+            let code = super::secded_encode(data);
+            assert_eq!(code & 0x7f, super::hamming_encode(data));
+            assert_eq!(code.count_ones() % 2, 0);
+            assert_eq!(super::secded_decode(code), Decoded::Valid(data));
+            for bit in 0..8 {
+                let decoded = super::secded_decode(code ^ 1 << bit);
+                assert_eq!(decoded, Decoded::Corrected { data, bit });
+                for other in 0..bit {
+                    let decoded = super::secded_decode(code ^ 1 << bit ^ 1 << other);
+                    assert_eq!(decoded, Decoded::Uncorrectable);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn crc() {
+        let check = b"123456789";
+        assert_eq!(Crc::CRC8.checksum(check), 0xf4);
+        assert_eq!(Crc::CRC16.checksum(check), 0xbb3d);
+        assert_eq!(Crc::CRC16_CCITT.checksum(check), 0x29b1);
+        assert_eq!(Crc::CRC32.checksum(check), 0xcbf4_3926);
+        assert_eq!(Crc::CRC32.checksum(b""), 0);
+        let crc32c = Crc {
+            polynomial: 0x1edc_6f41,
+            ..Crc::CRC32
+        };
+        assert_eq!(crc32c.checksum(check), 0xe306_9283);
+        let crc3 = Crc {
+            width: 3,
+            polynomial: 0x3,
+            xor_out: 0x7,
+            ..Crc::CRC8
+        };
+        assert_eq!(crc3.checksum(check), 0x4);
+
+        // Messages followed by their CRC leave no remainder, and flipping
+        // one or two of their bits always does
+        for message in 0..=u16::MAX {
+            let [high, low] = message.to_be_bytes();
+            let checksum = Crc::CRC8.checksum(&[high, low]) as u8;
+            assert_eq!(Crc::CRC8.checksum(&[high, low, checksum]), 0);
+            if message % 4099 == 0 {
+                let word = u32::from_be_bytes([0, high, low, checksum]);
+                for bit in 0..24 {
+                    for other in 0..=bit {
+                        let flipped = (word ^ 1 << bit ^ 1 << other).to_be_bytes();
+                        let remainder = Crc::CRC8.checksum(&flipped[1..]);
+                        assert_eq!(remainder == 0, bit == other, "{message} {bit} {other}");
+                    }
+                }
+            }
+        }
+    }
+}
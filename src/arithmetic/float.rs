@@ -0,0 +1,571 @@
+use core::cmp::Ordering;
+use core::ops::Neg;
+
+use super::{shift_left_arithmetic, shift_right_arithmetic};
+
+/// How results between two representable numbers are chosen
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Rounding {
+    /// Nearest, ties to the even significand, like native floats
+    #[default]
+    NearestEven,
+    /// Nearest, ties away from zero
+    NearestAway,
+    TowardZero,
+    TowardPositive,
+    TowardNegative,
+}
+
+const SIGN: u32 = 1 << 31;
+const FRACTION_BITS: usize = 23;
+const BIAS: i32 = 127;
+/// Biased exponent of infinities and NaNs
+const SPECIAL: i32 = 0xff;
+/// Bit of the leading one of unpacked significands, leaving the sum of two
+/// below the top bit so that shifts stay logical
+const LEADING: usize = 61;
+/// Bits below the 24 a significand keeps, deciding how it rounds
+const ROUNDING_BITS: usize = LEADING - FRACTION_BITS;
+
+/// IEEE-754 binary32 number, computed on with the gate-level integer
+/// operations only
+///
+/// Equality is that of the bits, `compare` being the IEEE-754 one.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Binary32(u32);
+
+enum Class {
+    Nan,
+    Infinite,
+    Zero,
+    /// `significand * 2^(exponent - BIAS - LEADING)`, with the leading one
+    /// of the significand at `LEADING`
+    Finite {
+        exponent: i32,
+        significand: u64,
+    },
+}
+
+fn plus(a: i32, b: i32) -> i32 {
+    super::add(a as u32, b as u32).value as i32
+}
+
+fn minus(a: i32, b: i32) -> i32 {
+    super::sub(a as u32, b as u32).value as i32
+}
+
+/// Whether `a < b`, the difference being negative unless it overflowed
+fn less(a: i32, b: i32) -> bool {
+    let difference = super::sub(a as u32, b as u32);
+    (difference.value & SIGN != 0) != difference.overflow
+}
+
+/// Whether `a < b`, the difference borrowing
+fn below(a: u64, b: u64) -> bool {
+    super::sub(a, b).carry
+}
+
+/// `a` shifted right with its lowest bit set if a set bit was shifted out,
+/// so that rounding still sees it
+fn shift_right_jamming(a: u64, amount: usize) -> u64 {
+    let shifted = shift_right_arithmetic(a, amount).value;
+    let lost = shift_left_arithmetic(shifted, amount).value != a;
+    shifted | lost as u64
+}
+
+/// Significand shifted left until its leading one is at `LEADING`, with
+/// the exponent lowered to match
+fn normalize(exponent: i32, significand: u64) -> (i32, u64) {
+    // Priority encoder of the leading one
+    let shift = (0..=LEADING)
+        .rev()
+        .take_while(|&i| significand >> i & 1 == 0)
+        .count();
+    (
+        minus(exponent, shift as i32),
+        shift_left_arithmetic(significand, shift).value,
+    )
+}
+
+impl Binary32 {
+    pub const NAN: Binary32 = Binary32(0x7fc0_0000);
+    pub const INFINITY: Binary32 = Binary32(0x7f80_0000);
+    pub const MAX: Binary32 = Binary32(0x7f7f_ffff);
+
+    pub const fn from_bits(bits: u32) -> Self {
+        Self(bits)
+    }
+
+    pub const fn to_bits(self) -> u32 {
+        self.0
+    }
+
+    pub fn is_sign_negative(self) -> bool {
+        self.0 & SIGN != 0
+    }
+
+    pub fn is_nan(self) -> bool {
+        matches!(self.class(), Class::Nan)
+    }
+
+    fn with_sign(self, negative: bool) -> Self {
+        Self(self.0 & !SIGN | (negative as u32) << 31)
+    }
+
+    /// NaN of the same payload, signaling NaNs being quieted
+    fn quiet(self) -> Self {
+        Self(self.0 | 1 << (FRACTION_BITS - 1))
+    }
+
+    fn class(self) -> Class {
+        let exponent = (self.0 >> FRACTION_BITS & 0xff) as i32;
+        let fraction = (self.0 & ((1 << FRACTION_BITS) - 1)) as u64;
+        match (exponent, fraction) {
+            (SPECIAL, 0) => Class::Infinite,
+            (SPECIAL, _) => Class::Nan,
+            (0, 0) => Class::Zero,
+            // Subnormals have no hidden bit, and the exponent of the
+            // smallest normals
+            (0, _) => {
+                let (exponent, significand) = normalize(1, fraction << ROUNDING_BITS);
+                Class::Finite {
+                    exponent,
+                    significand,
+                }
+            }
+            _ => Class::Finite {
+                exponent,
+                significand: (fraction | 1 << FRACTION_BITS) << ROUNDING_BITS,
+            },
+        }
+    }
+
+    /// Largest finite number or infinity, whichever the rounding picks
+    fn overflow(negative: bool, rounding: Rounding) -> Self {
+        let infinite = match rounding {
+            Rounding::NearestEven | Rounding::NearestAway => true,
+            Rounding::TowardZero => false,
+            Rounding::TowardPositive => !negative,
+            Rounding::TowardNegative => negative,
+        };
+        match infinite {
+            true => Self::INFINITY,
+            false => Self::MAX,
+        }
+        .with_sign(negative)
+    }
+
+    /// Representable number nearest to a finite one, in the direction of
+    /// the rounding
+    fn round(negative: bool, exponent: i32, significand: u64, rounding: Rounding) -> Self {
+        // Below the normal range, significands lose bits to keep the
+        // exponent of the smallest normals
+        let (exponent, significand) = match less(exponent, 1) {
+            true => (
+                1,
+                shift_right_jamming(significand, minus(1, exponent) as usize),
+            ),
+            false => (exponent, significand),
+        };
+        if less(SPECIAL - 1, exponent) {
+            return Self::overflow(negative, rounding);
+        }
+        let kept = significand >> ROUNDING_BITS;
+        let rest = significand & ((1 << ROUNDING_BITS) - 1);
+        let half = 1 << (ROUNDING_BITS - 1);
+        let up = match rounding {
+            Rounding::NearestEven => below(half, rest) || (rest == half && kept & 1 != 0),
+            Rounding::NearestAway => !below(rest, half),
+            Rounding::TowardZero => false,
+            Rounding::TowardPositive => rest != 0 && !negative,
+            Rounding::TowardNegative => rest != 0 && negative,
+        };
+        // The hidden bit adds into the exponent field, so rounding up past
+        // the significand bumps the exponent, and subnormals become normal
+        let biased = (minus(exponent, 1) as u32) << FRACTION_BITS;
+        let magnitude = super::add(biased, kept as u32).value;
+        let magnitude = super::add(magnitude, up as u32).value;
+        if !super::sub(magnitude, Self::INFINITY.0).carry {
+            return Self::overflow(negative, rounding);
+        }
+        Self(magnitude).with_sign(negative)
+    }
+
+    fn zero(negative: bool) -> Self {
+        Self(0).with_sign(negative)
+    }
+
+    pub fn add(self, other: Self, rounding: Rounding) -> Self {
+        use Class::*;
+        let (a_negative, b_negative) = (self.is_sign_negative(), other.is_sign_negative());
+        // Exact zeros are positive unless rounding down
+        let cancelled = Self::zero(rounding == Rounding::TowardNegative);
+        match (self.class(), other.class()) {
+            (Nan, _) => self.quiet(),
+            (_, Nan) => other.quiet(),
+            (Infinite, Infinite) if a_negative != b_negative => Self::NAN,
+            (Infinite, _) => self,
+            (_, Infinite) => other,
+            (Zero, Zero) if a_negative == b_negative => self,
+            (Zero, Zero) => cancelled,
+            (Zero, _) => other,
+            (_, Zero) => self,
+            (
+                Finite {
+                    exponent: a_exponent,
+                    significand: a_significand,
+                },
+                Finite {
+                    exponent: b_exponent,
+                    significand: b_significand,
+                },
+            ) => {
+                // Align the smaller magnitude on the larger one
+                let a_smaller = less(a_exponent, b_exponent)
+                    || (a_exponent == b_exponent && below(a_significand, b_significand));
+                let ((negative, exponent, large), (small_exponent, small)) = match a_smaller {
+                    true => (
+                        (b_negative, b_exponent, b_significand),
+                        (a_exponent, a_significand),
+                    ),
+                    false => (
+                        (a_negative, a_exponent, a_significand),
+                        (b_exponent, b_significand),
+                    ),
+                };
+                let small = shift_right_jamming(small, minus(exponent, small_exponent) as usize);
+
+                if a_negative == b_negative {
+                    let sum = super::add(large, small).value;
+                    match sum >> (LEADING + 1) & 1 {
+                        0 => Self::round(negative, exponent, sum, rounding),
+                        _ => Self::round(
+                            negative,
+                            plus(exponent, 1),
+                            shift_right_jamming(sum, 1),
+                            rounding,
+                        ),
+                    }
+                } else {
+                    let difference = super::sub(large, small).value;
+                    if difference == 0 {
+                        return cancelled;
+                    }
+                    let (exponent, significand) = normalize(exponent, difference);
+                    Self::round(negative, exponent, significand, rounding)
+                }
+            }
+        }
+    }
+
+    pub fn sub(self, other: Self, rounding: Rounding) -> Self {
+        match other.is_nan() {
+            true => self.add(other, rounding),
+            false => self.add(-other, rounding),
+        }
+    }
+
+    pub fn mul(self, other: Self, rounding: Rounding) -> Self {
+        use Class::*;
+        let negative = self.is_sign_negative() != other.is_sign_negative();
+        match (self.class(), other.class()) {
+            (Nan, _) => self.quiet(),
+            (_, Nan) => other.quiet(),
+            (Infinite, Zero) | (Zero, Infinite) => Self::NAN,
+            (Infinite, _) | (_, Infinite) => Self::INFINITY.with_sign(negative),
+            (Zero, _) | (_, Zero) => Self::zero(negative),
+            (
+                Finite {
+                    exponent: a_exponent,
+                    significand: a_significand,
+                },
+                Finite {
+                    exponent: b_exponent,
+                    significand: b_significand,
+                },
+            ) => {
+                // 24-bit significands make a product of 47 or 48 bits, its
+                // leading one brought to `LEADING` or just below
+                let product = super::mul(
+                    a_significand >> ROUNDING_BITS,
+                    b_significand >> ROUNDING_BITS,
+                )
+                .value;
+                let product = shift_left_arithmetic(product, LEADING - 2 * FRACTION_BITS - 1).value;
+                let exponent = minus(plus(a_exponent, b_exponent), BIAS - 1);
+                let (exponent, significand) = normalize(exponent, product);
+                Self::round(negative, exponent, significand, rounding)
+            }
+        }
+    }
+
+    /// IEEE-754 comparison: NaNs are unordered and both zeros are equal
+    pub fn compare(self, other: Self) -> Option<Ordering> {
+        if self.is_nan() || other.is_nan() {
+            return None;
+        }
+        let (a, b) = (self.0 & !SIGN, other.0 & !SIGN);
+        if a == 0 && b == 0 {
+            return Some(Ordering::Equal);
+        }
+        Some(match (self.is_sign_negative(), other.is_sign_negative()) {
+            (false, true) => Ordering::Greater,
+            (true, false) => Ordering::Less,
+            (negative, _) => {
+                let magnitude = match (a == b, super::sub(a, b).carry) {
+                    (true, _) => Ordering::Equal,
+                    (false, true) => Ordering::Less,
+                    (false, false) => Ordering::Greater,
+                };
+                match negative {
+                    true => magnitude.reverse(),
+                    false => magnitude,
+                }
+            }
+        })
+    }
+}
+
+impl Neg for Binary32 {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self(self.0 ^ SIGN)
+    }
+}
+
+impl From<f32> for Binary32 {
+    fn from(value: f32) -> Self {
+        Self(value.to_bits())
+    }
+}
+
+impl From<Binary32> for f32 {
+    fn from(value: Binary32) -> Self {
+        f32::from_bits(value.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::cmp::Ordering;
+
+    use super::{Binary32, Rounding};
+
+    const MODES: [Rounding; 5] = [
+        Rounding::NearestEven,
+        Rounding::NearestAway,
+        Rounding::TowardZero,
+        Rounding::TowardPositive,
+        Rounding::TowardNegative,
+    ];
+
+    /// Marsaglia's xorshift
+    struct XorShift(u64);
+
+    impl XorShift {
+        fn next(&mut self) -> u32 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            (self.0 >> 32) as u32
+        }
+    }
+
+    /// Operands with special values, random bits, and nearby magnitudes
+    /// that cancel out
+    fn operands(count: usize) -> Vec<(f32, f32)> {
+        let specials = [
+            0.0,
+            -0.0,
+            1.0,
+            -1.5,
+            f32::MIN_POSITIVE,
+            -f32::MIN_POSITIVE,
+            f32::from_bits(1),
+            f32::from_bits(0x7f_ffff),
+            f32::MAX,
+            -f32::MAX,
+            f32::INFINITY,
+            f32::NEG_INFINITY,
+            f32::NAN,
+            1e-20,
+            3e20,
+        ];
+        let mut operands = Vec::new();
+        for &a in &specials {
+            for &b in &specials {
+                operands.push((a, b));
+            }
+        }
+        let mut random = XorShift(0x2545_f491_4f6c_dd1d);
+        for i in 0..count {
+            let a = random.next();
+            let b = match i % 3 {
+                0 => random.next(),
+                1 => a ^ random.next() >> 9 ^ (random.next() & 1) << 31,
+                _ => a & 0xff80_0000 | random.next() & 0x807f_ffff,
+            };
+            operands.push((f32::from_bits(a), f32::from_bits(b)));
+        }
+        operands
+    }
+
+    /// Ordering of `x` against the exact `s + e`, `e` being far below the
+    /// last bit of `s`
+    fn against(x: f32, s: f64, e: f64) -> Ordering {
+        let x = match x.is_infinite() {
+            true => 2f64.powi(128).copysign(x as f64),
+            false => x as f64,
+        };
+        // Exact when `x` and `s` are close, and far from `e` otherwise
+        (x - s).partial_cmp(&e).unwrap()
+    }
+
+    /// Float of the exact nonzero `s + e` in the direction of the rounding
+    fn round(s: f64, e: f64, rounding: Rounding) -> f32 {
+        let mut low = s as f32;
+        if against(low, s, e) == Ordering::Greater {
+            low = low.next_down();
+        }
+        // Past the largest float, still below infinity
+        low = low.min(f32::MAX);
+        if against(low, s, e) == Ordering::Equal {
+            return low;
+        }
+        let high = low.next_up();
+        let positive = s > 0.0;
+        match rounding {
+            Rounding::TowardZero if positive => low,
+            Rounding::TowardZero => high,
+            Rounding::TowardPositive => high,
+            Rounding::TowardNegative => low,
+            Rounding::NearestEven | Rounding::NearestAway => {
+                let wide = |x: f32| match x.is_infinite() {
+                    true => 2f64.powi(128).copysign(x as f64),
+                    false => x as f64,
+                };
+                // Sign of the distance to `low` minus the distance to
+                // `high`, each part being exact
+                let difference = (s - wide(low)) - (wide(high) - s);
+                match difference.partial_cmp(&(-2.0 * e)).unwrap() {
+                    Ordering::Less => low,
+                    Ordering::Greater => high,
+                    Ordering::Equal if rounding == Rounding::NearestAway => match positive {
+                        true => high,
+                        false => low,
+                    },
+                    // Infinities count as even, past the odd largest float
+                    Ordering::Equal => match low.is_infinite() || low.to_bits() & 1 == 0 {
+                        true => low,
+                        false => high,
+                    },
+                }
+            }
+        }
+    }
+
+    /// Double nearest to the sum of two floats, and its exact error
+    fn two_sum(a: f32, b: f32) -> (f64, f64) {
+        let (a, b) = (a as f64, b as f64);
+        let s = a + b;
+        let v = s - a;
+        (s, (a - (s - v)) + (b - v))
+    }
+
+    fn same(result: Binary32, expected: f32) -> bool {
+        match expected.is_nan() {
+            true => result.is_nan(),
+            false => result.to_bits() == expected.to_bits(),
+        }
+    }
+
+    #[test]
+    fn native() {
+        for (a, b) in operands(3000) {
+            let (x, y) = (Binary32::from(a), Binary32::from(b));
+            let mode = Rounding::NearestEven;
+            assert!(same(x.add(y, mode), a + b), "{a:e} + {b:e}");
+            assert!(same(x.sub(y, mode), a - b), "{a:e} - {b:e}");
+            assert!(same(x.mul(y, mode), a * b), "{a:e} * {b:e}");
+            assert_eq!(x.compare(y), a.partial_cmp(&b), "{a:e} <> {b:e}");
+        }
+    }
+
+    #[test]
+    fn rounding() {
+        for (a, b) in operands(600) {
+            let (x, y) = (Binary32::from(a), Binary32::from(b));
+            for rounding in MODES {
+                for (result, native, (s, e), op) in [
+                    (x.add(y, rounding), a + b, two_sum(a, b), '+'),
+                    (x.sub(y, rounding), a - b, two_sum(a, -b), '-'),
+                    // Products of floats are exact as doubles
+                    (x.mul(y, rounding), a * b, (a as f64 * b as f64, 0.0), '*'),
+                ] {
+                    // Operands of opposite signs for the operation
+                    let cancels = |op| match op {
+                        '+' => a.is_sign_negative() != b.is_sign_negative(),
+                        '-' => a.is_sign_negative() == b.is_sign_negative(),
+                        _ => false,
+                    };
+                    let expected = match s == 0.0 && e == 0.0 || !s.is_finite() {
+                        // Exact zeros, infinities and NaNs follow native
+                        // floats, but for the sign of cancellations
+                        true if s == 0.0 && cancels(op) => match rounding {
+                            Rounding::TowardNegative => -0.0,
+                            _ => 0.0,
+                        },
+                        true => native,
+                        false => round(s, e, rounding),
+                    };
+                    assert!(
+                        same(result, expected),
+                        "{a:e} {op} {b:e} {rounding:?}: {:e} instead of {expected:e}",
+                        f32::from(result)
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn special_values() {
+        let mode = Rounding::NearestEven;
+        let [one, two] = [1.0f32, 2.0].map(Binary32::from);
+        assert_eq!(f32::from(one.add(one, mode)), 2.0);
+        assert!(Binary32::INFINITY.sub(Binary32::INFINITY, mode).is_nan());
+        assert!(Binary32::INFINITY.mul(Binary32::from(0.0), mode).is_nan());
+        assert_eq!(
+            Binary32::MAX.add(Binary32::MAX, Rounding::TowardZero),
+            Binary32::MAX
+        );
+        assert_eq!(
+            Binary32::MAX.mul(two, Rounding::TowardNegative),
+            Binary32::MAX
+        );
+        assert_eq!(Binary32::MAX.mul(two, mode), Binary32::INFINITY);
+
+        // Halfway between 1 and the next float
+        let half_ulp = Binary32::from(f32::EPSILON / 2.0);
+        assert_eq!(one.add(half_ulp, Rounding::NearestEven), one);
+        assert_eq!(
+            f32::from(one.add(half_ulp, Rounding::NearestAway)),
+            1.0f32.next_up()
+        );
+        let smallest = Binary32::from_bits(1);
+        assert_eq!(
+            smallest.mul(Binary32::from(0.5), Rounding::TowardPositive),
+            smallest
+        );
+        assert_eq!(
+            smallest.mul(Binary32::from(0.5), Rounding::NearestEven),
+            Binary32::from(0.0)
+        );
+        assert_eq!(
+            Binary32::from(-0.0).compare(Binary32::from(0.0)),
+            Some(Ordering::Equal)
+        );
+        assert_eq!(Binary32::NAN.compare(one), None);
+    }
+}
@@ -0,0 +1,323 @@
+use crate::algebra::BooleanAlgebra;
+
+use super::Word;
+
+/// Widest balanced code `balanced` searches for
+pub const MAX_BALANCED_WIDTH: u32 = 6;
+
+/// Binary reflected code of bits from the least significant one, each
+/// code bit being the XOR of a bit and the next one
+fn encode<B: BooleanAlgebra + Clone>(bits: &[B]) -> Vec<B> {
+    let zero = B::constant(false);
+    (0..bits.len())
+        .map(|i| bits[i].xor(bits.get(i + 1).unwrap_or(&zero)))
+        .collect()
+}
+
+/// Bits of a binary reflected code, each bit being the XOR of the code
+/// bits from it to the most significant one
+fn decode<B: BooleanAlgebra + Clone>(code: &[B]) -> Vec<B> {
+    let mut bits = code.to_vec();
+    for i in (0..bits.len().saturating_sub(1)).rev() {
+        bits[i] = bits[i].xor(&bits[i + 1]);
+    }
+    bits
+}
+
+/// Binary reflected Gray code, consecutive values differing by one bit
+pub fn gray_encode<W: Word>(a: W) -> W {
+    W::from_bits(&encode(&a.to_bits()))
+}
+
+/// Value whose binary reflected Gray code is `code`
+pub fn gray_decode<W: Word>(code: W) -> W {
+    W::from_bits(&decode(&code.to_bits()))
+}
+
+fn check_digits(digits: &[u32], radix: u32) {
+    assert!(radix >= 2, "Radix below 2");
+    assert!(
+        digits.iter().all(|&digit| digit < radix),
+        "Digit out of the radix"
+    );
+}
+
+/// Reflected `radix`-ary Gray code of digits from the least significant
+/// one, consecutive values differing by one in a single digit
+///
+/// Lower digits count down instead of up when the code digits above them
+/// add up to an odd number, so the last code is a single digit away from
+/// the first one for even radices only.
+pub fn reflected_encode(digits: &[u32], radix: u32) -> Vec<u32> {
+    check_digits(digits, radix);
+    let mut code = digits.to_vec();
+    let mut odd = false;
+    for i in (0..digits.len()).rev() {
+        if odd {
+            code[i] = radix - 1 - digits[i];
+        }
+        odd ^= code[i] % 2 == 1;
+    }
+    code
+}
+
+/// Digits whose reflected `radix`-ary Gray code is `code`
+pub fn reflected_decode(code: &[u32], radix: u32) -> Vec<u32> {
+    check_digits(code, radix);
+    let mut digits = code.to_vec();
+    let mut odd = false;
+    for i in (0..code.len()).rev() {
+        if odd {
+            digits[i] = radix - 1 - code[i];
+        }
+        odd ^= code[i] % 2 == 1;
+    }
+    digits
+}
+
+/// Modular `radix`-ary Gray code of digits from the least significant
+/// one, consecutive values differing in a single digit, cyclically
+///
+/// Each code digit is a digit minus the next one, modulo the radix.
+pub fn modular_encode(digits: &[u32], radix: u32) -> Vec<u32> {
+    check_digits(digits, radix);
+    (0..digits.len())
+        .map(|i| (digits[i] + radix - digits.get(i + 1).unwrap_or(&0)) % radix)
+        .collect()
+}
+
+/// Digits whose modular `radix`-ary Gray code is `code`
+pub fn modular_decode(code: &[u32], radix: u32) -> Vec<u32> {
+    check_digits(code, radix);
+    let mut digits = code.to_vec();
+    for i in (0..digits.len().saturating_sub(1)).rev() {
+        digits[i] = (code[i] + digits[i + 1]) % radix;
+    }
+    digits
+}
+
+/// Cyclic binary Gray code whose bits each flip about as often as the
+/// others, at most two flips apart, found by backtracking
+///
+/// Codes wider than `MAX_BALANCED_WIDTH` take too long to find.
+pub fn balanced(width: u32) -> Vec<u32> {
+    assert!(
+        width <= MAX_BALANCED_WIDTH,
+        "Balanced code wider than {MAX_BALANCED_WIDTH} bits"
+    );
+    if width == 0 {
+        return vec![0];
+    }
+    let length = 1usize << width;
+    // Flips are even in a cycle, so balanced counts are two consecutive
+    // even numbers
+    let fair = length / width as usize;
+    let limit = (fair + 1).next_multiple_of(2);
+
+    fn search(
+        code: &mut Vec<u32>,
+        visited: &mut [bool],
+        flips: &mut [usize],
+        limit: usize,
+    ) -> bool {
+        let last = *code.last().unwrap();
+        if code.len() == visited.len() {
+            // Close the cycle back to zero
+            if !last.is_power_of_two() {
+                return false;
+            }
+            let bit = last.trailing_zeros() as usize;
+            flips[bit] += 1;
+            let (min, max) = (flips.iter().min().unwrap(), flips.iter().max().unwrap());
+            let fair = max - min <= 2;
+            flips[bit] -= 1;
+            return fair;
+        }
+        // Least flipped bits first
+        let mut bits: Vec<usize> = (0..flips.len()).collect();
+        bits.sort_by_key(|&bit| flips[bit]);
+        for bit in bits {
+            let next = last ^ 1 << bit;
+            if flips[bit] >= limit || visited[next as usize] {
+                continue;
+            }
+            visited[next as usize] = true;
+            flips[bit] += 1;
+            code.push(next);
+            if search(code, visited, flips, limit) {
+                return true;
+            }
+            code.pop();
+            flips[bit] -= 1;
+            visited[next as usize] = false;
+        }
+        false
+    }
+
+    let mut code = vec![0];
+    let mut visited = vec![false; length];
+    visited[0] = true;
+    let mut flips = vec![0; width as usize];
+    assert!(search(&mut code, &mut visited, &mut flips, limit));
+    code
+}
+
+/// Every `width`-bit binary reflected Gray code in order, from zero
+pub fn codes(width: u32) -> Codes {
+    assert!(width < u128::BITS, "Too many codes to count");
+    Codes {
+        next: 0,
+        end: 1 << width,
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Codes {
+    next: u128,
+    end: u128,
+}
+
+impl Iterator for Codes {
+    type Item = u128;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.next == self.end {
+            return None;
+        }
+        self.next += 1;
+        Some(gray_encode(self.next - 1))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match usize::try_from(self.end - self.next) {
+            Ok(length) => (length, Some(length)),
+            Err(_) => (usize::MAX, None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::arithmetic::BitVector;
+
+    #[test]
+    fn binary() {
+        for a in 0..=u16::MAX {
+            let code = super::gray_encode(a);
+            assert_eq!(super::gray_decode(code), a);
+            assert_eq!(code, a ^ a >> 1);
+            assert_eq!(
+                (code ^ super::gray_encode(a.wrapping_add(1))).count_ones(),
+                1
+            );
+        }
+        assert_eq!(super::gray_decode(u128::MAX), u128::MAX / 3 * 2);
+
+        for width in [0, 1, 5, 130] {
+            for value in [0, 1, 3, 7, 0x1234_5678_9abc_def0_u128] {
+                let a = BitVector::from_u128(value, width);
+                let value = a.to_u128().unwrap();
+                let code = super::gray_encode(a.clone());
+                assert_eq!(super::gray_decode(code.clone()), a);
+                assert_eq!(code, BitVector::from_u128(value ^ value >> 1, width));
+            }
+        }
+    }
+
+    /// Digits of `value`, least significant first
+    fn digits(mut value: u32, radix: u32, length: usize) -> Vec<u32> {
+        (0..length)
+            .map(|_| {
+                let digit = value % radix;
+                value /= radix;
+                digit
+            })
+            .collect()
+    }
+
+    /// Digits that differ, with their values on both sides
+    fn changes(a: &[u32], b: &[u32]) -> Vec<(u32, u32)> {
+        a.iter()
+            .zip(b)
+            .filter(|(a, b)| a != b)
+            .map(|(&a, &b)| (a, b))
+            .collect()
+    }
+
+    #[test]
+    fn nary() {
+        for radix in 2..=5u32 {
+            let count = radix.pow(3);
+            let codes = |encode: fn(&[u32], u32) -> Vec<u32>| -> Vec<Vec<u32>> {
+                (0..count)
+                    .map(|value| encode(&digits(value, radix, 3), radix))
+                    .collect()
+            };
+            let reflected = codes(super::reflected_encode);
+            let modular = codes(super::modular_encode);
+            for (value, (reflected, modular)) in reflected.iter().zip(&modular).enumerate() {
+                let digits = digits(value as u32, radix, 3);
+                assert_eq!(super::reflected_decode(reflected, radix), digits);
+                assert_eq!(super::modular_decode(modular, radix), digits);
+            }
+
+            for pair in reflected.windows(2) {
+                let changed = changes(&pair[0], &pair[1]);
+                assert_eq!(changed.len(), 1, "{pair:?}");
+                assert_eq!(changed[0].0.abs_diff(changed[0].1), 1, "{pair:?}");
+            }
+            let mut cycle = modular.clone();
+            cycle.push(modular[0].clone());
+            for pair in cycle.windows(2) {
+                assert_eq!(changes(&pair[0], &pair[1]).len(), 1, "{pair:?}");
+            }
+        }
+
+        assert_eq!(super::reflected_encode(&[0, 1], 3), [2, 1]);
+        assert_eq!(super::modular_encode(&[0, 1], 3), [2, 1]);
+        assert_eq!(super::reflected_encode(&[1, 0, 1], 2), [1, 1, 1]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn digit_out_of_radix() {
+        super::reflected_encode(&[0, 3], 3);
+    }
+
+    #[test]
+    fn balanced() {
+        for width in 0..=super::MAX_BALANCED_WIDTH {
+            let code = super::balanced(width);
+            assert_eq!(code.len(), 1 << width);
+            let mut sorted = code.clone();
+            sorted.sort();
+            assert!(sorted.iter().copied().eq(0..1 << width));
+
+            let mut flips = vec![0; width as usize];
+            for (i, &a) in code.iter().enumerate() {
+                let b = code[(i + 1) % code.len()];
+                if width > 0 {
+                    assert_eq!((a ^ b).count_ones(), 1, "{width}");
+                    flips[(a ^ b).trailing_zeros() as usize] += 1;
+                }
+            }
+            let (min, max) = (flips.iter().min(), flips.iter().max());
+            assert!(
+                max.zip(min).is_none_or(|(max, min)| max - min <= 2),
+                "{flips:?}"
+            );
+        }
+        assert_eq!(super::balanced(2), [0, 1, 3, 2]);
+    }
+
+    #[test]
+    fn codes() {
+        let codes: Vec<u128> = super::codes(3).collect();
+        assert_eq!(codes, [0, 1, 3, 2, 6, 7, 5, 4]);
+        assert_eq!(super::codes(0).collect::<Vec<_>>(), [0]);
+        assert_eq!(super::codes(20).size_hint(), (1 << 20, Some(1 << 20)));
+        let mut codes = super::codes(127);
+        assert_eq!(codes.nth(5), Some(7));
+        assert_eq!(codes.size_hint(), (usize::MAX, None));
+    }
+}
@@ -0,0 +1,232 @@
+use super::{
+    booth_mul, difference_flags, nonrestoring_div, product_flags, restoring_div, ripple_add,
+    ripple_sub, shift_add_mul, shift_left, shift_right, signed_product_flags, sum_flags, Adder,
+    Flagged, Multiplier,
+};
+use crate::expression::{Expression, Symbol, SYMBOL_RANGE};
+
+/// Circuit of each bit of a result, least significant first
+///
+/// Formulas are trees, repeating the gates several outputs share, so that
+/// products and quotients grow exponentially with the width.
+pub type Bits = Vec<Expression>;
+
+/// Variables `first`, `first + 1`... for the bits of a `width`-bit operand,
+/// least significant first
+///
+/// Panics when running past `Z`.
+pub fn variables(first: Symbol, width: usize) -> Bits {
+    (0..width)
+        .map(|i| {
+            let symbol = u8::try_from(first as usize + i)
+                .ok()
+                .filter(|symbol| SYMBOL_RANGE.contains(symbol));
+            let symbol = symbol.unwrap_or_else(|| {
+                panic!("Not enough symbols for {width} bits from {}", first as char)
+            });
+            Expression::Var(symbol)
+        })
+        .collect()
+}
+
+fn check_widths(a: &[Expression], b: &[Expression]) {
+    assert_eq!(a.len(), b.len(), "Operands of different widths");
+}
+
+/// Ripple-carry sum
+pub fn add(a: &[Expression], b: &[Expression]) -> Flagged<Bits, Expression> {
+    check_widths(a, b);
+    sum_flags(ripple_add(a, b, Expression::Val(false)))
+}
+
+/// Sum by the given adder
+pub fn add_with(a: &[Expression], b: &[Expression], adder: Adder) -> Flagged<Bits, Expression> {
+    check_widths(a, b);
+    sum_flags(adder.sum(a, b, Expression::Val(false)))
+}
+
+/// Ripple-carry difference
+pub fn sub(a: &[Expression], b: &[Expression]) -> Flagged<Bits, Expression> {
+    check_widths(a, b);
+    difference_flags(ripple_sub(a, b))
+}
+
+/// Two's complement negation
+pub fn neg(a: &[Expression]) -> Flagged<Bits, Expression> {
+    difference_flags(ripple_sub(&vec![Expression::Val(false); a.len()], a))
+}
+
+/// Shift-and-add product, truncated to the width of the operands
+pub fn mul(a: &[Expression], b: &[Expression]) -> Flagged<Bits, Expression> {
+    check_widths(a, b);
+    product_flags(a, b, shift_add_mul(a, b, ripple_add))
+}
+
+/// Product by the given multiplier and adder
+pub fn mul_with(
+    a: &[Expression],
+    b: &[Expression],
+    multiplier: Multiplier,
+    adder: Adder,
+) -> Flagged<Bits, Expression> {
+    check_widths(a, b);
+    product_flags(a, b, multiplier.product(a, b, adder))
+}
+
+/// Signed product by Booth's algorithm
+pub fn mul_booth(a: &[Expression], b: &[Expression]) -> Flagged<Bits, Expression> {
+    check_widths(a, b);
+    signed_product_flags(booth_mul(a, b), a.len())
+}
+
+/// Quotient and remainder by restoring division, meaningless when `b` is
+/// zero
+pub fn div_restoring(a: &[Expression], b: &[Expression]) -> (Bits, Bits) {
+    check_widths(a, b);
+    restoring_div(a, b)
+}
+
+/// Quotient and remainder by non-restoring division, meaningless when `b`
+/// is zero
+pub fn div_nonrestoring(a: &[Expression], b: &[Expression]) -> (Bits, Bits) {
+    check_widths(a, b);
+    nonrestoring_div(a, b)
+}
+
+pub fn shift_left_arithmetic(a: &[Expression], amount: usize) -> Flagged<Bits, Expression> {
+    shift_left(a, amount)
+}
+
+pub fn shift_right_arithmetic(a: &[Expression], amount: usize) -> Flagged<Bits, Expression> {
+    shift_right(a, amount)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{variables, Bits};
+    use crate::arithmetic::{self, Adder, BitVector, Flagged, Multiplier};
+    use crate::expression::{Environment, Expression};
+
+    /// Whether two circuits compute the same function
+    fn equivalent(a: &Expression, b: &Expression) -> bool {
+        !Expression::xor(a.clone(), b.clone()).sat()
+    }
+
+    fn all_equivalent(a: &Flagged<Bits, Expression>, b: &Flagged<Bits, Expression>) -> bool {
+        a.value.iter().zip(&b.value).all(|(a, b)| equivalent(a, b))
+            && equivalent(&a.carry, &b.carry)
+            && equivalent(&a.overflow, &b.overflow)
+    }
+
+    /// Value of the circuit for operands `x` from `A` and `y` right after
+    fn run(circuit: &Flagged<Bits, Expression>, x: u128, y: u128, width: usize) -> Flagged<u128> {
+        let mut env = Environment::default();
+        for i in 0..width {
+            env.set(b'A' + i as u8, x >> i & 1 != 0);
+            env.set(b'A' + (width + i) as u8, y >> i & 1 != 0);
+        }
+        let eval = |bit: &Expression| bit.interpret(&|symbol| env.get(symbol)).unwrap();
+        let bits: Vec<bool> = circuit.value.iter().map(eval).collect();
+        Flagged {
+            value: BitVector::from(bits).to_u128().unwrap(),
+            carry: eval(&circuit.carry),
+            overflow: eval(&circuit.overflow),
+        }
+    }
+
+    fn native(flagged: Flagged<BitVector>) -> Flagged<u128> {
+        Flagged {
+            value: flagged.value.to_u128().unwrap(),
+            carry: flagged.carry,
+            overflow: flagged.overflow,
+        }
+    }
+
+    #[test]
+    fn commutativity() {
+        let (a, b) = (variables(b'A', 8), variables(b'I', 8));
+        assert!(all_equivalent(&super::add(&a, &b), &super::add(&b, &a)));
+
+        let (a, b) = (variables(b'A', 6), variables(b'G', 6));
+        assert!(all_equivalent(
+            &super::add(&a, &b),
+            &super::add_with(&b, &a, Adder::KoggeStone)
+        ));
+        assert!(!all_equivalent(&super::add(&a, &b), &super::sub(&a, &b)));
+
+        let (a, b) = (variables(b'A', 3), variables(b'D', 3));
+        assert!(all_equivalent(&super::mul(&a, &b), &super::mul(&b, &a)));
+        assert!(all_equivalent(
+            &super::mul(&a, &b),
+            &super::mul_with(&b, &a, Multiplier::Dadda, Adder::BrentKung)
+        ));
+    }
+
+    #[test]
+    fn identities() {
+        let (a, b) = (variables(b'A', 8), variables(b'I', 8));
+        // (a + b) - b = a
+        let sum = super::add(&a, &b).value;
+        let difference = super::sub(&sum, &b).value;
+        assert!(difference.iter().zip(&a).all(|(x, a)| equivalent(x, a)));
+        // -(-a) = a
+        let twice = super::neg(&super::neg(&a).value).value;
+        assert!(twice.iter().zip(&a).all(|(x, a)| equivalent(x, a)));
+        // a << 1 = a + a
+        let shifted = super::shift_left_arithmetic(&a, 1);
+        assert!(all_equivalent(&shifted, &super::add(&a, &a)));
+    }
+
+    #[test]
+    fn agreement() {
+        let width = 3;
+        let (a, b) = (variables(b'A', width), variables(b'D', width));
+        let circuits = [
+            super::add(&a, &b),
+            super::sub(&a, &b),
+            super::mul(&a, &b),
+            super::mul_booth(&a, &b),
+            super::neg(&a),
+            super::shift_right_arithmetic(&a, 2),
+        ];
+        let division = super::div_restoring(&a, &b);
+        let nonrestoring = super::div_nonrestoring(&a, &b);
+        for x in 0..1 << width {
+            for y in 0..1 << width {
+                let (wx, wy) = (
+                    BitVector::from_u128(x, width),
+                    BitVector::from_u128(y, width),
+                );
+                let expected = [
+                    arithmetic::add(wx.clone(), wy.clone()),
+                    arithmetic::sub(wx.clone(), wy.clone()),
+                    arithmetic::mul(wx.clone(), wy.clone()),
+                    arithmetic::mul_booth(wx.clone(), wy.clone()),
+                    arithmetic::neg(wx.clone()),
+                    arithmetic::shift_right_arithmetic(wx.clone(), 2),
+                ];
+                for (circuit, expected) in circuits.iter().zip(expected) {
+                    assert_eq!(run(circuit, x, y, width), native(expected), "{x} {y}");
+                }
+
+                if y != 0 {
+                    for (quotient, remainder) in [&division, &nonrestoring] {
+                        let circuit = |value: &Bits| Flagged {
+                            value: value.clone(),
+                            carry: Expression::Val(false),
+                            overflow: Expression::Val(false),
+                        };
+                        assert_eq!(run(&circuit(quotient), x, y, width).value, x / y);
+                        assert_eq!(run(&circuit(remainder), x, y, width).value, x % y);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn too_wide() {
+        variables(b'T', 8);
+    }
+}
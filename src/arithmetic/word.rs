@@ -0,0 +1,130 @@
+use core::fmt;
+
+/// Unsigned bit vector the gate-level routines work on
+pub trait Word: Sized {
+    fn width(&self) -> usize;
+
+    /// Bits from the least significant one
+    fn to_bits(&self) -> Vec<bool>;
+
+    /// Word holding these bits, as many as the width of fixed-width words
+    fn from_bits(bits: &[bool]) -> Self;
+}
+
+macro_rules! impl_word {
+    ($($type:ty),*) => {
+        $(
+            impl Word for $type {
+                fn width(&self) -> usize {
+                    <$type>::BITS as usize
+                }
+
+                fn to_bits(&self) -> Vec<bool> {
+                    (0..<$type>::BITS).map(|i| self >> i & 1 != 0).collect()
+                }
+
+                fn from_bits(bits: &[bool]) -> Self {
+                    assert_eq!(bits.len(), <$type>::BITS as usize, "Bit count of a fixed width word");
+                    bits.iter().rev().fold(0, |word, &bit| word << 1 | bit as $type)
+                }
+            }
+        )*
+    };
+}
+
+impl_word!(u8, u16, u32, u64, u128);
+
+/// Bit vector of any width
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct BitVector(Vec<bool>);
+
+impl BitVector {
+    /// Vector of zeros
+    pub fn new(width: usize) -> Self {
+        Self(vec![false; width])
+    }
+
+    /// Low `width` bits of a value
+    pub fn from_u128(value: u128, width: usize) -> Self {
+        Self((0..width).map(|i| i < 128 && value >> i & 1 != 0).collect())
+    }
+
+    /// Value of the vector, `None` if it needs more than 128 bits
+    pub fn to_u128(&self) -> Option<u128> {
+        if self.0.iter().skip(128).any(|&bit| bit) {
+            return None;
+        }
+        let mut bits = self.0.clone();
+        bits.resize(128, false);
+        Some(u128::from_bits(&bits[..128]))
+    }
+
+    pub fn bit(&self, i: usize) -> bool {
+        self.0[i]
+    }
+
+    pub fn set_bit(&mut self, i: usize, value: bool) {
+        self.0[i] = value;
+    }
+}
+
+impl From<Vec<bool>> for BitVector {
+    fn from(bits: Vec<bool>) -> Self {
+        Self(bits)
+    }
+}
+
+/// Binary, most significant bit first
+impl fmt::Display for BitVector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for &bit in self.0.iter().rev() {
+            write!(f, "{}", bit as u8)?;
+        }
+        Ok(())
+    }
+}
+
+impl Word for BitVector {
+    fn width(&self) -> usize {
+        self.0.len()
+    }
+
+    fn to_bits(&self) -> Vec<bool> {
+        self.0.clone()
+    }
+
+    fn from_bits(bits: &[bool]) -> Self {
+        Self(bits.to_vec())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BitVector, Word};
+
+    #[test]
+    fn native() {
+        assert_eq!(
+            0b1101u8.to_bits(),
+            [true, false, true, true, false, false, false, false]
+        );
+        for value in [0, 1, 0xdead_beef, u128::MAX, 1 << 127] {
+            assert_eq!(u128::from_bits(&value.to_bits()), value);
+        }
+        assert_eq!(u16::MAX.width(), 16);
+    }
+
+    #[test]
+    fn bit_vector() {
+        let vector = BitVector::from_u128(0b1011, 6);
+        assert_eq!(vector.to_string(), "001011");
+        assert_eq!(vector.to_u128(), Some(0b1011));
+        assert!(vector.bit(3) && !vector.bit(2));
+
+        let mut wide = BitVector::from_u128(u128::MAX, 200);
+        assert_eq!(wide.to_u128(), Some(u128::MAX));
+        wide.set_bit(150, true);
+        assert_eq!(wide.to_u128(), None);
+        assert_eq!(BitVector::new(0).to_u128(), Some(0));
+    }
+}
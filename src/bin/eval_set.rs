@@ -1,6 +1,63 @@
 use std::io::{stdin, stdout, Write};
+use std::num::ParseIntError;
+use std::ops::RangeInclusive;
 
-use ready_set_boole::set::{Set, self};
+use ready_set_boole::set::{self, Extent, IntervalSet, Universe};
+
+/// Parse a number, or a range written `a..b` or `a..=b`
+fn parse_range(part: &str) -> Result<RangeInclusive<i32>, ParseIntError> {
+    if let Some((start, end)) = part.split_once("..=") {
+        return Ok(start.parse()?..=end.parse()?);
+    }
+    if let Some((start, end)) = part.split_once("..") {
+        let (start, end): (i32, i32) = (start.parse()?, end.parse()?);
+        // An empty exclusive range stays empty once inclusive
+        return Ok(match end.checked_sub(1) {
+            Some(last) => start..=last,
+            None => RangeInclusive::new(1, 0),
+        });
+    }
+    let value = part.parse()?;
+    Ok(value..=value)
+}
+
+/// Parse a line of disjoint numbers and ranges, reporting errors on stderr
+fn parse_set(line: &str) -> Option<IntervalSet<i32>> {
+    let ranges: Vec<RangeInclusive<i32>> = match line.split_whitespace().map(parse_range).collect()
+    {
+        Ok(ranges) => ranges,
+        Err(err) => {
+            eprintln!("Could not parse numbers: {err}");
+            return None;
+        }
+    };
+
+    let mut set = IntervalSet::new();
+    for range in ranges {
+        let single = IntervalSet::from_ranges([range.clone()]);
+        if !set.is_disjoint(&single) {
+            eprintln!("Invalid set: duplicate found");
+            return None;
+        }
+        set.insert_range(range);
+    }
+    Some(set)
+}
+
+/// Ranges of the set, `{ 1..=3, 7 }`
+fn format_set(set: &IntervalSet<i32>) -> String {
+    let ranges: Vec<String> = set
+        .ranges()
+        .map(|range| match range.start() == range.end() {
+            true => range.start().to_string(),
+            false => format!("{}..={}", range.start(), range.end()),
+        })
+        .collect();
+    match ranges.is_empty() {
+        true => "{ }".to_string(),
+        false => format!("{{ {} }}", ranges.join(", ")),
+    }
+}
 
 pub fn main() {
     let mut line = String::new();
@@ -8,13 +65,28 @@ pub fn main() {
     'outer: loop {
         print!(">> ");
         stdout().flush().unwrap();
-        
+
         line.clear();
         if stdin().read_line(&mut line).unwrap() == 0 {
             break;
         }
         let formula = line.trim().to_uppercase();
 
+        // An empty universe line stands for the union of the sets
+        print!("U =  ");
+        stdout().flush().unwrap();
+        line.clear();
+        if stdin().read_line(&mut line).unwrap() == 0 {
+            break;
+        }
+        let universe = match line.trim() {
+            "" => None,
+            line => match parse_set(line) {
+                Some(universe) => Some(universe),
+                None => continue 'outer,
+            },
+        };
+
         let mut environment = Vec::new();
         loop {
             print!(">    ");
@@ -29,28 +101,19 @@ pub fn main() {
                 break;
             }
 
-            let numbers: Vec<i32> = match line
-                .split_whitespace()
-                .map(|part| part.parse())
-                .collect()
-            {
-                Ok(numbers) => numbers,
-                Err(err) => {
-                    eprintln!("Could not parse numbers: {err}");
-                    continue 'outer;
-                }
-            };
-
-            let Ok(set) = Set::try_from(numbers) else {
-                eprintln!("Invalid set: duplicate found");
+            let Some(set) = parse_set(&line) else {
                 continue 'outer;
             };
-
             environment.push(set)
         }
 
-        match set::try_evaluate(&formula, environment) {
-            Ok(set) => println!("=> {set}"),
+        let universe = match &universe {
+            Some(universe) => Universe::Set(universe),
+            None => Universe::Environment,
+        };
+        match set::try_evaluate_in(&formula, environment, universe) {
+            Ok(Extent::Finite(set)) => println!("=> {}", format_set(&set)),
+            Ok(Extent::Cofinite(_)) => unreachable!("Finite universes yield finite sets"),
             Err(err) => eprintln!("Evaluation error: {err:?}"),
         }
     }
@@ -1,9 +1,17 @@
-use ready_set_boole::arithmetic::gray_code;
+use ready_set_boole::arithmetic::{gray_code, gray_decode};
 
 pub fn main() {
+    // Numbers after `-d` are codes to decode
+    let mut decode = false;
     for arg in std::env::args().skip(1) {
-        let a = arg.parse().expect("Invalid number argument");
-        println!("{a:8b} -> {:8b}", gray_code(a));
+        if arg == "-d" || arg == "--decode" {
+            decode = true;
+            continue;
+        }
+        let a: u32 = arg.parse().expect("Invalid number argument");
+        match decode {
+            false => println!("{a:8b} -> {:8b}", gray_code(a)),
+            true => println!("{a:8b} -> {:8b}", gray_decode(a)),
+        }
     }
 }
-
@@ -1,4 +1,4 @@
-use ready_set_boole::sat;
+use ready_set_boole::expression::Expression;
 use std::io::{stdin, stdout, Write};
 
 pub fn main() {
@@ -11,7 +11,21 @@ pub fn main() {
             break;
         }
 
-        println!("=> {}", sat(&formula.trim().to_uppercase()));
+        let expression: Expression = match formula.trim().to_uppercase().parse() {
+            Ok(expression) => expression,
+            Err(err) => {
+                eprintln!("Could not parse formula: {err:?}");
+                continue;
+            }
+        };
+        match expression.unsat_core() {
+            None => println!("=> true"),
+            Some(core) => {
+                println!("=> false");
+                for conjunct in core {
+                    println!("   because of {conjunct}");
+                }
+            }
+        }
     }
 }
-
@@ -0,0 +1,382 @@
+use std::collections::HashMap;
+
+use crate::cnf::{Cnf, Literal};
+use crate::expression::{Expression, Symbol};
+
+/// Clausal encodings of "at most k of these literals hold"
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Encoding {
+    /// Sinz's sequential counter, `O(n·k)` clauses and variables
+    SequentialCounter,
+    /// Bailleux and Boufkhad's totalizer, `O(n·k)` clauses over a tree of
+    /// unary counters
+    Totalizer,
+    /// Batcher's odd-even merge sort, `O(n·log²n)` comparators
+    SortingNetwork,
+}
+
+pub fn at_most(cnf: &mut Cnf, literals: &[Literal], k: usize, encoding: Encoding) {
+    if k >= literals.len() {
+        return;
+    }
+    if k == 0 {
+        for &literal in literals {
+            cnf.add_clause([!literal]);
+        }
+        return;
+    }
+    match encoding {
+        Encoding::SequentialCounter => sequential_counter(cnf, literals, k),
+        Encoding::Totalizer => {
+            let outputs = totalizer(cnf, literals, k + 1);
+            cnf.add_clause([!outputs[k]]);
+        }
+        Encoding::SortingNetwork => {
+            let outputs = sorting_network(cnf, literals);
+            cnf.add_clause([!outputs[k]]);
+        }
+    }
+}
+
+pub fn at_least(cnf: &mut Cnf, literals: &[Literal], k: usize, encoding: Encoding) {
+    if k > literals.len() {
+        cnf.add_clause([]);
+        return;
+    }
+    let negated: Vec<Literal> = literals.iter().map(|&l| !l).collect();
+    at_most(cnf, &negated, literals.len() - k, encoding);
+}
+
+pub fn exactly(cnf: &mut Cnf, literals: &[Literal], k: usize, encoding: Encoding) {
+    at_most(cnf, literals, k, encoding);
+    at_least(cnf, literals, k, encoding);
+}
+
+/// `s[i][j]` holds when more than `j` of the first `i + 1` literals do
+fn sequential_counter(cnf: &mut Cnf, literals: &[Literal], k: usize) {
+    let n = literals.len();
+    let s: Vec<Vec<Literal>> = (0..n - 1)
+        .map(|_| {
+            (0..k)
+                .map(|_| Literal::positive(cnf.fresh_variable()))
+                .collect()
+        })
+        .collect();
+
+    cnf.add_clause([!literals[0], s[0][0]]);
+    for &counter in &s[0][1..] {
+        cnf.add_clause([!counter]);
+    }
+    for i in 1..n - 1 {
+        let x = literals[i];
+        cnf.add_clause([!x, s[i][0]]);
+        cnf.add_clause([!s[i - 1][0], s[i][0]]);
+        for j in 1..k {
+            cnf.add_clause([!x, !s[i - 1][j - 1], s[i][j]]);
+            cnf.add_clause([!s[i - 1][j], s[i][j]]);
+        }
+        cnf.add_clause([!x, !s[i - 1][k - 1]]);
+    }
+    cnf.add_clause([!literals[n - 1], !s[n - 2][k - 1]]);
+}
+
+/// Unary count of the literals, truncated to `limit` outputs: the `j`th
+/// output is implied as soon as `j + 1` literals hold
+fn totalizer(cnf: &mut Cnf, literals: &[Literal], limit: usize) -> Vec<Literal> {
+    if literals.len() == 1 {
+        return literals.to_vec();
+    }
+    let (left, right) = literals.split_at(literals.len() / 2);
+    let a = totalizer(cnf, left, limit);
+    let b = totalizer(cnf, right, limit);
+    let outputs: Vec<Literal> = (0..limit.min(a.len() + b.len()))
+        .map(|_| Literal::positive(cnf.fresh_variable()))
+        .collect();
+    for i in 0..=a.len() {
+        for j in 0..=b.len() {
+            let Some(&output) = outputs.get((i + j).min(limit).wrapping_sub(1)) else {
+                continue;
+            };
+            let mut clause = vec![output];
+            clause.extend(i.checked_sub(1).map(|i| !a[i]));
+            clause.extend(j.checked_sub(1).map(|j| !b[j]));
+            cnf.add_clause(clause);
+        }
+    }
+    outputs
+}
+
+/// Outputs sorted with true values first, only propagating upwards
+fn sorting_network(cnf: &mut Cnf, literals: &[Literal]) -> Vec<Literal> {
+    let mut wires = literals.to_vec();
+    let size = wires.len().next_power_of_two();
+    if size > wires.len() {
+        let padding = Literal::positive(cnf.fresh_variable());
+        cnf.add_clause([!padding]);
+        wires.resize(size, padding);
+    }
+
+    let mut compare = |wires: &mut [Literal], i: usize, j: usize| {
+        let (a, b) = (wires[i], wires[j]);
+        let max = Literal::positive(cnf.fresh_variable());
+        let min = Literal::positive(cnf.fresh_variable());
+        cnf.add_clause([!a, max]);
+        cnf.add_clause([!b, max]);
+        cnf.add_clause([!a, !b, min]);
+        wires[i] = max;
+        wires[j] = min;
+    };
+
+    // Iterative odd-even merge sort over wires `0..size`
+    let mut p = 1;
+    while p < size {
+        let mut k = p;
+        while k >= 1 {
+            for j in (k % p..size - k).step_by(2 * k) {
+                for i in 0..k.min(size - j - k) {
+                    if (i + j) / (2 * p) == (i + j + k) / (2 * p) {
+                        compare(&mut wires, i + j, i + j + k);
+                    }
+                }
+            }
+            k /= 2;
+        }
+        p *= 2;
+    }
+    wires
+}
+
+/// Encode `Σ weight·literal ≤ bound` by a decision diagram over partial sums
+///
+/// Negative weights are folded into the bound by negating their literal.
+pub fn pseudo_boolean_at_most(cnf: &mut Cnf, terms: &[(i64, Literal)], bound: i64) {
+    let mut bound = bound;
+    let terms: Vec<(i64, Literal)> = terms
+        .iter()
+        .filter(|(weight, _)| *weight != 0)
+        .map(|&(weight, literal)| match weight < 0 {
+            true => {
+                bound -= weight;
+                (-weight, !literal)
+            }
+            false => (weight, literal),
+        })
+        .collect();
+    // Total weight of the terms from each index on
+    let mut suffix = vec![0; terms.len() + 1];
+    for i in (0..terms.len()).rev() {
+        suffix[i] = suffix[i + 1] + terms[i].0;
+    }
+
+    /// Literal stating that terms from `index` on sum to at most `bound`,
+    /// `None` when this trivially holds
+    fn node(
+        cnf: &mut Cnf,
+        nodes: &mut HashMap<(usize, i64), Literal>,
+        terms: &[(i64, Literal)],
+        suffix: &[i64],
+        index: usize,
+        bound: i64,
+    ) -> Option<Literal> {
+        if bound >= suffix[index] {
+            return None;
+        }
+        if let Some(&node) = nodes.get(&(index, bound)) {
+            return Some(node);
+        }
+        let current = Literal::positive(cnf.fresh_variable());
+        nodes.insert((index, bound), current);
+        if bound < 0 {
+            cnf.add_clause([!current]);
+            return Some(current);
+        }
+        let (weight, literal) = terms[index];
+        if let Some(next) = node(cnf, nodes, terms, suffix, index + 1, bound - weight) {
+            cnf.add_clause([!current, !literal, next]);
+        }
+        if let Some(next) = node(cnf, nodes, terms, suffix, index + 1, bound) {
+            cnf.add_clause([!current, next]);
+        }
+        Some(current)
+    }
+
+    if let Some(root) = node(cnf, &mut HashMap::new(), &terms, &suffix, 0, bound) {
+        cnf.add_clause([root]);
+    }
+}
+
+/// Encode `Σ weight·literal ≥ bound`
+pub fn pseudo_boolean_at_least(cnf: &mut Cnf, terms: &[(i64, Literal)], bound: i64) {
+    let negated: Vec<(i64, Literal)> = terms.iter().map(|&(w, l)| (-w, l)).collect();
+    pseudo_boolean_at_most(cnf, &negated, -bound);
+}
+
+/// Encode `Σ weight·literal = bound`
+pub fn pseudo_boolean_exactly(cnf: &mut Cnf, terms: &[(i64, Literal)], bound: i64) {
+    pseudo_boolean_at_most(cnf, terms, bound);
+    pseudo_boolean_at_least(cnf, terms, bound);
+}
+
+/// Every `size`-subset of `0..n`, in lexicographic order
+fn combinations(n: usize, size: usize) -> Vec<Vec<usize>> {
+    if size == 0 {
+        return vec![vec![]];
+    }
+    (size - 1..n)
+        .flat_map(|last| {
+            combinations(last, size - 1).into_iter().map(move |mut c| {
+                c.push(last);
+                c
+            })
+        })
+        .collect()
+}
+
+fn conjunction(expressions: impl IntoIterator<Item = Expression>) -> Expression {
+    expressions
+        .into_iter()
+        .reduce(|a, b| a & b)
+        .unwrap_or(Expression::Val(true))
+}
+
+/// Binomial encodings over expression symbols, without auxiliary variables
+impl Expression {
+    /// Every `k + 1` symbols contain a false one
+    pub fn at_most(k: usize, symbols: &[Symbol]) -> Self {
+        conjunction(
+            combinations(symbols.len(), k + 1)
+                .into_iter()
+                .map(|subset| {
+                    subset
+                        .into_iter()
+                        .map(|i| !Expression::Var(symbols[i]))
+                        .reduce(|a, b| a | b)
+                        .unwrap()
+                }),
+        )
+    }
+
+    /// Every `n - k + 1` symbols contain a true one
+    pub fn at_least(k: usize, symbols: &[Symbol]) -> Self {
+        if k == 0 {
+            return Expression::Val(true);
+        }
+        if k > symbols.len() {
+            return Expression::Val(false);
+        }
+        conjunction(
+            combinations(symbols.len(), symbols.len() + 1 - k)
+                .into_iter()
+                .map(|subset| {
+                    subset
+                        .into_iter()
+                        .map(|i| Expression::Var(symbols[i]))
+                        .reduce(|a, b| a | b)
+                        .unwrap()
+                }),
+        )
+    }
+
+    pub fn exactly(k: usize, symbols: &[Symbol]) -> Self {
+        Expression::at_most(k, symbols) & Expression::at_least(k, symbols)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Encoding;
+    use crate::cnf::{Cnf, Literal};
+    use crate::expression::Expression;
+    use crate::solver::Solver;
+
+    const ENCODINGS: [Encoding; 3] = [
+        Encoding::SequentialCounter,
+        Encoding::Totalizer,
+        Encoding::SortingNetwork,
+    ];
+
+    /// Check an encoding against a predicate on every input assignment
+    fn check(n: u32, encode: impl Fn(&mut Cnf, &[Literal]), expected: impl Fn(&[bool]) -> bool) {
+        let literals: Vec<Literal> = (0..n).map(Literal::positive).collect();
+        let mut cnf = Cnf::with_variables(n);
+        encode(&mut cnf, &literals);
+        let mut solver = Solver::from(cnf);
+        for bits in 0..1u32 << n {
+            let values: Vec<bool> = (0..n).map(|i| bits >> i & 1 != 0).collect();
+            let assumptions: Vec<Literal> = (0..n)
+                .map(|i| Literal::new(i, values[i as usize]))
+                .collect();
+            let sat = solver.solve_with_assumptions(&assumptions).is_some();
+            assert_eq!(sat, expected(&values), "{values:?}");
+        }
+    }
+
+    fn count(values: &[bool]) -> usize {
+        values.iter().filter(|&&v| v).count()
+    }
+
+    #[test]
+    fn cardinality() {
+        for encoding in ENCODINGS {
+            for n in 1..=5 {
+                for k in 0..=n + 1 {
+                    let e = |cnf: &mut Cnf, l: &[Literal]| super::at_most(cnf, l, k, encoding);
+                    check(n as u32, e, |v| count(v) <= k);
+                    let e = |cnf: &mut Cnf, l: &[Literal]| super::at_least(cnf, l, k, encoding);
+                    check(n as u32, e, |v| count(v) >= k);
+                    let e = |cnf: &mut Cnf, l: &[Literal]| super::exactly(cnf, l, k, encoding);
+                    check(n as u32, e, |v| count(v) == k);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn pseudo_boolean() {
+        let weights = [3, -2, 5, 1, 4];
+        let sum = |v: &[bool]| -> i64 {
+            weights
+                .iter()
+                .zip(v)
+                .filter(|(_, &v)| v)
+                .map(|(w, _)| w)
+                .sum()
+        };
+        for bound in -3..=14 {
+            let terms = |l: &[Literal]| {
+                weights
+                    .iter()
+                    .copied()
+                    .zip(l.iter().copied())
+                    .collect::<Vec<_>>()
+            };
+            let e =
+                |cnf: &mut Cnf, l: &[Literal]| super::pseudo_boolean_at_most(cnf, &terms(l), bound);
+            check(5, e, |v| sum(v) <= bound);
+            let e = |cnf: &mut Cnf, l: &[Literal]| {
+                super::pseudo_boolean_at_least(cnf, &terms(l), bound)
+            };
+            check(5, e, |v| sum(v) >= bound);
+            let e =
+                |cnf: &mut Cnf, l: &[Literal]| super::pseudo_boolean_exactly(cnf, &terms(l), bound);
+            check(5, e, |v| sum(v) == bound);
+        }
+    }
+
+    #[test]
+    fn expression() {
+        let symbols = *b"ABCD";
+        for k in 0..=5 {
+            let at_most = Expression::at_most(k, &symbols);
+            let at_least = Expression::at_least(k, &symbols);
+            let exactly = Expression::exactly(k, &symbols);
+            for env in Expression::exactly(0, &symbols).envs() {
+                let count = env.values().filter(|&v| v).count();
+                assert_eq!(at_most.eval(env), count <= k);
+                assert_eq!(at_least.eval(env), count >= k);
+                assert_eq!(exactly.eval(env), count == k);
+            }
+        }
+        assert_eq!(Expression::at_most(1, b"AB").to_string(), "A!B!|");
+    }
+}
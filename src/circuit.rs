@@ -0,0 +1,500 @@
+use std::collections::HashMap;
+
+use crate::algebra::BooleanAlgebra;
+use crate::expression::{Expression, Symbol, SYMBOL_RANGE};
+
+pub mod aiger;
+pub mod blif;
+
+pub use aiger::AigerParseError;
+pub use blif::BlifParseError;
+
+/// Index of a gate in its circuit
+pub type Node = usize;
+
+/// Gate of a circuit, reading nodes built before it
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Gate {
+    /// The `n`th input of the circuit
+    Input(usize),
+    Constant(bool),
+    Not(Node),
+    And(Node, Node),
+    Or(Node, Node),
+    Xor(Node, Node),
+    /// `then` if `select` is set, `otherwise` if not
+    Mux {
+        select: Node,
+        then: Node,
+        otherwise: Node,
+    },
+}
+
+impl Gate {
+    /// Nodes the gate reads
+    pub fn operands(&self) -> impl Iterator<Item = Node> {
+        let operands = match *self {
+            Gate::Input(_) | Gate::Constant(_) => [None; 3],
+            Gate::Not(a) => [Some(a), None, None],
+            Gate::And(a, b) | Gate::Or(a, b) | Gate::Xor(a, b) => [Some(a), Some(b), None],
+            Gate::Mux {
+                select,
+                then,
+                otherwise,
+            } => [Some(select), Some(then), Some(otherwise)],
+        };
+        operands.into_iter().flatten()
+    }
+
+    /// Same gate reading other nodes
+    fn map(self, node: impl Fn(Node) -> Node) -> Self {
+        match self {
+            Gate::Input(_) | Gate::Constant(_) => self,
+            Gate::Not(a) => Gate::Not(node(a)),
+            Gate::And(a, b) => Gate::And(node(a), node(b)),
+            Gate::Or(a, b) => Gate::Or(node(a), node(b)),
+            Gate::Xor(a, b) => Gate::Xor(node(a), node(b)),
+            Gate::Mux {
+                select,
+                then,
+                otherwise,
+            } => Gate::Mux {
+                select: node(select),
+                then: node(then),
+                otherwise: node(otherwise),
+            },
+        }
+    }
+}
+
+/// Directed acyclic graph of gates with named inputs and outputs
+///
+/// Gates only read nodes built before them, so their order is topological.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Circuit {
+    gates: Vec<Gate>,
+    inputs: Vec<(String, Node)>,
+    outputs: Vec<(String, Node)>,
+}
+
+impl Circuit {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn push(&mut self, gate: Gate) -> Node {
+        debug_assert!(gate.operands().all(|node| node < self.gates.len()));
+        self.gates.push(gate);
+        self.gates.len() - 1
+    }
+
+    pub fn input(&mut self, name: impl Into<String>) -> Node {
+        let node = self.push(Gate::Input(self.inputs.len()));
+        self.inputs.push((name.into(), node));
+        node
+    }
+
+    pub fn constant(&mut self, value: bool) -> Node {
+        self.push(Gate::Constant(value))
+    }
+
+    pub fn not(&mut self, a: Node) -> Node {
+        self.push(Gate::Not(a))
+    }
+
+    pub fn and(&mut self, a: Node, b: Node) -> Node {
+        self.push(Gate::And(a, b))
+    }
+
+    pub fn or(&mut self, a: Node, b: Node) -> Node {
+        self.push(Gate::Or(a, b))
+    }
+
+    pub fn xor(&mut self, a: Node, b: Node) -> Node {
+        self.push(Gate::Xor(a, b))
+    }
+
+    pub fn mux(&mut self, select: Node, then: Node, otherwise: Node) -> Node {
+        self.push(Gate::Mux {
+            select,
+            then,
+            otherwise,
+        })
+    }
+
+    pub fn output(&mut self, name: impl Into<String>, node: Node) {
+        debug_assert!(node < self.gates.len());
+        self.outputs.push((name.into(), node));
+    }
+
+    pub fn gates(&self) -> &[Gate] {
+        &self.gates
+    }
+
+    pub fn inputs(&self) -> impl Iterator<Item = (&str, Node)> {
+        self.inputs
+            .iter()
+            .map(|(name, node)| (name.as_str(), *node))
+    }
+
+    pub fn outputs(&self) -> impl Iterator<Item = (&str, Node)> {
+        self.outputs
+            .iter()
+            .map(|(name, node)| (name.as_str(), *node))
+    }
+
+    /// Number of logic gates, leaving out inputs and constants
+    pub fn gate_count(&self) -> usize {
+        self.gates
+            .iter()
+            .filter(|gate| !matches!(gate, Gate::Input(_) | Gate::Constant(_)))
+            .count()
+    }
+
+    /// Most logic gates on a path from an input to an output
+    pub fn depth(&self) -> usize {
+        let mut depths: Vec<usize> = Vec::with_capacity(self.gates.len());
+        for gate in &self.gates {
+            let depth = match gate {
+                Gate::Input(_) | Gate::Constant(_) => 0,
+                gate => gate.operands().map(|node| depths[node]).max().unwrap() + 1,
+            };
+            depths.push(depth);
+        }
+        self.outputs()
+            .map(|(_, node)| depths[node])
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Outputs for the given inputs, in any Boolean algebra
+    ///
+    /// Each bit of a `u64` simulates its own input vector, running 64 of
+    /// them in a single pass.
+    pub fn simulate<A: BooleanAlgebra + Clone>(&self, inputs: &[A]) -> Vec<A> {
+        assert_eq!(inputs.len(), self.inputs.len(), "Wrong number of inputs");
+        let mut values: Vec<A> = Vec::with_capacity(self.gates.len());
+        for gate in &self.gates {
+            let value = match *gate {
+                Gate::Input(n) => inputs[n].clone(),
+                Gate::Constant(value) => A::constant(value),
+                Gate::Not(a) => values[a].not(),
+                Gate::And(a, b) => values[a].and(&values[b]),
+                Gate::Or(a, b) => values[a].or(&values[b]),
+                Gate::Xor(a, b) => values[a].xor(&values[b]),
+                Gate::Mux {
+                    select,
+                    then,
+                    otherwise,
+                } => {
+                    let select = &values[select];
+                    let then = select.and(&values[then]);
+                    then.or(&select.not().and(&values[otherwise]))
+                }
+            };
+            values.push(value);
+        }
+        self.outputs()
+            .map(|(_, node)| values[node].clone())
+            .collect()
+    }
+
+    /// Circuit computing each expression, with an input for each symbol in
+    /// alphabetical order
+    pub fn from_expressions<'a>(
+        outputs: impl IntoIterator<Item = (&'a str, &'a Expression)>,
+    ) -> Self {
+        fn build(circuit: &mut Circuit, inputs: &HashMap<Symbol, Node>, expr: &Expression) -> Node {
+            use Expression::*;
+            let mut binary = |a: &Expression, b: &Expression| {
+                (build(circuit, inputs, a), build(circuit, inputs, b))
+            };
+            match expr {
+                Var(symbol) => inputs[symbol],
+                &Val(value) => circuit.constant(value),
+                Not(x) => {
+                    let x = build(circuit, inputs, x);
+                    circuit.not(x)
+                }
+                And(a, b) => {
+                    let (a, b) = binary(a, b);
+                    circuit.and(a, b)
+                }
+                Or(a, b) => {
+                    let (a, b) = binary(a, b);
+                    circuit.or(a, b)
+                }
+                Xor(a, b) => {
+                    let (a, b) = binary(a, b);
+                    circuit.xor(a, b)
+                }
+                Implies(a, b) => {
+                    let (a, b) = binary(a, b);
+                    let not = circuit.not(a);
+                    circuit.or(not, b)
+                }
+                Equivalent(a, b) => {
+                    let (a, b) = binary(a, b);
+                    let xor = circuit.xor(a, b);
+                    circuit.not(xor)
+                }
+            }
+        }
+
+        let outputs: Vec<_> = outputs.into_iter().collect();
+        let mut circuit = Circuit::new();
+        let mut inputs = HashMap::new();
+        for symbol in SYMBOL_RANGE {
+            if outputs
+                .iter()
+                .any(|(_, expr)| expr.symbols().any(|s| s == symbol))
+            {
+                inputs.insert(symbol, circuit.input((symbol as char).to_string()));
+            }
+        }
+        for (name, expr) in outputs {
+            let node = build(&mut circuit, &inputs, expr);
+            circuit.output(name, node);
+        }
+        circuit
+    }
+
+    /// Expression of each output, `None` if an input is not named by a
+    /// symbol from `A` to `Z`
+    pub fn to_expressions(&self) -> Option<Vec<Expression>> {
+        let inputs: Option<Vec<Expression>> = self
+            .inputs()
+            .map(|(name, _)| match name.as_bytes() {
+                &[symbol] if SYMBOL_RANGE.contains(&symbol) => Some(Expression::Var(symbol)),
+                _ => None,
+            })
+            .collect();
+        Some(self.simulate(&inputs?))
+    }
+
+    /// Copy of the circuit, each gate being rebuilt by `emit` from the new
+    /// nodes of its operands, and skipped if not `live`
+    fn rebuild(
+        &self,
+        live: impl Fn(Node) -> bool,
+        mut emit: impl FnMut(&mut Circuit, Gate) -> Node,
+    ) -> Self {
+        let mut circuit = Circuit::new();
+        let mut nodes = vec![usize::MAX; self.gates.len()];
+        for (node, gate) in self.gates.iter().enumerate() {
+            nodes[node] = match *gate {
+                Gate::Input(n) => circuit.input(self.inputs[n].0.clone()),
+                _ if !live(node) => continue,
+                gate => emit(&mut circuit, gate.map(|node| nodes[node])),
+            };
+        }
+        for (name, node) in self.outputs() {
+            circuit.output(name, nodes[node]);
+        }
+        circuit
+    }
+
+    /// Equivalent circuit with constants folded into the gates they feed,
+    /// along with double negations and gates of identical operands
+    pub fn propagate_constants(&self) -> Self {
+        self.rebuild(|_| true, fold)
+    }
+
+    /// Equivalent circuit without the gates no output depends on
+    pub fn remove_dead(&self) -> Self {
+        let mut live = vec![false; self.gates.len()];
+        for (_, node) in self.outputs() {
+            live[node] = true;
+        }
+        for node in (0..self.gates.len()).rev() {
+            if live[node] {
+                for operand in self.gates[node].operands() {
+                    live[operand] = true;
+                }
+            }
+        }
+        self.rebuild(|node| live[node], |circuit, gate| circuit.push(gate))
+    }
+
+    /// Equivalent circuit building each gate once, operands of symmetric
+    /// gates being put in order so that `a & b` and `b & a` are merged
+    pub fn strash(&self) -> Self {
+        let mut built: HashMap<Gate, Node> = HashMap::new();
+        self.rebuild(
+            |_| true,
+            |circuit, gate| {
+                let gate = match gate {
+                    Gate::And(a, b) => Gate::And(a.min(b), a.max(b)),
+                    Gate::Or(a, b) => Gate::Or(a.min(b), a.max(b)),
+                    Gate::Xor(a, b) => Gate::Xor(a.min(b), a.max(b)),
+                    gate => gate,
+                };
+                *built.entry(gate).or_insert_with(|| circuit.push(gate))
+            },
+        )
+    }
+}
+
+/// Node computing the gate, simplified against the constants and the
+/// operands it reads
+fn fold(circuit: &mut Circuit, gate: Gate) -> Node {
+    let constant = |circuit: &Circuit, node: Node| match circuit.gates[node] {
+        Gate::Constant(value) => Some(value),
+        _ => None,
+    };
+    match gate {
+        Gate::Not(a) => match circuit.gates[a] {
+            Gate::Constant(value) => circuit.constant(!value),
+            Gate::Not(x) => x,
+            _ => circuit.not(a),
+        },
+        Gate::And(a, b) => match (constant(circuit, a), constant(circuit, b)) {
+            (Some(false), _) | (_, Some(false)) => circuit.constant(false),
+            (Some(true), _) => b,
+            (_, Some(true)) => a,
+            _ if a == b => a,
+            _ => circuit.and(a, b),
+        },
+        Gate::Or(a, b) => match (constant(circuit, a), constant(circuit, b)) {
+            (Some(true), _) | (_, Some(true)) => circuit.constant(true),
+            (Some(false), _) => b,
+            (_, Some(false)) => a,
+            _ if a == b => a,
+            _ => circuit.or(a, b),
+        },
+        Gate::Xor(a, b) => match (constant(circuit, a), constant(circuit, b)) {
+            (Some(false), _) => b,
+            (_, Some(false)) => a,
+            (Some(true), _) => fold(circuit, Gate::Not(b)),
+            (_, Some(true)) => fold(circuit, Gate::Not(a)),
+            _ if a == b => circuit.constant(false),
+            _ => circuit.xor(a, b),
+        },
+        Gate::Mux {
+            select,
+            then,
+            otherwise,
+        } => match (
+            constant(circuit, select),
+            constant(circuit, then),
+            constant(circuit, otherwise),
+        ) {
+            (Some(true), _, _) => then,
+            (Some(false), _, _) => otherwise,
+            _ if then == otherwise => then,
+            (_, Some(true), Some(false)) => select,
+            (_, Some(false), Some(true)) => fold(circuit, Gate::Not(select)),
+            (_, Some(false), _) => {
+                let not = fold(circuit, Gate::Not(select));
+                fold(circuit, Gate::And(not, otherwise))
+            }
+            (_, Some(true), _) => fold(circuit, Gate::Or(select, otherwise)),
+            (_, _, Some(false)) => fold(circuit, Gate::And(select, then)),
+            (_, _, Some(true)) => {
+                let not = fold(circuit, Gate::Not(select));
+                fold(circuit, Gate::Or(not, then))
+            }
+            _ => circuit.mux(select, then, otherwise),
+        },
+        gate => circuit.push(gate),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Circuit, Gate};
+    use crate::expression::Expression;
+
+    /// Sum and carry of three bits, with a dead gate
+    pub(super) fn full_adder() -> Circuit {
+        let mut circuit = Circuit::new();
+        let [a, b, c] = ["a", "b", "c"].map(|name| circuit.input(name));
+        let half = circuit.xor(a, b);
+        let sum = circuit.xor(half, c);
+        let both = circuit.and(a, b);
+        let carry = circuit.mux(half, c, both);
+        circuit.or(a, c);
+        circuit.output("sum", sum);
+        circuit.output("carry", carry);
+        circuit
+    }
+
+    /// Every input vector with its outputs
+    pub(super) fn truth_table(circuit: &Circuit) -> Vec<Vec<bool>> {
+        let inputs = circuit.inputs().count();
+        (0..1u32 << inputs)
+            .map(|row| {
+                let inputs: Vec<bool> = (0..inputs).map(|i| row >> i & 1 != 0).collect();
+                circuit.simulate(&inputs)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn simulate() {
+        let circuit = full_adder();
+        for (row, outputs) in truth_table(&circuit).into_iter().enumerate() {
+            let ones = row.count_ones();
+            assert_eq!(outputs, [ones % 2 == 1, ones >= 2]);
+        }
+        assert_eq!(circuit.gate_count(), 5);
+        assert_eq!(circuit.depth(), 2);
+
+        // Lane `i` of each word holds the bits of row `i`
+        let lanes = [0xaa, 0xcc, 0xf0u64];
+        let [sum, carry] = circuit.simulate(&lanes)[..] else {
+            panic!()
+        };
+        assert_eq!((sum, carry), (0x96, 0xe8));
+    }
+
+    #[test]
+    fn expressions() {
+        let formulas = ["AB&C|", "AB>C=", "A0^", "BD^"];
+        let expressions: Vec<Expression> = formulas.iter().map(|f| f.parse().unwrap()).collect();
+        let circuit = Circuit::from_expressions(formulas.iter().copied().zip(expressions.iter()));
+        let names: Vec<&str> = circuit.inputs().map(|(name, _)| name).collect();
+        assert_eq!(names, ["A", "B", "C", "D"]);
+
+        let back = circuit.to_expressions().unwrap();
+        for (expression, back) in expressions.iter().zip(&back) {
+            for env in expression.envs() {
+                assert_eq!(expression.eval(env), back.eval(env));
+            }
+        }
+        assert_eq!(back[2].to_string(), "A");
+        assert!(full_adder().to_expressions().is_none());
+    }
+
+    #[test]
+    fn optimizations() {
+        let mut circuit = Circuit::new();
+        let [a, b] = ["a", "b"].map(|name| circuit.input(name));
+        let one = circuit.constant(true);
+        let zero = circuit.constant(false);
+        let x = circuit.and(a, one);
+        let y = circuit.xor(b, one);
+        let z = circuit.not(y);
+        let w = circuit.mux(zero, x, z);
+        let v = circuit.and(a, b);
+        let u = circuit.and(b, a);
+        let t = circuit.or(v, u);
+        circuit.output("w", w);
+        circuit.output("t", t);
+
+        let folded = circuit.propagate_constants().remove_dead();
+        assert_eq!(truth_table(&folded), truth_table(&circuit));
+        // `w` is `b`, and only `t` keeps gates
+        assert_eq!(folded.outputs().next(), Some(("w", 1)));
+        assert_eq!(folded.gate_count(), 3);
+
+        let hashed = folded.strash().propagate_constants().remove_dead();
+        assert_eq!(truth_table(&hashed), truth_table(&circuit));
+        assert_eq!(hashed.gate_count(), 1);
+        assert!(matches!(hashed.gates().last(), Some(Gate::And(0, 1))));
+
+        let adder = full_adder();
+        assert_eq!(adder.remove_dead().gate_count(), 4);
+        assert_eq!(truth_table(&adder.remove_dead()), truth_table(&adder));
+    }
+}
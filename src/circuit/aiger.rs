@@ -0,0 +1,341 @@
+use core::fmt::Write;
+
+use super::{Circuit, Gate, Node};
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum AigerParseError {
+    /// First line not starting with `aag`
+    MissingHeader,
+    /// Header without the five counts `M I L O A`
+    InvalidHeader,
+    /// Fewer lines than the header announces
+    MissingLine,
+    /// Line of numbers that is not a valid input, output or AND gate
+    InvalidLiteral,
+    InvalidSymbol,
+    /// Variable neither constant, input nor AND gate
+    UndefinedVariable(usize),
+    RedefinedVariable(usize),
+    /// AND gate depending on itself
+    Cycle(usize),
+    /// Latches or the extensions of AIGER 1.9
+    Unsupported(String),
+}
+
+/// AND gates of a parsed file, built as their variables are first needed
+struct Variables {
+    circuit: Circuit,
+    /// Node of each defined variable, and the operands of each AND gate
+    nodes: Vec<Option<Node>>,
+    ands: Vec<Option<(usize, usize)>>,
+    visiting: Vec<bool>,
+}
+
+impl Variables {
+    fn literal(&mut self, literal: usize) -> Result<Node, AigerParseError> {
+        let node = self.variable(literal / 2)?;
+        Ok(match literal % 2 {
+            0 => node,
+            _ => self.circuit.not(node),
+        })
+    }
+
+    /// Node of a variable, building the AND gates it depends on with an
+    /// explicit stack so that deep chains do not overflow the call stack
+    fn variable(&mut self, variable: usize) -> Result<Node, AigerParseError> {
+        let mut stack = vec![variable];
+        while let Some(&top) = stack.last() {
+            if self.nodes[top].is_some() {
+                stack.pop();
+                continue;
+            }
+            let Some((a, b)) = self.ands[top] else {
+                return Err(AigerParseError::UndefinedVariable(top));
+            };
+            self.visiting[top] = true;
+            match [a / 2, b / 2]
+                .into_iter()
+                .find(|&v| self.nodes[v].is_none())
+            {
+                Some(operand) if self.visiting[operand] => {
+                    return Err(AigerParseError::Cycle(operand));
+                }
+                Some(operand) => stack.push(operand),
+                None => {
+                    self.visiting[top] = false;
+                    let (a, b) = (self.literal(a)?, self.literal(b)?);
+                    self.nodes[top] = Some(self.circuit.and(a, b));
+                    stack.pop();
+                }
+            }
+        }
+        Ok(self.nodes[variable].unwrap())
+    }
+}
+
+impl Circuit {
+    /// ASCII AIGER description of the circuit, as an and-inverter graph
+    ///
+    /// OR gates take one AND gate, XOR and MUX gates three.
+    pub fn to_aag(&self) -> String {
+        let inputs = self.inputs.len();
+        let mut ands: Vec<[usize; 3]> = Vec::new();
+        let mut and = |a: usize, b: usize| {
+            let literal = 2 * (inputs + ands.len() + 1);
+            ands.push([literal, a, b]);
+            literal
+        };
+        let mut literals: Vec<usize> = Vec::with_capacity(self.gates.len());
+        for gate in &self.gates {
+            let literal = match gate.map(|node| literals[node]) {
+                Gate::Input(n) => 2 * (n + 1),
+                Gate::Constant(value) => value as usize,
+                Gate::Not(a) => a ^ 1,
+                Gate::And(a, b) => and(a, b),
+                Gate::Or(a, b) => and(a ^ 1, b ^ 1) ^ 1,
+                Gate::Xor(a, b) => {
+                    let (left, right) = (and(a, b ^ 1), and(a ^ 1, b));
+                    and(left ^ 1, right ^ 1) ^ 1
+                }
+                Gate::Mux {
+                    select,
+                    then,
+                    otherwise,
+                } => {
+                    let (left, right) = (and(select, then), and(select ^ 1, otherwise));
+                    and(left ^ 1, right ^ 1) ^ 1
+                }
+            };
+            literals.push(literal);
+        }
+
+        let maximum = inputs + ands.len();
+        let outputs = self.outputs.len();
+        let mut aag = format!("aag {maximum} {inputs} 0 {outputs} {}\n", ands.len());
+        for input in 1..=inputs {
+            writeln!(aag, "{}", 2 * input).unwrap();
+        }
+        for (_, node) in self.outputs() {
+            writeln!(aag, "{}", literals[node]).unwrap();
+        }
+        for [literal, a, b] in ands {
+            writeln!(aag, "{literal} {a} {b}").unwrap();
+        }
+        for (i, (name, _)) in self.inputs().enumerate() {
+            writeln!(aag, "i{i} {name}").unwrap();
+        }
+        for (i, (name, _)) in self.outputs().enumerate() {
+            writeln!(aag, "o{i} {name}").unwrap();
+        }
+        aag
+    }
+
+    /// Circuit of an ASCII AIGER description without latches
+    ///
+    /// Inputs and outputs missing from the symbol table are named `i` and
+    /// `o` followed by their position, like their entries in it.
+    pub fn from_aag(aag: &str) -> Result<Self, AigerParseError> {
+        use AigerParseError::*;
+
+        let mut lines = aag.lines();
+        let header = lines.next().ok_or(MissingHeader)?;
+        let counts = match header.split_whitespace().collect::<Vec<_>>()[..] {
+            ["aag", ref counts @ ..] => counts
+                .iter()
+                .map(|count| count.parse::<usize>().map_err(|_| InvalidHeader))
+                .collect::<Result<Vec<_>, _>>()?,
+            _ => return Err(MissingHeader),
+        };
+        let (maximum, inputs, latches, outputs, ands) = match counts[..] {
+            [m, i, l, o, a, ref extensions @ ..] if extensions.len() <= 4 => {
+                if extensions.iter().any(|&count| count != 0) {
+                    return Err(Unsupported("AIGER 1.9 extensions".into()));
+                }
+                (m, i, l, o, a)
+            }
+            _ => return Err(InvalidHeader),
+        };
+        if latches != 0 {
+            return Err(Unsupported("latches".into()));
+        }
+        match inputs.checked_add(ands) {
+            Some(defined) if defined <= maximum => {}
+            _ => return Err(InvalidHeader),
+        }
+        let largest = maximum
+            .checked_mul(2)
+            .and_then(|literal| literal.checked_add(1))
+            .ok_or(InvalidHeader)?;
+
+        // Variables are only allocated up to the largest literal in use, so
+        // that a huge `M` does not allocate more than the file holds
+        let mut used = 1;
+
+        let mut numbers = |count: usize| {
+            let line = lines.next().ok_or(MissingLine)?;
+            let numbers = line
+                .split_whitespace()
+                .map(|number| number.parse::<usize>().map_err(|_| InvalidLiteral))
+                .collect::<Result<Vec<_>, _>>()?;
+            match numbers.len() == count && numbers.iter().all(|&n| n <= largest) {
+                true => {
+                    used = numbers.iter().fold(used, |used, &n| used.max(n / 2 + 1));
+                    Ok(numbers)
+                }
+                false => Err(InvalidLiteral),
+            }
+        };
+        let input_literals = (0..inputs)
+            .map(|_| numbers(1).map(|numbers| numbers[0]))
+            .collect::<Result<Vec<_>, _>>()?;
+        let output_literals = (0..outputs)
+            .map(|_| numbers(1).map(|numbers| numbers[0]))
+            .collect::<Result<Vec<_>, _>>()?;
+        let and_literals = (0..ands)
+            .map(|_| numbers(3))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut input_names: Vec<String> = (0..inputs).map(|i| format!("i{i}")).collect();
+        let mut output_names: Vec<String> = (0..outputs).map(|i| format!("o{i}")).collect();
+        for line in lines {
+            if line == "c" {
+                break;
+            }
+            let (position, name) = line.split_once(' ').ok_or(InvalidSymbol)?;
+            let (names, position) = match position.split_at(1.min(position.len())) {
+                ("i", position) => (&mut input_names, position),
+                ("o", position) => (&mut output_names, position),
+                _ => return Err(InvalidSymbol),
+            };
+            match position
+                .parse::<usize>()
+                .ok()
+                .and_then(|i| names.get_mut(i))
+            {
+                Some(slot) if !name.is_empty() => *slot = name.to_owned(),
+                _ => return Err(InvalidSymbol),
+            }
+        }
+
+        let mut variables = Variables {
+            circuit: Circuit::new(),
+            nodes: vec![None; used],
+            ands: vec![None; used],
+            visiting: vec![false; used],
+        };
+        variables.nodes[0] = Some(variables.circuit.constant(false));
+        for (literal, name) in input_literals.into_iter().zip(input_names) {
+            if literal < 2 || literal % 2 != 0 {
+                return Err(InvalidLiteral);
+            }
+            if variables.nodes[literal / 2].is_some() {
+                return Err(RedefinedVariable(literal / 2));
+            }
+            variables.nodes[literal / 2] = Some(variables.circuit.input(name));
+        }
+        for and in and_literals {
+            let [literal, a, b] = and[..] else {
+                unreachable!()
+            };
+            if literal < 2 || literal % 2 != 0 {
+                return Err(InvalidLiteral);
+            }
+            let variable = literal / 2;
+            if variables.nodes[variable].is_some() || variables.ands[variable].is_some() {
+                return Err(RedefinedVariable(variable));
+            }
+            variables.ands[variable] = Some((a, b));
+        }
+        for (literal, name) in output_literals.into_iter().zip(output_names) {
+            let node = variables.literal(literal)?;
+            variables.circuit.output(name, node);
+        }
+        Ok(variables.circuit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AigerParseError;
+    use crate::circuit::tests::{full_adder, truth_table};
+    use crate::circuit::Circuit;
+
+    #[test]
+    fn round_trip() {
+        let adder = full_adder();
+        let aag = adder.to_aag();
+        // Three AND gates for each XOR and the MUX, one for the AND and the OR
+        assert!(aag.starts_with("aag 14 3 0 2 11\n2\n4\n6\n"));
+        assert!(aag.ends_with("i0 a\ni1 b\ni2 c\no0 sum\no1 carry\n"));
+
+        let back = Circuit::from_aag(&aag).unwrap();
+        assert_eq!(truth_table(&back), truth_table(&adder));
+        let names: Vec<&str> = back.inputs().map(|(name, _)| name).collect();
+        assert_eq!(names, ["a", "b", "c"]);
+        // Only the gates of the outputs are read back
+        assert_eq!(back.to_aag().lines().next(), Some("aag 13 3 0 2 10"));
+    }
+
+    #[test]
+    fn parse() {
+        // Gates out of order, an inverted output and a comment
+        let aag = "aag 5 2 0 2 3\n2\n4\n11\n1\n10 9 7\n6 2 4\n8 3 5\no0 xnor\nc\nanything\n";
+        let circuit = Circuit::from_aag(aag).unwrap();
+        let names: Vec<&str> = circuit
+            .inputs()
+            .chain(circuit.outputs())
+            .map(|(n, _)| n)
+            .collect();
+        assert_eq!(names, ["i0", "i1", "xnor", "o1"]);
+        for (row, outputs) in truth_table(&circuit).into_iter().enumerate() {
+            assert_eq!(outputs, [row == 0 || row == 3, true], "{row:02b}");
+        }
+        assert_eq!(
+            Circuit::from_aag("aag 0 0 0 1 0\n0\n")
+                .unwrap()
+                .simulate::<bool>(&[]),
+            [false]
+        );
+
+        // Gaps in the variables do not need to be allocated
+        let sparse = Circuit::from_aag("aag 1099511627776 1 0 1 0\n2\n3\n").unwrap();
+        assert_eq!(sparse.simulate::<bool>(&[false]), [true]);
+    }
+
+    #[test]
+    fn errors() {
+        use AigerParseError::*;
+        let parse = |aag: &str| Circuit::from_aag(aag).unwrap_err();
+        assert_eq!(parse(""), MissingHeader);
+        assert_eq!(parse("aig 1 1 0 1 0\n2\n2\n"), MissingHeader);
+        assert_eq!(parse("aag 1 1 0 1\n2\n2\n"), InvalidHeader);
+        assert_eq!(parse("aag 1 1 0 1 1\n2\n2\n"), InvalidHeader);
+        assert_eq!(parse("aag 18446744073709551615 0 0 0 0\n"), InvalidHeader);
+        assert_eq!(parse("aag 9223372036854775808 0 0 0 0\n"), InvalidHeader);
+        assert_eq!(parse("aag 5 18446744073709551615 0 0 2\n"), InvalidHeader);
+        assert_eq!(parse("aag 1 1 0 1 0\n2\n"), MissingLine);
+        assert_eq!(parse("aag 1 1 0 1 0\n3\n2\n"), InvalidLiteral);
+        assert_eq!(parse("aag 1 1 0 1 0\n2\n4\n"), InvalidLiteral);
+        assert_eq!(parse("aag 2 1 0 1 0\n2\n4\n"), UndefinedVariable(2));
+        assert_eq!(parse("aag 2 1 0 1 1\n2\n4\n2 4 4\n"), RedefinedVariable(1));
+        assert_eq!(parse("aag 3 1 0 1 2\n2\n4\n4 6 2\n6 4 2\n"), Cycle(2));
+        assert_eq!(parse("aag 1 1 0 1 0\n2\n2\nx0 a\n"), InvalidSymbol);
+        assert_eq!(parse("aag 1 0 1 0 0\n2 3\n"), Unsupported("latches".into()));
+        assert_eq!(
+            parse("aag 1 1 0 0 0 1\n2\n2\n"),
+            Unsupported("AIGER 1.9 extensions".into())
+        );
+    }
+
+    #[test]
+    fn deep_chain() {
+        let ands = 200_000;
+        let mut aag = format!("aag {} 1 0 1 {ands}\n2\n{}\n", ands + 1, 2 * (ands + 1));
+        for n in 1..=ands {
+            aag += &format!("{} {} 2\n", 2 * (n + 1), 2 * n);
+        }
+        let circuit = Circuit::from_aag(&aag).unwrap();
+        assert_eq!(circuit.simulate::<bool>(&[true]), [true]);
+        assert_eq!(circuit.simulate::<bool>(&[false]), [false]);
+    }
+}
@@ -0,0 +1,351 @@
+use core::fmt::Write;
+use std::collections::{HashMap, HashSet};
+
+use super::{Circuit, Gate, Node};
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum BlifParseError {
+    /// Cube outside of a `.names` block
+    UnexpectedCube,
+    /// Cube of the wrong width or with other characters than `0`, `1`, `-`
+    InvalidCube,
+    /// Cover mixing cubes of the on-set and of the off-set
+    MixedCover,
+    /// Construct other than combinational `.names`, like `.latch`
+    Unsupported(String),
+    UndefinedSignal(String),
+    RedefinedSignal(String),
+    /// Signal depending on itself
+    Cycle(String),
+}
+
+/// Single-output cover of a `.names` block
+struct Cover<'a> {
+    inputs: Vec<&'a str>,
+    cubes: Vec<&'a str>,
+    /// Whether the cubes list where the output is set rather than cleared
+    on_set: bool,
+}
+
+/// Builder of the signals of a model in dependency order
+struct Signals<'a> {
+    circuit: Circuit,
+    covers: HashMap<&'a str, Cover<'a>>,
+    nodes: HashMap<&'a str, Node>,
+    visiting: HashSet<&'a str>,
+}
+
+impl<'a> Signals<'a> {
+    fn define(&mut self, name: &'a str, cover: Option<Cover<'a>>) -> Result<(), BlifParseError> {
+        match cover.map(|cover| self.covers.insert(name, cover)) {
+            Some(Some(_)) => Err(BlifParseError::RedefinedSignal(name.to_owned())),
+            _ => Ok(()),
+        }
+    }
+
+    /// Node of a signal, building the covers it depends on with an explicit
+    /// stack so that deep chains do not overflow the call stack
+    fn node(&mut self, name: &'a str) -> Result<Node, BlifParseError> {
+        let mut stack = vec![name];
+        while let Some(&top) = stack.last() {
+            if self.nodes.contains_key(top) {
+                stack.pop();
+                continue;
+            }
+            let Some(cover) = self.covers.get(top) else {
+                return Err(BlifParseError::UndefinedSignal(top.to_owned()));
+            };
+            self.visiting.insert(top);
+            let pending = cover
+                .inputs
+                .iter()
+                .find(|&&input| !self.nodes.contains_key(input));
+            match pending {
+                Some(&input) if self.visiting.contains(input) => {
+                    return Err(BlifParseError::Cycle(input.to_owned()));
+                }
+                Some(&input) => stack.push(input),
+                None => {
+                    self.visiting.remove(top);
+                    let cover = self.covers.remove(top).unwrap();
+                    self.build(top, cover);
+                    stack.pop();
+                }
+            }
+        }
+        Ok(self.nodes[name])
+    }
+
+    /// Node of a cover whose inputs are all built
+    fn build(&mut self, name: &'a str, cover: Cover<'a>) {
+        let inputs: Vec<Node> = cover.inputs.iter().map(|input| self.nodes[input]).collect();
+
+        // Sum of products, each cube being the conjunction of its literals
+        let circuit = &mut self.circuit;
+        let mut sum = None;
+        for cube in &cover.cubes {
+            let mut product = None;
+            for (&input, literal) in inputs.iter().zip(cube.bytes()) {
+                let literal = match literal {
+                    b'1' => input,
+                    b'0' => circuit.not(input),
+                    _ => continue,
+                };
+                product = Some(match product {
+                    Some(product) => circuit.and(product, literal),
+                    None => literal,
+                });
+            }
+            let product = product.unwrap_or_else(|| circuit.constant(true));
+            sum = Some(match sum {
+                Some(sum) => circuit.or(sum, product),
+                None => product,
+            });
+        }
+        let sum = sum.unwrap_or_else(|| circuit.constant(false));
+        let node = match cover.on_set {
+            true => sum,
+            false => circuit.not(sum),
+        };
+        self.nodes.insert(name, node);
+    }
+}
+
+impl Circuit {
+    /// Berkeley Logic Interchange Format description of the circuit
+    ///
+    /// Gates are named `_n` followed by their node, and outputs not named
+    /// like their node are buffers of it.
+    pub fn to_blif(&self, model: &str) -> String {
+        let name = |node: Node| match self.gates[node] {
+            Gate::Input(n) => self.inputs[n].0.clone(),
+            _ => format!("_n{node}"),
+        };
+        let names = |nodes: &mut dyn Iterator<Item = (&str, Node)>| {
+            nodes
+                .map(|(name, _)| format!(" {name}"))
+                .collect::<String>()
+        };
+
+        let mut blif = format!(".model {model}\n");
+        writeln!(blif, ".inputs{}", names(&mut self.inputs())).unwrap();
+        writeln!(blif, ".outputs{}", names(&mut self.outputs())).unwrap();
+        for (node, gate) in self.gates.iter().enumerate() {
+            let cover = match gate {
+                Gate::Input(_) => continue,
+                Gate::Constant(true) => "1\n",
+                Gate::Constant(false) => "",
+                Gate::Not(_) => "0 1\n",
+                Gate::And(..) => "11 1\n",
+                Gate::Or(..) => "1- 1\n-1 1\n",
+                Gate::Xor(..) => "01 1\n10 1\n",
+                Gate::Mux { .. } => "11- 1\n0-1 1\n",
+            };
+            let operands: String = gate.operands().map(|a| name(a) + " ").collect();
+            write!(blif, ".names {operands}{}\n{cover}", name(node)).unwrap();
+        }
+        for (output, node) in self.outputs() {
+            if name(node) != output {
+                write!(blif, ".names {} {output}\n1 1\n", name(node)).unwrap();
+            }
+        }
+        blif.push_str(".end\n");
+        blif
+    }
+
+    /// Circuit of the first combinational model of a BLIF description
+    ///
+    /// Signals unused by the outputs are left out.
+    pub fn from_blif(blif: &str) -> Result<Self, BlifParseError> {
+        use BlifParseError::*;
+
+        // Comments go to the end of the line, and `\` continues a line
+        let text: String = blif
+            .lines()
+            .map(|line| line.split('#').next().unwrap())
+            .map(|line| match line.strip_suffix('\\') {
+                Some(line) => format!("{line} "),
+                None => format!("{line}\n"),
+            })
+            .collect();
+
+        let mut signals = Signals {
+            circuit: Circuit::new(),
+            covers: HashMap::new(),
+            nodes: HashMap::new(),
+            visiting: HashSet::new(),
+        };
+        let mut outputs = Vec::new();
+        let mut cover: Option<Cover> = None;
+        let mut defined = "";
+        for line in text.lines() {
+            let tokens: Vec<&str> = line.split_whitespace().collect();
+            match tokens[..] {
+                [] => {}
+                [".model", ..] => {}
+                [".end"] => break,
+                [".inputs", ref names @ ..] => {
+                    for &name in names {
+                        let node = signals.circuit.input(name);
+                        if signals.nodes.insert(name, node).is_some() {
+                            return Err(RedefinedSignal(name.to_owned()));
+                        }
+                    }
+                }
+                [".outputs", ref names @ ..] => outputs.extend_from_slice(names),
+                [".names", ref names @ ..] => {
+                    signals.define(defined, cover.take())?;
+                    let Some((&output, inputs)) = names.split_last() else {
+                        return Err(InvalidCube);
+                    };
+                    defined = output;
+                    cover = Some(Cover {
+                        inputs: inputs.to_vec(),
+                        cubes: Vec::new(),
+                        on_set: true,
+                    });
+                }
+                [command, ..] if command.starts_with('.') => {
+                    return Err(Unsupported(command.to_owned()))
+                }
+                _ => {
+                    let Some(cover) = cover.as_mut() else {
+                        return Err(UnexpectedCube);
+                    };
+                    let (cube, value) = match tokens[..] {
+                        [value] if cover.inputs.is_empty() => ("", value),
+                        [cube, value] => (cube, value),
+                        _ => return Err(InvalidCube),
+                    };
+                    let on_set = match value {
+                        "1" => true,
+                        "0" => false,
+                        _ => return Err(InvalidCube),
+                    };
+                    if cube.len() != cover.inputs.len()
+                        || !cube.bytes().all(|c| matches!(c, b'0' | b'1' | b'-'))
+                    {
+                        return Err(InvalidCube);
+                    }
+                    if !cover.cubes.is_empty() && on_set != cover.on_set {
+                        return Err(MixedCover);
+                    }
+                    cover.on_set = on_set;
+                    cover.cubes.push(cube);
+                }
+            }
+        }
+        signals.define(defined, cover.take())?;
+        if let Some(input) = signals
+            .nodes
+            .keys()
+            .find(|&i| signals.covers.contains_key(i))
+        {
+            return Err(RedefinedSignal(input.to_string()));
+        }
+
+        for output in outputs {
+            let node = signals.node(output)?;
+            signals.circuit.output(output, node);
+        }
+        Ok(signals.circuit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BlifParseError;
+    use crate::circuit::tests::{full_adder, truth_table};
+    use crate::circuit::{Circuit, Gate};
+
+    #[test]
+    fn round_trip() {
+        let adder = full_adder();
+        let blif = adder.to_blif("adder");
+        assert!(blif.starts_with(".model adder\n.inputs a b c\n.outputs sum carry\n"));
+        assert!(blif.contains(".names a b _n3\n01 1\n10 1\n"));
+        assert!(blif.contains(".names _n6 carry\n1 1\n"));
+
+        let back = Circuit::from_blif(&blif).unwrap();
+        assert_eq!(truth_table(&back), truth_table(&adder));
+        let names: Vec<&str> = back.outputs().map(|(name, _)| name).collect();
+        assert_eq!(names, ["sum", "carry"]);
+        // Covers are read back as sums of products
+        assert!(back
+            .gates()
+            .iter()
+            .all(|gate| !matches!(gate, Gate::Xor(..) | Gate::Mux { .. })));
+    }
+
+    #[test]
+    fn parse() {
+        let blif = "\
+# Majority, described out of order
+.model majority
+.inputs a b \\
+  c
+.outputs m n
+.names ab bc ca m
+1-- 1
+-1- 1
+--1 1
+.names a b ab
+11 1
+.names b c bc
+11 1
+.names c a ca
+11 1
+.names m n   # off-set cover
+1 0
+.end
+";
+        let circuit = Circuit::from_blif(blif).unwrap();
+        for (row, outputs) in truth_table(&circuit).into_iter().enumerate() {
+            let majority = row.count_ones() >= 2;
+            assert_eq!(outputs, [majority, !majority], "{row:03b}");
+        }
+
+        let constants = ".model c\n.inputs\n.outputs one zero\n.names one\n1\n.names zero\n.end";
+        let circuit = Circuit::from_blif(constants).unwrap();
+        assert_eq!(circuit.simulate::<bool>(&[]), [true, false]);
+    }
+
+    #[test]
+    fn errors() {
+        use BlifParseError::*;
+        let parse = |blif: &str| Circuit::from_blif(blif).unwrap_err();
+        let model = ".model m\n.inputs a\n.outputs x\n";
+        assert_eq!(parse(&format!("{model}.names a x\n11 1\n")), InvalidCube);
+        assert_eq!(parse(&format!("{model}.names a x\n2 1\n")), InvalidCube);
+        assert_eq!(parse(&format!("{model}.names a x\n1 1\n0 0\n")), MixedCover);
+        assert_eq!(
+            parse(&format!("{model}.names y x\n1 1\n")),
+            UndefinedSignal("y".into())
+        );
+        assert_eq!(
+            parse(&format!("{model}.names a x\n1 1\n.names a x\n0 1\n")),
+            RedefinedSignal("x".into())
+        );
+        assert_eq!(
+            parse(&format!("{model}.names x y\n1 1\n.names y x\n1 1\n")),
+            Cycle("x".into())
+        );
+        assert_eq!(
+            parse(&format!("{model}.latch a x 0\n")),
+            Unsupported(".latch".into())
+        );
+        assert_eq!(parse(&format!("{model}1 1\n")), UnexpectedCube);
+    }
+
+    #[test]
+    fn deep_chain() {
+        let signals = 200_000;
+        let mut blif = format!(".model chain\n.inputs s0\n.outputs s{signals}\n");
+        for n in 0..signals {
+            blif += &format!(".names s{n} s{}\n1 1\n", n + 1);
+        }
+        let circuit = Circuit::from_blif(&blif).unwrap();
+        assert_eq!(circuit.simulate::<bool>(&[true]), [true]);
+        assert_eq!(circuit.simulate::<bool>(&[false]), [false]);
+    }
+}
@@ -0,0 +1,433 @@
+use core::fmt;
+
+use crate::expression::{Environment, Expression, Symbol};
+
+pub type Variable = u32;
+
+/// A variable or its negation, packed as `variable << 1 | negated`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Literal(u32);
+
+impl Literal {
+    /// Largest variable a literal packs along with its sign
+    pub const MAX_VARIABLE: Variable = Variable::MAX >> 1;
+
+    pub fn new(variable: Variable, value: bool) -> Self {
+        Self(variable << 1 | !value as u32)
+    }
+
+    pub fn positive(variable: Variable) -> Self {
+        Self::new(variable, true)
+    }
+
+    pub fn negative(variable: Variable) -> Self {
+        Self::new(variable, false)
+    }
+
+    /// Literal of the variable backing an expression symbol
+    pub fn symbol(symbol: Symbol) -> Self {
+        Self::positive(symbol_variable(symbol))
+    }
+
+    pub fn variable(self) -> Variable {
+        self.0 >> 1
+    }
+
+    pub fn is_negative(self) -> bool {
+        self.0 & 1 != 0
+    }
+
+    /// Value the variable must take for the literal to hold
+    pub fn value(self) -> bool {
+        !self.is_negative()
+    }
+
+    /// Dense index in `0..2 * variables`, usable to address per-literal tables
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+
+    pub fn eval(self, model: &[bool]) -> bool {
+        model[self.variable() as usize] == self.value()
+    }
+
+    /// Literal from its DIMACS encoding, `None` for the `0` terminator and
+    /// variables past `MAX_VARIABLE`
+    pub fn from_dimacs(value: i64) -> Option<Self> {
+        let variable = Variable::try_from(value.unsigned_abs().checked_sub(1)?).ok()?;
+        (variable <= Self::MAX_VARIABLE).then(|| Self::new(variable, value > 0))
+    }
+}
+
+impl core::ops::Not for Literal {
+    type Output = Literal;
+
+    fn not(self) -> Self::Output {
+        Self(self.0 ^ 1)
+    }
+}
+
+impl fmt::Display for Literal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.is_negative() {
+            true => write!(f, "-{}", self.variable() + 1),
+            false => write!(f, "{}", self.variable() + 1),
+        }
+    }
+}
+
+pub fn symbol_variable(symbol: Symbol) -> Variable {
+    (symbol - b'A') as Variable
+}
+
+pub type Clause = Vec<Literal>;
+
+/// Syntactic classes of CNF with a dedicated polynomial algorithm
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Class {
+    /// Every clause has at most two literals
+    TwoSat,
+    /// Every clause has at most one positive literal
+    Horn,
+    /// Every clause has at most one negative literal
+    DualHorn,
+    General,
+}
+
+/// Conjunction of clauses over variables `0..variables`
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Cnf {
+    clauses: Vec<Clause>,
+    variables: u32,
+}
+
+impl Cnf {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Empty formula over variables `0..variables`
+    pub fn with_variables(variables: u32) -> Self {
+        Self {
+            clauses: Vec::new(),
+            variables,
+        }
+    }
+
+    pub fn variables(&self) -> u32 {
+        self.variables
+    }
+
+    pub fn clauses(&self) -> &[Clause] {
+        &self.clauses
+    }
+
+    pub fn fresh_variable(&mut self) -> Variable {
+        self.variables += 1;
+        self.variables - 1
+    }
+
+    /// Add a clause, sorting and deduplicating its literals
+    pub fn add_clause(&mut self, clause: impl IntoIterator<Item = Literal>) {
+        let mut clause: Clause = clause.into_iter().collect();
+        clause.sort_unstable();
+        clause.dedup();
+        if let Some(max) = clause.iter().map(|l| l.variable() + 1).max() {
+            self.variables = self.variables.max(max);
+        }
+        self.clauses.push(clause);
+    }
+
+    pub fn class(&self) -> Class {
+        let counts = |negative| {
+            self.clauses
+                .iter()
+                .map(move |c| c.iter().filter(|l| l.is_negative() == negative).count())
+        };
+        if self.clauses.iter().all(|c| c.len() <= 2) {
+            Class::TwoSat
+        } else if counts(false).all(|n| n <= 1) {
+            Class::Horn
+        } else if counts(true).all(|n| n <= 1) {
+            Class::DualHorn
+        } else {
+            Class::General
+        }
+    }
+
+    pub fn eval(&self, model: &[bool]) -> bool {
+        self.clauses
+            .iter()
+            .all(|clause| clause.iter().any(|l| l.eval(model)))
+    }
+
+    /// Tseitin encoding, defining a fresh variable for each connective so
+    /// that the formula stays linear in the size of the expression
+    ///
+    /// Symbols keep the variables of `From<&Expression>`, and the formula is
+    /// only equisatisfiable with the expression over them.
+    pub fn tseitin(expression: &Expression) -> Self {
+        let symbols = expression.symbols().last();
+        let mut cnf = Cnf::with_variables(symbols.map_or(0, |s| symbol_variable(s) + 1));
+        cnf.add_tseitin(expression);
+        cnf
+    }
+
+    /// Add the Tseitin encoding of an expression, whose symbols must all be
+    /// below `variables` so that definitions do not reuse their variables
+    pub(crate) fn add_tseitin(&mut self, expression: &Expression) {
+        let root = self.define(expression);
+        self.add_clause([root]);
+    }
+
+    /// Literal equivalent to the expression, adding the clauses defining it
+    fn define(&mut self, expression: &Expression) -> Literal {
+        use Expression::*;
+
+        let (a, b) = match expression {
+            &Var(symbol) => return Literal::symbol(symbol),
+            &Val(value) => {
+                let x = Literal::positive(self.fresh_variable());
+                self.add_clause([Literal::new(x.variable(), value)]);
+                return x;
+            }
+            Not(x) => return !self.define(x),
+            And(a, b) | Or(a, b) | Xor(a, b) | Implies(a, b) | Equivalent(a, b) => {
+                (self.define(a), self.define(b))
+            }
+        };
+        let g = Literal::positive(self.fresh_variable());
+        let clauses: &[&[Literal]] = match expression {
+            And(..) => &[&[!g, a], &[!g, b], &[g, !a, !b]],
+            Or(..) => &[&[g, !a], &[g, !b], &[!g, a, b]],
+            Implies(..) => &[&[g, a], &[g, !b], &[!g, !a, b]],
+            Xor(..) => &[&[!g, a, b], &[!g, !a, !b], &[g, !a, b], &[g, a, !b]],
+            _ => &[&[g, a, b], &[g, !a, !b], &[!g, !a, b], &[!g, a, !b]],
+        };
+        for clause in clauses {
+            self.add_clause(clause.iter().copied());
+        }
+        g
+    }
+}
+
+impl From<&Expression> for Cnf {
+    /// Distribute the negation normal form into clauses
+    ///
+    /// Variables are the expression symbols, `A` being variable `0`.
+    fn from(expression: &Expression) -> Self {
+        use Expression::*;
+
+        fn clauses(expr: &Expression) -> Vec<Clause> {
+            match expr {
+                Val(true) => vec![],
+                Val(false) => vec![vec![]],
+                &Var(symbol) => vec![vec![Literal::symbol(symbol)]],
+                Not(x) => match **x {
+                    Var(symbol) => vec![vec![!Literal::symbol(symbol)]],
+                    _ => unreachable!("Expression is not in negation normal form"),
+                },
+                And(a, b) => {
+                    let mut result = clauses(a);
+                    result.extend(clauses(b));
+                    result
+                }
+                Or(a, b) => {
+                    let b = clauses(b);
+                    clauses(a)
+                        .into_iter()
+                        .flat_map(|x| b.iter().map(move |y| [x.as_slice(), y].concat()))
+                        .collect()
+                }
+                Xor(..) | Implies(..) | Equivalent(..) => {
+                    unreachable!("Expression is not in negation normal form")
+                }
+            }
+        }
+
+        let mut cnf = Cnf::new();
+        for clause in clauses(&expression.negation_normal()) {
+            cnf.add_clause(clause);
+        }
+        cnf
+    }
+}
+
+impl fmt::Display for Cnf {
+    /// DIMACS representation
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "p cnf {} {}", self.variables, self.clauses.len())?;
+        for clause in &self.clauses {
+            for literal in clause {
+                write!(f, "{literal} ")?;
+            }
+            writeln!(f, "0")?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum DimacsParseError {
+    MissingHeader,
+    InvalidHeader,
+    InvalidLiteral,
+    /// Literal over a variable past the count of the header
+    UndeclaredVariable,
+    /// Number of clauses other than the count of the header
+    ClauseCount,
+    UnterminatedClause,
+}
+
+impl core::str::FromStr for Cnf {
+    type Err = DimacsParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        use DimacsParseError::*;
+
+        let mut lines = s
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('c'));
+        let header = lines.next().ok_or(MissingHeader)?;
+        let (variables, clauses) = match header.split_whitespace().collect::<Vec<_>>()[..] {
+            ["p", "cnf", variables, clauses] => (
+                variables.parse::<Variable>().map_err(|_| InvalidHeader)?,
+                clauses.parse::<usize>().map_err(|_| InvalidHeader)?,
+            ),
+            ["p", ..] => return Err(InvalidHeader),
+            _ => return Err(MissingHeader),
+        };
+        if variables > Literal::MAX_VARIABLE + 1 {
+            return Err(InvalidHeader);
+        }
+
+        let mut cnf = Cnf::with_variables(variables);
+        let mut clause = Vec::new();
+        for token in lines.flat_map(str::split_whitespace) {
+            let value: i64 = token.parse().map_err(|_| InvalidLiteral)?;
+            if value == 0 {
+                cnf.add_clause(clause.drain(..));
+                continue;
+            }
+            let literal = Literal::from_dimacs(value).ok_or(InvalidLiteral)?;
+            if literal.variable() >= variables {
+                return Err(UndeclaredVariable);
+            }
+            clause.push(literal);
+        }
+        if !clause.is_empty() {
+            return Err(UnterminatedClause);
+        }
+        if cnf.clauses().len() != clauses {
+            return Err(ClauseCount);
+        }
+        Ok(cnf)
+    }
+}
+
+/// Restrict a model to the symbols used by an expression
+pub fn model_environment(expression: &Expression, model: &[bool]) -> Environment {
+    let mut env = Environment::default();
+    for symbol in expression.symbols() {
+        let value = model
+            .get(symbol_variable(symbol) as usize)
+            .copied()
+            .unwrap_or_default();
+        env.set(symbol, value);
+    }
+    env
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{symbol_variable, Class, Cnf, Literal};
+    use crate::expression::Expression;
+    use crate::solver::Solver;
+
+    fn cnf(formula: &str) -> Cnf {
+        let expression: Expression = formula.parse().unwrap();
+        Cnf::from(&expression)
+    }
+
+    #[test]
+    fn from_expression() {
+        let a = Literal::symbol(b'A');
+        let b = Literal::symbol(b'B');
+        let c = Literal::symbol(b'C');
+        assert_eq!(cnf("AB&").clauses(), &[vec![a], vec![b]]);
+        assert_eq!(cnf("AB|!").clauses(), &[vec![!a], vec![!b]]);
+        assert_eq!(cnf("AB&C|").clauses(), &[vec![a, c], vec![b, c]]);
+        assert_eq!(cnf("A1|").clauses(), &[] as &[Vec<Literal>]);
+        assert_eq!(cnf("A0&").clauses(), &[vec![a], vec![]]);
+        assert_eq!(cnf("AB&C|").variables(), 3);
+    }
+
+    #[test]
+    fn equisatisfiable() {
+        for formula in ["AB^", "AB=C>", "AB|C&!", "ABC^^", "AB>A>A>"] {
+            let expression: Expression = formula.parse().unwrap();
+            let cnf = Cnf::from(&expression);
+            for env in expression.envs() {
+                let model: Vec<bool> = (b'A'..=b'Z').map(|s| env.get(s).unwrap_or(false)).collect();
+                assert_eq!(cnf.eval(&model), expression.eval(env), "{formula}");
+            }
+        }
+    }
+
+    #[test]
+    fn tseitin() {
+        for formula in ["AB^", "AB=C>", "AB|C&!", "ABC^^", "AB>A>A>", "A1&", "C0|"] {
+            let expression: Expression = formula.parse().unwrap();
+            let cnf = Cnf::tseitin(&expression);
+            for env in expression.envs() {
+                // Assumptions on the symbols propagate to the definitions
+                let mut solver = Solver::from(cnf.clone());
+                let assumptions: Vec<Literal> = env
+                    .symbols()
+                    .map(|s| Literal::new(symbol_variable(s), env.get(s).unwrap()))
+                    .collect();
+                let model = solver.solve_with_assumptions(&assumptions);
+                assert_eq!(model.is_some(), expression.eval(env), "{formula}");
+            }
+        }
+        assert_eq!(Cnf::tseitin(&"AB&C|".parse().unwrap()).clauses().len(), 7);
+        assert!(!Cnf::tseitin(&"AA!&".parse().unwrap()).clauses().is_empty());
+    }
+
+    #[test]
+    fn dimacs() {
+        use super::DimacsParseError::*;
+
+        let cnf: Cnf = "c comment\np cnf 3 2\n1 -3 0\n2 3\n-1 0\n".parse().unwrap();
+        assert_eq!(
+            cnf.to_string().parse::<Cnf>().unwrap().clauses(),
+            cnf.clauses()
+        );
+        assert_eq!(cnf.clauses().len(), 2);
+
+        let parse = |text: &str| text.parse::<Cnf>().unwrap_err();
+        assert_eq!(parse("1 2 0\n"), MissingHeader);
+        assert_eq!(parse("p dnf 3 2\n"), InvalidHeader);
+        assert_eq!(parse("p cnf 3\n"), InvalidHeader);
+        assert_eq!(parse("p cnf 3 x\n"), InvalidHeader);
+        assert_eq!(parse("p cnf 1 1\n1 x 0\n"), InvalidLiteral);
+        assert_eq!(parse("p cnf 1 1\n4294967297 0\n"), InvalidLiteral);
+        assert_eq!(parse("p cnf 1 1\n-2147483649 0\n"), InvalidLiteral);
+        assert_eq!(parse("p cnf 2 1\n1 2\n"), UnterminatedClause);
+        assert_eq!(parse("p cnf 1 1\n2147483647 0\n"), UndeclaredVariable);
+        assert_eq!(parse("p cnf 2 1\n1 -3 0\n"), UndeclaredVariable);
+        assert_eq!(parse("p cnf 1 2\n1 0\n"), ClauseCount);
+        assert_eq!(parse("p cnf 1 1\n1 0\n-1 0\n"), ClauseCount);
+        assert_eq!(parse("p cnf 2147483649 0\n"), InvalidHeader);
+        assert!(Literal::from_dimacs(2147483648).is_some());
+    }
+
+    #[test]
+    fn class() {
+        assert_eq!(cnf("AB|C&").class(), Class::TwoSat);
+        assert_eq!(cnf("A!B!C||").class(), Class::Horn);
+        assert_eq!(cnf("A!BC||A&").class(), Class::DualHorn);
+        assert_eq!(cnf("ABC||A!B!C!||&").class(), Class::General);
+    }
+}
@@ -1,3 +1,8 @@
+pub mod hilbert;
+pub mod morton;
+
+pub use hilbert::Hilbert;
+pub use morton::Morton;
 
 /// Create a Z-order curve mapping
 pub fn map(x: u16, y: u16) -> f64 {
@@ -27,8 +32,88 @@ pub fn reverse_map(n: f64) -> (u16, u16) {
     (x, y)
 }
 
+/// Bijection between the points of a grid and their positions along a
+/// curve visiting each of them once
+pub trait SpaceFillingCurve {
+    /// Number of coordinates of the points
+    fn dimensions(&self) -> usize;
+
+    /// Bits of each coordinate
+    fn width(&self) -> u32;
+
+    /// Position of the point along the curve
+    fn index(&self, point: &[u64]) -> u128;
+
+    /// Point at a position along the curve
+    fn point(&self, index: u128) -> Vec<u64>;
+
+    /// Bits of the positions along the curve
+    fn key_bits(&self) -> u32 {
+        self.dimensions() as u32 * self.width()
+    }
+
+    fn index_u64(&self, point: &[u64]) -> u64 {
+        assert!(self.key_bits() <= u64::BITS, "Key wider than 64 bits");
+        self.index(point) as u64
+    }
+
+    fn point_u64(&self, index: u64) -> Vec<u64> {
+        self.point(index as u128)
+    }
+
+    /// Position along the curve scaled to `[0, 1]`, rounded to the
+    /// precision of `f64`
+    fn map(&self, point: &[u64]) -> f64 {
+        self.index(point) as f64 / last_index(self.key_bits()) as f64
+    }
+
+    /// Point at a position along the curve scaled to `[0, 1]`
+    fn reverse_map(&self, n: f64) -> Vec<u64> {
+        assert!((0.0..=1.0).contains(&n), "Position out of [0, 1]");
+        let last = last_index(self.key_bits());
+        // Saturates past the last index when rounding up
+        self.point(((n * last as f64) as u128).min(last))
+    }
+}
+
+fn last_index(bits: u32) -> u128 {
+    u128::MAX >> (u128::BITS - bits)
+}
+
+/// Panics unless the curve has room for its keys
+fn check_shape(dimensions: usize, width: u32) {
+    assert!(dimensions >= 1, "Curve without dimensions");
+    assert!(
+        (1..=u64::BITS).contains(&width),
+        "Coordinate width out of range"
+    );
+    assert!(
+        dimensions as u128 * width as u128 <= u128::BITS as u128,
+        "Key wider than 128 bits"
+    );
+}
+
+/// Panics unless the point is on the grid of the curve
+fn check_point(point: &[u64], dimensions: usize, width: u32) {
+    assert_eq!(point.len(), dimensions, "Point of the wrong dimension");
+    assert!(
+        point.iter().all(|&a| width == u64::BITS || a >> width == 0),
+        "Coordinate wider than the curve"
+    );
+}
+
+/// Panics unless the index is a position along the curve
+fn check_index(index: u128, key_bits: u32) {
+    assert!(
+        key_bits == u128::BITS || index >> key_bits == 0,
+        "Index wider than the curve"
+    );
+}
+
 #[cfg(test)]
 mod tests {
+    use super::{Morton, SpaceFillingCurve};
+
     #[test]
     fn reversible() {
         for x in (0..=u16::MAX).step_by(132) {
@@ -38,4 +123,20 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn normalized() {
+        let curve = Morton::new(2, 16);
+        for x in (0..=u16::MAX).step_by(1021) {
+            for y in (0..=u16::MAX).step_by(773) {
+                let n = super::map(x, y);
+                assert_eq!(curve.map(&[x as u64, y as u64]), n);
+                assert_eq!(curve.reverse_map(n), [x as u64, y as u64]);
+            }
+        }
+        let wide = Morton::new(2, 64);
+        assert_eq!(wide.map(&[u64::MAX; 2]), 1.0);
+        assert_eq!(wide.reverse_map(1.0), [u64::MAX; 2]);
+        assert_eq!(wide.reverse_map(0.0), [0; 2]);
+    }
 }
@@ -0,0 +1,158 @@
+use super::{check_index, check_point, check_shape, SpaceFillingCurve};
+
+/// Hilbert curve, consecutive points of which are neighbors on the grid,
+/// after Skilling's "Programming the Hilbert curve"
+///
+/// The key is that of a Morton curve over the transposed index, the first
+/// coordinate taking the most significant bit of each group.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Hilbert {
+    dimensions: usize,
+    width: u32,
+}
+
+impl Hilbert {
+    pub fn new(dimensions: usize, width: u32) -> Self {
+        check_shape(dimensions, width);
+        Self { dimensions, width }
+    }
+
+    /// Bit `i` of each coordinate as a group, the first coordinate first
+    fn interleave(&self, transposed: &[u64]) -> u128 {
+        let mut index = 0;
+        for i in (0..self.width).rev() {
+            for &a in transposed {
+                index = index << 1 | (a >> i & 1) as u128;
+            }
+        }
+        index
+    }
+
+    fn transpose(&self, index: u128) -> Vec<u64> {
+        check_index(index, self.key_bits());
+        let mut transposed = vec![0; self.dimensions];
+        let mut bit = self.key_bits();
+        for i in (0..self.width).rev() {
+            for a in &mut transposed {
+                bit -= 1;
+                *a |= ((index >> bit & 1) as u64) << i;
+            }
+        }
+        transposed
+    }
+}
+
+/// Swaps the bits below `q` of the first coordinate and another one, or
+/// flips those of the first coordinate when the other has bit `q` set
+fn exchange(x: &mut [u64], i: usize, q: u64) {
+    let p = q - 1;
+    if x[i] & q != 0 {
+        x[0] ^= p;
+    } else {
+        let t = (x[0] ^ x[i]) & p;
+        x[0] ^= t;
+        x[i] ^= t;
+    }
+}
+
+impl SpaceFillingCurve for Hilbert {
+    fn dimensions(&self) -> usize {
+        self.dimensions
+    }
+
+    fn width(&self) -> u32 {
+        self.width
+    }
+
+    fn index(&self, point: &[u64]) -> u128 {
+        check_point(point, self.dimensions, self.width);
+        let mut x = point.to_vec();
+        // Inverse undo of the rotations and reflections
+        for q in (1..self.width).rev().map(|i| 1 << i) {
+            for i in 0..x.len() {
+                exchange(&mut x, i, q);
+            }
+        }
+        // Gray encode
+        for i in 1..x.len() {
+            x[i] ^= x[i - 1];
+        }
+        let last = x[x.len() - 1];
+        let t = (1..self.width)
+            .map(|i| 1 << i)
+            .filter(|q| last & q != 0)
+            .fold(0, |t, q: u64| t ^ (q - 1));
+        for a in &mut x {
+            *a ^= t;
+        }
+        self.interleave(&x)
+    }
+
+    fn point(&self, index: u128) -> Vec<u64> {
+        let mut x = self.transpose(index);
+        // Gray decode
+        let t = x[x.len() - 1] >> 1;
+        for i in (1..x.len()).rev() {
+            x[i] ^= x[i - 1];
+        }
+        x[0] ^= t;
+        // Undo the excess work
+        for q in (1..self.width).map(|i| 1 << i) {
+            for i in (0..x.len()).rev() {
+                exchange(&mut x, i, q);
+            }
+        }
+        x
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Hilbert, SpaceFillingCurve};
+
+    #[test]
+    fn neighbors() {
+        for (dimensions, width) in [(1, 5), (2, 1), (2, 4), (3, 3), (4, 2)] {
+            let curve = Hilbert::new(dimensions, width);
+            let points: Vec<Vec<u64>> = (0..1 << curve.key_bits())
+                .map(|index| curve.point(index))
+                .collect();
+            for (index, point) in points.iter().enumerate() {
+                assert_eq!(curve.index(point), index as u128);
+            }
+            for pair in points.windows(2) {
+                let distance: u64 = pair[0]
+                    .iter()
+                    .zip(&pair[1])
+                    .map(|(a, b)| a.abs_diff(*b))
+                    .sum();
+                assert_eq!(distance, 1, "{pair:?}");
+            }
+            assert_eq!(points[0], vec![0; dimensions]);
+        }
+        assert_eq!(
+            (0..4)
+                .map(|index| Hilbert::new(2, 1).point(index))
+                .collect::<Vec<_>>(),
+            [[0, 0], [0, 1], [1, 1], [1, 0]]
+        );
+    }
+
+    #[test]
+    fn wide() {
+        let curve = Hilbert::new(2, 64);
+        for point in [[0, 0], [u64::MAX, 0], [0x0123_4567_89ab_cdef, u64::MAX]] {
+            assert_eq!(curve.point(curve.index(&point)), point);
+        }
+        assert_eq!(curve.index(&[u64::MAX, 0]), u128::MAX);
+        let space = Hilbert::new(3, 21);
+        let point = [0x1f_ffff, 0x12_3456, 7];
+        assert_eq!(space.point_u64(space.index_u64(&point)), point);
+    }
+
+    #[test]
+    #[should_panic]
+    fn index_too_wide() {
+        Hilbert::new(2, 4).point(1 << 8);
+    }
+}
@@ -0,0 +1,93 @@
+use super::{check_index, check_point, check_shape, SpaceFillingCurve};
+
+/// Z-order curve, interleaving the bits of the coordinates: bit `i` of
+/// coordinate `d` is bit `i * dimensions + d` of the key
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Morton {
+    dimensions: usize,
+    width: u32,
+}
+
+impl Morton {
+    pub fn new(dimensions: usize, width: u32) -> Self {
+        check_shape(dimensions, width);
+        Self { dimensions, width }
+    }
+}
+
+impl SpaceFillingCurve for Morton {
+    fn dimensions(&self) -> usize {
+        self.dimensions
+    }
+
+    fn width(&self) -> u32 {
+        self.width
+    }
+
+    fn index(&self, point: &[u64]) -> u128 {
+        check_point(point, self.dimensions, self.width);
+        let mut index = 0;
+        for i in (0..self.width).rev() {
+            for &a in point.iter().rev() {
+                index = index << 1 | (a >> i & 1) as u128;
+            }
+        }
+        index
+    }
+
+    fn point(&self, index: u128) -> Vec<u64> {
+        check_index(index, self.key_bits());
+        let mut point = vec![0; self.dimensions];
+        for bit in 0..self.key_bits() {
+            let (i, d) = (bit / self.dimensions as u32, bit as usize % self.dimensions);
+            point[d] |= ((index >> bit & 1) as u64) << i;
+        }
+        point
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Morton, SpaceFillingCurve};
+
+    #[test]
+    fn interleaved() {
+        let curve = Morton::new(3, 4);
+        assert_eq!(curve.index(&[0b0001, 0b0000, 0b0000]), 0b001);
+        assert_eq!(curve.index(&[0b0000, 0b0001, 0b0000]), 0b010);
+        assert_eq!(curve.index(&[0b1000, 0b0100, 0b0010]), 0b001_010_100_000);
+        for index in 0..1 << curve.key_bits() {
+            assert_eq!(curve.index(&curve.point(index)), index);
+        }
+
+        // Same key as the bit tricks of `map`
+        let plane = Morton::new(2, 16);
+        assert_eq!(plane.index(&[0xffff, 0]), 0x5555_5555);
+        assert_eq!(plane.index_u64(&[0x1234, 0xabcd]), 0x898e_a5b2);
+        assert_eq!(plane.point_u64(0x898e_a5b2), [0x1234, 0xabcd]);
+
+        let wide = Morton::new(5, 25);
+        let point = [0x1ab_cdef, 0, 0x1ff_ffff, 0x123_4567, 1];
+        assert_eq!(wide.point(wide.index(&point)), point);
+        let line = Morton::new(1, 64);
+        assert_eq!(line.index(&[u64::MAX]), u64::MAX as u128);
+    }
+
+    #[test]
+    #[should_panic]
+    fn key_too_wide() {
+        Morton::new(3, 43);
+    }
+
+    #[test]
+    #[should_panic]
+    fn coordinate_too_wide() {
+        Morton::new(2, 4).index(&[16, 0]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn index_too_wide() {
+        Morton::new(2, 4).point(1 << 8);
+    }
+}
@@ -1,3 +1,5 @@
+use crate::expression::{Expression, ExpressionParseError};
+
 #[derive(Debug, PartialEq, Eq)]
 pub enum EvaluationError {
     MissingArgument,
@@ -5,47 +7,17 @@ pub enum EvaluationError {
     UnknownSymbol,
 }
 
+/// Evaluate a formula made of constants only
 pub fn try_evaluate(expression: &str) -> Result<bool, EvaluationError> {
-    use EvaluationError::*;
-    let mut stack = Vec::<bool>::new();
-    for symbol in expression.bytes() {
-        let result = match symbol {
-            b'0' => false,
-            b'1' => true,
-            b'!' => !(stack.pop().ok_or(MissingArgument)?),
-            b'|' => {
-                let b = stack.pop().ok_or(MissingArgument)?;
-                let a = stack.pop().ok_or(MissingArgument)?;
-                a || b
-            }
-            b'&' => {
-                let b = stack.pop().ok_or(MissingArgument)?;
-                let a = stack.pop().ok_or(MissingArgument)?;
-                a && b
-            }
-            b'^' => {
-                let b = stack.pop().ok_or(MissingArgument)?;
-                let a = stack.pop().ok_or(MissingArgument)?;
-                a ^ b
-            }
-            b'>' => {
-                let b = stack.pop().ok_or(MissingArgument)?;
-                let a = stack.pop().ok_or(MissingArgument)?;
-                a <= b
-            }
-            b'=' => {
-                let b = stack.pop().ok_or(MissingArgument)?;
-                let a = stack.pop().ok_or(MissingArgument)?;
-                a == b
-            }
-            _ => return Err(UnknownSymbol),
-        };
-        stack.push(result)
-    }
-    if stack.len() != 1 {
-        return Err(IncompleteComputation);
-    }
-    Ok(stack.pop().unwrap())
+    let expression: Expression = expression.parse().map_err(|err| match err {
+        ExpressionParseError::MissingArgument => EvaluationError::MissingArgument,
+        ExpressionParseError::IncompleteComputation => EvaluationError::IncompleteComputation,
+        ExpressionParseError::UnknownSymbol => EvaluationError::UnknownSymbol,
+    })?;
+    // Variables are not part of the grammar here
+    expression
+        .interpret::<bool>(&|_| None)
+        .ok_or(EvaluationError::UnknownSymbol)
 }
 
 #[cfg(test)]
@@ -59,5 +31,17 @@ mod tests {
         assert_eq!(super::try_evaluate("11>"), Ok(true));
         assert_eq!(super::try_evaluate("10="), Ok(false));
         assert_eq!(super::try_evaluate("1011||="), Ok(true));
+        assert_eq!(
+            super::try_evaluate("1&"),
+            Err(super::EvaluationError::MissingArgument)
+        );
+        assert_eq!(
+            super::try_evaluate("11"),
+            Err(super::EvaluationError::IncompleteComputation)
+        );
+        assert_eq!(
+            super::try_evaluate("A1|"),
+            Err(super::EvaluationError::UnknownSymbol)
+        );
     }
 }
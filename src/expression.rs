@@ -1,14 +1,24 @@
 use core::fmt;
 use std::io;
 
+use crate::algebra::BooleanAlgebra;
+use crate::cnf::{self, Cnf};
+use crate::proof;
+use crate::solver;
+
 pub type Symbol = u8;
 
-const SYMBOL_RANGE: core::ops::RangeInclusive<u8> = b'A'..=b'Z';
+/// Most clauses `model` distributes an expression into before switching to
+/// the Tseitin encoding
+const DISTRIBUTION_LIMIT: usize = 1 << 12;
+
+pub(crate) const SYMBOL_RANGE: core::ops::RangeInclusive<u8> = b'A'..=b'Z';
 
 /// Expression representation
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Expression {
-    Var(Symbol),
+    Var(#[cfg_attr(feature = "serde", serde(with = "crate::serialize::symbol"))] Symbol),
     Val(bool),
     Not(Box<Expression>),
     Or(Box<Expression>, Box<Expression>),
@@ -121,8 +131,9 @@ impl Expression {
         Self::Equivalent(a.into(), b.into())
     }
 
-    pub fn envs(&self) -> impl Iterator<Item=Environment> {
-        pub fn rec(expr: &Expression, env: &mut Environment) {
+    /// Environment with every symbol of the expression enabled and unset
+    fn symbol_mask(&self) -> Environment {
+        fn rec(expr: &Expression, env: &mut Environment) {
             use Expression::*;
             match expr {
                 Val(_value) => {}
@@ -136,7 +147,16 @@ impl Expression {
         }
         let mut env = Environment::default();
         rec(self, &mut env);
+        env
+    }
+
+    pub fn symbols(&self) -> impl Iterator<Item = Symbol> {
+        let env = self.symbol_mask();
+        SYMBOL_RANGE.filter(move |&s| env.get(s).is_some())
+    }
 
+    pub fn envs(&self) -> impl Iterator<Item=Environment> {
+        let mut env = self.symbol_mask();
         std::iter::from_fn(move || {
             (env.mask != 0).then(|| {
                 let value = env;
@@ -163,6 +183,21 @@ impl Expression {
         }
     }
 
+    /// Evaluate in any Boolean algebra, `None` if a symbol has no value
+    pub fn interpret<A: BooleanAlgebra>(&self, value: &impl Fn(Symbol) -> Option<A>) -> Option<A> {
+        use Expression::*;
+        Some(match self {
+            &Var(symbol) => value(symbol)?,
+            &Val(constant) => A::constant(constant),
+            Not(x) => x.interpret(value)?.not(),
+            Or(a, b) => a.interpret(value)?.or(&b.interpret(value)?),
+            And(a, b) => a.interpret(value)?.and(&b.interpret(value)?),
+            Xor(a, b) => a.interpret(value)?.xor(&b.interpret(value)?),
+            Implies(a, b) => a.interpret(value)?.implies(&b.interpret(value)?),
+            Equivalent(a, b) => a.interpret(value)?.equivalent(&b.interpret(value)?),
+        })
+    }
+
     pub fn negation_normal(&self) -> Expression {
         use Expression::*;
 
@@ -222,7 +257,7 @@ impl Expression {
                 And(a, b) => or(neg(a), neg(b)),
                 Xor(a, b) => and(or(neg(a), norm(b)), or(norm(a), neg(b))),
                 Implies(a, b) => and(norm(a), neg(b)),
-                Equivalent(a, b) => and(or(neg(a), neg(a)), or(norm(a), norm(b))),
+                Equivalent(a, b) => and(or(neg(a), neg(b)), or(norm(a), norm(b))),
             }
         }
 
@@ -248,8 +283,89 @@ impl Expression {
         norm(self)
     }
 
+    /// Satisfiability, dispatching to the fastest applicable solver
     pub fn sat(&self) -> bool {
-        self.envs().any(|env| self.eval(env))
+        self.model().is_some()
+    }
+
+    /// A satisfying assignment of the expression symbols, if any
+    ///
+    /// Expressions whose clauses would blow up when distributed, like
+    /// chains of `^`, go through the Tseitin encoding instead.
+    pub fn model(&self) -> Option<Environment> {
+        let cnf = match self.distributed_clauses(true) <= DISTRIBUTION_LIMIT {
+            true => Cnf::from(self),
+            false => Cnf::tseitin(self),
+        };
+        solver::solve(&cnf).map(|model| cnf::model_environment(self, &model))
+    }
+
+    /// Number of clauses distributing the expression, or its negation, into
+    /// CNF yields, saturating
+    fn distributed_clauses(&self, positive: bool) -> usize {
+        use Expression::*;
+        let count = |x: &Expression, positive| x.distributed_clauses(positive);
+        let or = |a: usize, b: usize| a.saturating_mul(b);
+        let and = |a: usize, b: usize| a.saturating_add(b);
+        match (self, positive) {
+            (&Val(value), _) => (value != positive) as usize,
+            (Var(_), _) => 1,
+            (Not(x), _) => count(x, !positive),
+            (And(a, b), true) | (Or(a, b), false) => and(count(a, positive), count(b, positive)),
+            (And(a, b), false) | (Or(a, b), true) => or(count(a, positive), count(b, positive)),
+            (Implies(a, b), true) => or(count(a, false), count(b, true)),
+            (Implies(a, b), false) => and(count(a, true), count(b, false)),
+            // Negation normal forms are `a & !b | !a & b` and `a & b | !a & !b`
+            (Xor(a, b), true) | (Equivalent(a, b), false) => or(
+                and(count(a, true), count(b, false)),
+                and(count(a, false), count(b, true)),
+            ),
+            (Xor(a, b), false) | (Equivalent(a, b), true) => or(
+                and(count(a, true), count(b, true)),
+                and(count(a, false), count(b, false)),
+            ),
+        }
+    }
+
+    /// Operands of the top-level chain of conjunctions
+    pub fn conjuncts(&self) -> Vec<&Expression> {
+        match self {
+            Expression::And(a, b) => [a.conjuncts(), b.conjuncts()].concat(),
+            expr => vec![expr],
+        }
+    }
+
+    /// Minimal set of conjuncts that is already unsatisfiable, `None` if the
+    /// expression is satisfiable
+    ///
+    /// Conjuncts that would blow up when distributed go through the Tseitin
+    /// encoding, like in [`Expression::model`].
+    pub fn unsat_core(&self) -> Option<Vec<&Expression>> {
+        // Satisfiable expressions, the common case, skip the core search
+        if self.sat() {
+            return None;
+        }
+        let conjuncts = self.conjuncts();
+        let symbols = self.symbols().last();
+        let mut cnf = Cnf::with_variables(symbols.map_or(0, |s| cnf::symbol_variable(s) + 1));
+        let mut origins = Vec::new();
+        for (origin, conjunct) in conjuncts.iter().enumerate() {
+            match conjunct.distributed_clauses(true) <= DISTRIBUTION_LIMIT {
+                true => {
+                    for clause in Cnf::from(*conjunct).clauses() {
+                        cnf.add_clause(clause.iter().copied());
+                    }
+                }
+                false => cnf.add_tseitin(conjunct),
+            }
+            origins.resize(cnf.clauses().len(), origin);
+        }
+        let mut core: Vec<usize> = proof::unsat_core(&cnf)?
+            .into_iter()
+            .map(|clause| origins[clause])
+            .collect();
+        core.dedup();
+        Some(core.into_iter().map(|origin| conjuncts[origin]).collect())
     }
 
     pub fn write_truth_table(&self, output: &mut impl io::Write) -> io::Result<()> {
@@ -267,7 +383,7 @@ impl Expression {
         for _ in 0..count {
             write!(output, "---|")?;
         }
-        writeln!(output, "")?;
+        writeln!(output)?;
         for env in envs {
             write!(output, "|")?;
             for value in env.values() {
@@ -320,7 +436,13 @@ pub struct Environment {
 impl Environment {
     pub fn enable(&mut self, symbol: Symbol) {
         debug_assert!(SYMBOL_RANGE.contains(&symbol));
-        self.mask |= 1 << b'Z' - symbol;
+        self.mask |= 1 << (b'Z' - symbol);
+    }
+
+    pub fn set(&mut self, symbol: Symbol, value: bool) {
+        self.enable(symbol);
+        let index = b'Z' - symbol;
+        self.values = (self.values & !(1 << index)) | (value as u32) << index;
     }
 
     pub fn get(&self, symbol: Symbol) -> Option<bool> {
@@ -342,6 +464,7 @@ impl Environment {
 }
 
 #[cfg(test)]
+#[allow(clippy::bool_assert_comparison)]
 mod tests {
     use crate::expression::Expression;
 
@@ -396,6 +519,33 @@ mod tests {
         check("AB|!C!&", "A!B!C!&&");
     }
 
+    #[test]
+    fn unsat_core() {
+        fn core(formula: &str) -> Option<Vec<String>> {
+            let expr: Expression = formula.parse().unwrap();
+            let core = expr.unsat_core()?;
+            Some(core.into_iter().map(Expression::to_string).collect())
+        }
+        assert_eq!(core("AB|"), None);
+        assert_eq!(
+            core("AB|C&A!&B!&"),
+            Some(vec!["AB|".into(), "A!".into(), "B!".into()])
+        );
+        assert_eq!(
+            core("AC>BA>&C!&B&"),
+            Some(vec!["AC>".into(), "BA>".into(), "C!".into(), "B".into()])
+        );
+        assert_eq!(core("A0&B&"), Some(vec!["0".into()]));
+
+        // Parity chains are encoded through Tseitin instead of distributed
+        let parity = format!("ABCDEFGHIJKLMNOPQR{}", "^".repeat(17));
+        assert_eq!(core(&parity), None);
+        assert_eq!(
+            core(&format!("{parity}Z&{parity}!&")),
+            Some(vec![parity.clone(), format!("{parity}!")])
+        );
+    }
+
     #[test]
     fn sat() {
         fn sat(formula: &str) -> bool {
@@ -406,5 +556,13 @@ mod tests {
         assert_eq!(sat("AB&"), true);
         assert_eq!(sat("AA!&"), false);
         assert_eq!(sat("AA^"), false);
+
+        // Parity of 20 symbols distributes into 2^19 clauses
+        let parity = format!("ABCDEFGHIJKLMNOPQRST{}", "^".repeat(19));
+        let expr: Expression = parity.parse().unwrap();
+        assert!(expr.distributed_clauses(true) > super::DISTRIBUTION_LIMIT);
+        let model = expr.model().unwrap();
+        assert!(expr.eval(model));
+        assert!(!sat(&format!("{parity}AA!&&")));
     }
 }
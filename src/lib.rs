@@ -1,8 +1,18 @@
+pub mod aig;
+pub mod algebra;
 pub mod arithmetic;
+pub mod cardinality;
+pub mod circuit;
+pub mod cnf;
 pub mod curve;
 pub mod evaluate;
 pub mod expression;
+pub mod maxsat;
+pub mod proof;
+#[cfg(feature = "serde")]
+pub mod serialize;
 pub mod set;
+pub mod solver;
 
 use std::io::stdout;
 
@@ -38,7 +48,7 @@ pub fn negation_normal_form(formula: &str) -> String {
         Ok(expression) => expression.negation_normal().to_string(),
         Err(err) => {
             eprintln!("Could not parse formula: {err:?}");
-            return "".to_owned();
+            "".to_owned()
         }
     }
 }
@@ -48,7 +58,7 @@ pub fn conjonctive_normal_form(formula: &str) -> String {
         Ok(expression) => expression.conjonctive_normal().to_string(),
         Err(err) => {
             eprintln!("Could not parse formula: {err:?}");
-            return "".to_owned();
+            "".to_owned()
         }
     }
 }
@@ -58,7 +68,7 @@ pub fn sat(formula: &str) -> bool {
         Ok(expression) => expression.sat(),
         Err(err) => {
             eprintln!("Could not parse formula: {err:?}");
-            return false;
+            false
         }
     }
 }
@@ -68,13 +78,15 @@ pub fn powerset(set: Vec<i32>) -> Vec<Vec<i32>> {
         Ok(set) => set.powerset().map(|s| s.cloned().into()).collect(),
         Err(_) => {
             eprintln!("Invalid set: has duplicate");
-            return Vec::new();
+            Vec::new()
         }
     }
 }
 
 pub fn eval_set(formula: &str, sets: Vec<Vec<i32>>) -> Vec<i32> {
-    let Ok(environment) = sets.into_iter().map(set::Set::try_from).collect() else {
+    let Ok(environment): Result<Vec<set::Set<i32>>, _> =
+        sets.into_iter().map(set::Set::try_from).collect()
+    else {
         eprintln!("Invalid set found: has duplicate");
         return Vec::new();
     };
@@ -90,6 +102,7 @@ pub fn eval_set(formula: &str, sets: Vec<Vec<i32>>) -> Vec<i32> {
 pub use curve::{map, reverse_map};
 
 #[cfg(test)]
+#[allow(clippy::bool_assert_comparison, clippy::into_iter_on_ref)]
 mod correction_tests {
     #[test]
     fn adder() {
@@ -307,7 +320,7 @@ mod correction_tests {
         assert_eq!(super::conjonctive_normal_form("AB&!"), "A!B!|");
         assert_eq!(super::conjonctive_normal_form("AB|!"), "A!B!&");
         assert_eq!(super::conjonctive_normal_form("AB>!"), "AB!&");
-        assert_eq!(super::conjonctive_normal_form("AB=!"), "A!A!|AB|&");
+        assert_eq!(super::conjonctive_normal_form("AB=!"), "A!B!|AB|&");
     }
 
     #[test]
@@ -0,0 +1,198 @@
+use crate::cnf::{Clause, Cnf, Literal, Variable};
+use crate::proof;
+use crate::solver;
+
+pub type Weight = u64;
+
+/// Weighted partial MaxSAT instance
+///
+/// Hard clauses must hold, the total weight of violated soft clauses is
+/// minimized.
+#[derive(Clone, Debug, Default)]
+pub struct MaxSat {
+    hard: Vec<Clause>,
+    soft: Vec<(Clause, Weight)>,
+    variables: u32,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Solution {
+    pub model: Vec<bool>,
+    pub cost: Weight,
+}
+
+impl MaxSat {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn variables(&self) -> u32 {
+        self.variables
+    }
+
+    pub fn fresh_variable(&mut self) -> Variable {
+        self.variables += 1;
+        self.variables - 1
+    }
+
+    fn reserve(&mut self, clause: &[Literal]) {
+        if let Some(max) = clause.iter().map(|l| l.variable() + 1).max() {
+            self.variables = self.variables.max(max);
+        }
+    }
+
+    pub fn add_hard(&mut self, clause: impl IntoIterator<Item = Literal>) {
+        let clause: Clause = clause.into_iter().collect();
+        self.reserve(&clause);
+        self.hard.push(clause);
+    }
+
+    pub fn add_hard_cnf(&mut self, cnf: &Cnf) {
+        self.variables = self.variables.max(cnf.variables());
+        self.hard.extend_from_slice(cnf.clauses());
+    }
+
+    pub fn add_soft(&mut self, clause: impl IntoIterator<Item = Literal>, weight: Weight) {
+        let clause: Clause = clause.into_iter().collect();
+        self.reserve(&clause);
+        self.soft.push((clause, weight));
+    }
+
+    /// Total weight of the soft clauses a model violates
+    pub fn cost(&self, model: &[bool]) -> Weight {
+        self.soft
+            .iter()
+            .filter(|(clause, _)| !clause.iter().any(|l| l.eval(model)))
+            .map(|(_, weight)| weight)
+            .sum()
+    }
+
+    /// Optimal solution, `None` if the hard clauses are unsatisfiable
+    ///
+    /// Core-guided WPM1: while hard and soft clauses conflict, every soft
+    /// clause of an unsatisfiable core is relaxed by a fresh variable, at
+    /// most one of which may be set, for the weight of the lightest one.
+    pub fn solve(&self) -> Option<Solution> {
+        let mut hard = self.hard.clone();
+        let mut soft: Vec<(Clause, Weight)> = self
+            .soft
+            .iter()
+            .filter(|(_, weight)| *weight > 0)
+            .cloned()
+            .collect();
+        let mut variables = self.variables;
+
+        loop {
+            let mut cnf = Cnf::with_variables(variables);
+            for clause in hard.iter().chain(soft.iter().map(|(clause, _)| clause)) {
+                cnf.add_clause(clause.iter().copied());
+            }
+            if let Some(mut model) = solver::solve(&cnf) {
+                model.truncate(self.variables as usize);
+                let cost = self.cost(&model);
+                return Some(Solution { model, cost });
+            }
+
+            let core: Vec<usize> = proof::unsat_core(&cnf)?
+                .into_iter()
+                .filter_map(|index| index.checked_sub(hard.len()))
+                .collect();
+            let minimum = core.iter().map(|&index| soft[index].1).min()?;
+            let mut relaxations = Vec::with_capacity(core.len());
+            for index in core {
+                // The part of the weight above the minimum stays unrelaxed
+                soft[index].1 -= minimum;
+                let mut relaxed = soft[index].0.clone();
+                let relaxation = Literal::positive(variables);
+                variables += 1;
+                relaxed.push(relaxation);
+                relaxations.push(relaxation);
+                soft.push((relaxed, minimum));
+            }
+            soft.retain(|(_, weight)| *weight > 0);
+
+            hard.push(relaxations.clone());
+            for (i, &a) in relaxations.iter().enumerate() {
+                for &b in &relaxations[i + 1..] {
+                    hard.push(vec![!a, !b]);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MaxSat;
+    use crate::cnf::Literal;
+
+    /// Minimum cost over every assignment of the original variables
+    fn brute_force(instance: &MaxSat) -> Option<u64> {
+        let variables = instance.variables();
+        (0..1u32 << variables)
+            .map(|bits| {
+                (0..variables)
+                    .map(|i| bits >> i & 1 != 0)
+                    .collect::<Vec<_>>()
+            })
+            .filter(|model| {
+                instance
+                    .hard
+                    .iter()
+                    .all(|clause| clause.iter().any(|l| l.eval(model)))
+            })
+            .map(|model| instance.cost(&model))
+            .min()
+    }
+
+    fn check(instance: &MaxSat) {
+        let expected = brute_force(instance);
+        let solution = instance.solve();
+        assert_eq!(solution.as_ref().map(|s| s.cost), expected);
+        if let Some(solution) = solution {
+            assert_eq!(instance.cost(&solution.model), solution.cost);
+        }
+    }
+
+    #[test]
+    fn unweighted() {
+        let [a, b, c] = [0, 1, 2].map(Literal::positive);
+        let mut instance = MaxSat::new();
+        instance.add_hard([a, b]);
+        instance.add_soft([!a], 1);
+        instance.add_soft([!b], 1);
+        instance.add_soft([!c], 1);
+        instance.add_soft([c], 1);
+        check(&instance);
+        assert_eq!(instance.solve().unwrap().cost, 2);
+    }
+
+    #[test]
+    fn weighted() {
+        let [a, b, c, d] = [0, 1, 2, 3].map(Literal::positive);
+        let mut instance = MaxSat::new();
+        instance.add_hard([a, b, c]);
+        instance.add_hard([!a, !d]);
+        instance.add_soft([!a], 5);
+        instance.add_soft([!b], 3);
+        instance.add_soft([!c], 4);
+        instance.add_soft([d], 2);
+        instance.add_soft([b, c], 1);
+        check(&instance);
+        assert_eq!(instance.solve().unwrap().cost, 3);
+
+        instance.add_soft([!b, d], 7);
+        instance.add_soft([a, !c], 6);
+        check(&instance);
+    }
+
+    #[test]
+    fn hard_conflict() {
+        let a = Literal::positive(0);
+        let mut instance = MaxSat::new();
+        instance.add_hard([a]);
+        instance.add_hard([!a]);
+        instance.add_soft([a], 1);
+        assert_eq!(instance.solve(), None);
+    }
+}
@@ -0,0 +1,294 @@
+use core::fmt;
+use std::io;
+
+use crate::cnf::{Clause, Cnf, Literal};
+use crate::solver;
+
+/// Single step of a DRAT proof
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Step {
+    Add(Clause),
+    Delete(Clause),
+}
+
+/// Clausal proof of unsatisfiability in the DRAT format
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Proof {
+    steps: Vec<Step>,
+}
+
+impl Proof {
+    pub fn from_lemmas(lemmas: impl IntoIterator<Item = Clause>) -> Self {
+        Self {
+            steps: lemmas.into_iter().map(Step::Add).collect(),
+        }
+    }
+
+    pub fn steps(&self) -> &[Step] {
+        &self.steps
+    }
+
+    pub fn write(&self, output: &mut impl io::Write) -> io::Result<()> {
+        write!(output, "{self}")
+    }
+
+    /// Check that every added lemma is RUP or RAT and that the empty clause
+    /// is eventually derived
+    pub fn check(&self, cnf: &Cnf) -> bool {
+        let mut database = cnf.clauses().to_vec();
+        for step in &self.steps {
+            match step {
+                Step::Add(lemma) => {
+                    if !is_rup(&database, lemma) && !is_rat(&database, lemma) {
+                        return false;
+                    }
+                    if lemma.is_empty() {
+                        return true;
+                    }
+                    database.push(lemma.clone());
+                }
+                Step::Delete(clause) => {
+                    let clause = normalized(clause.clone());
+                    let position = database
+                        .iter()
+                        .position(|c| normalized(c.clone()) == clause);
+                    if let Some(index) = position {
+                        database.swap_remove(index);
+                    }
+                }
+            }
+        }
+        false
+    }
+}
+
+/// Clause with sorted and unique literals, to compare clauses as sets
+fn normalized(mut clause: Clause) -> Clause {
+    clause.sort_unstable();
+    clause.dedup();
+    clause
+}
+
+/// Reverse unit propagation: assuming the clause false propagates to a
+/// conflict
+fn is_rup(database: &[Clause], clause: &[Literal]) -> bool {
+    let variables = database
+        .iter()
+        .flatten()
+        .chain(clause)
+        .map(|l| l.variable() as usize + 1)
+        .max()
+        .unwrap_or(0);
+    let mut assignment: Vec<Option<bool>> = vec![None; variables];
+    let value = |assignment: &[Option<bool>], literal: Literal| {
+        assignment[literal.variable() as usize].map(|v| v == literal.value())
+    };
+    for &literal in clause {
+        // A tautology holds whatever the assignment
+        if value(&assignment, literal) == Some(true) {
+            return true;
+        }
+        assignment[literal.variable() as usize] = Some(!literal.value());
+    }
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for clause in database {
+            if clause.iter().any(|&l| value(&assignment, l) == Some(true)) {
+                continue;
+            }
+            let mut unassigned = clause.iter().filter(|&&l| value(&assignment, l).is_none());
+            match (unassigned.next(), unassigned.next()) {
+                (None, _) => return true,
+                (Some(&unit), None) => {
+                    assignment[unit.variable() as usize] = Some(unit.value());
+                    changed = true;
+                }
+                _ => {}
+            }
+        }
+    }
+    false
+}
+
+/// Resolution asymmetric tautology on the first literal of the clause, as
+/// written in the proof
+fn is_rat(database: &[Clause], clause: &[Literal]) -> bool {
+    let Some(&pivot) = clause.first() else {
+        return false;
+    };
+    database
+        .iter()
+        .filter(|other| other.contains(&!pivot))
+        .all(|other| {
+            let resolvent: Clause = clause
+                .iter()
+                .chain(other.iter().filter(|&&l| l != !pivot))
+                .copied()
+                .collect();
+            is_rup(database, &resolvent)
+        })
+}
+
+impl fmt::Display for Proof {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for step in &self.steps {
+            let clause = match step {
+                Step::Add(clause) => clause,
+                Step::Delete(clause) => {
+                    write!(f, "d ")?;
+                    clause
+                }
+            };
+            for literal in clause {
+                write!(f, "{literal} ")?;
+            }
+            writeln!(f, "0")?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum ProofParseError {
+    InvalidLiteral,
+    UnterminatedClause,
+}
+
+impl core::str::FromStr for Proof {
+    type Err = ProofParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        use ProofParseError::*;
+
+        let mut steps = Vec::new();
+        let mut clause = Vec::new();
+        let mut delete = false;
+        for token in s.split_whitespace() {
+            if token == "d" && clause.is_empty() {
+                delete = true;
+                continue;
+            }
+            let value: i64 = token.parse().map_err(|_| InvalidLiteral)?;
+            if value != 0 {
+                clause.push(Literal::from_dimacs(value).ok_or(InvalidLiteral)?);
+                continue;
+            }
+            let clause = std::mem::take(&mut clause);
+            steps.push(match delete {
+                true => Step::Delete(clause),
+                false => Step::Add(clause),
+            });
+            delete = false;
+        }
+        if !clause.is_empty() || delete {
+            return Err(UnterminatedClause);
+        }
+        Ok(Self { steps })
+    }
+}
+
+/// Indices of a minimal unsatisfiable subset of the clauses, `None` if the
+/// formula is satisfiable
+///
+/// Clauses are dropped one by one as long as the rest stays unsatisfiable.
+pub fn unsat_core(cnf: &Cnf) -> Option<Vec<usize>> {
+    let subset = |indices: &[usize]| {
+        let mut subset = Cnf::with_variables(cnf.variables());
+        for &index in indices {
+            subset.add_clause(cnf.clauses()[index].iter().copied());
+        }
+        subset
+    };
+
+    if solver::solve(cnf).is_some() {
+        return None;
+    }
+    let mut core: Vec<usize> = (0..cnf.clauses().len()).collect();
+    let mut index = 0;
+    while index < core.len() {
+        let removed = core.remove(index);
+        if solver::solve(&subset(&core)).is_some() {
+            core.insert(index, removed);
+            index += 1;
+        }
+    }
+    Some(core)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Proof, Step};
+    use crate::cnf::Cnf;
+    use crate::expression::Expression;
+    use crate::solver;
+
+    fn cnf(formula: &str) -> Cnf {
+        let expression: Expression = formula.parse().unwrap();
+        Cnf::from(&expression)
+    }
+
+    #[test]
+    fn refutation_checks() {
+        for formula in [
+            "AA!&",
+            "AB|A!B|&AB!|&A!B!|&",
+            "ABC||A!B!C!||&AB=&BC=&",
+            "AB&C>A!B!C!||&A&B&",
+            "ABC^^AB&C&!&ABC||&AB!C!&&!&A!BC!&&!&A!B!C&&!&",
+        ] {
+            let cnf = cnf(formula);
+            let proof = solver::refute(&cnf).unwrap();
+            assert!(proof.check(&cnf), "{formula}");
+
+            let mut output = Vec::new();
+            proof.write(&mut output).unwrap();
+            let parsed: Proof = std::str::from_utf8(&output).unwrap().parse().unwrap();
+            assert_eq!(parsed, proof);
+
+            let dimacs: Cnf = cnf.to_string().parse().unwrap();
+            assert!(parsed.check(&dimacs), "{formula}");
+        }
+        assert!(solver::refute(&cnf("AB|")).is_none());
+    }
+
+    #[test]
+    fn rejects_invalid_proofs() {
+        let cnf = cnf("AB|A!B|&AB!|&A!B!|&");
+        for text in ["", "1 0\n", "1 2 0\n", "d 1 2 0\n0\n"] {
+            let proof: Proof = text.parse().unwrap();
+            assert!(!proof.check(&cnf), "{text:?}");
+        }
+        let proof: Proof = "1 0\n-1 0\n0\n".parse().unwrap();
+        assert!(proof.check(&cnf));
+    }
+
+    #[test]
+    fn rat() {
+        use crate::cnf::Literal;
+
+        let cnf = cnf("AB|");
+        let fresh = Literal::positive(2);
+        let a = Literal::symbol(b'A');
+        // Defining a fresh variable is not implied, but preserves satisfiability
+        assert!(!super::is_rup(cnf.clauses(), &[fresh]));
+        assert!(super::is_rat(cnf.clauses(), &[fresh]));
+        assert!(!super::is_rat(cnf.clauses(), &[!a]));
+
+        // The pivot is the first literal as written, not the smallest one
+        let proof: Proof = "3 -1 0\n".parse().unwrap();
+        let [Step::Add(lemma)] = proof.steps() else {
+            panic!("{proof:?}");
+        };
+        assert_eq!(lemma, &[fresh, !a]);
+        assert!(super::is_rat(cnf.clauses(), lemma));
+        assert!(!super::is_rat(cnf.clauses(), &[!a, fresh]));
+    }
+
+    #[test]
+    fn unsat_core() {
+        let cnf = cnf("AB|C&A!B|&AB!|&A!B!|&CD|&");
+        assert_eq!(super::unsat_core(&cnf), Some(vec![0, 2, 3, 4]));
+        assert_eq!(super::unsat_core(&self::cnf("AB|C&")), None);
+    }
+}
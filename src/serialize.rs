@@ -0,0 +1,217 @@
+use core::fmt;
+use core::marker::PhantomData;
+
+use serde::de::{self, Deserializer, Error as _, MapAccess, SeqAccess, Visitor};
+use serde::ser::{SerializeMap, SerializeSeq, Serializer};
+use serde::{Deserialize, Serialize};
+
+use crate::expression::{Environment, Expression, Symbol, SYMBOL_RANGE};
+use crate::set::{Set, Storage};
+
+fn parse_symbol<E: de::Error>(symbol: char) -> Result<Symbol, E> {
+    match u8::try_from(symbol) {
+        Ok(symbol) if SYMBOL_RANGE.contains(&symbol) => Ok(symbol),
+        _ => Err(E::invalid_value(
+            de::Unexpected::Char(symbol),
+            &"a letter from A to Z",
+        )),
+    }
+}
+
+/// Symbols as their letter rather than its code
+pub(crate) mod symbol {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(symbol: &Symbol, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_char(*symbol as char)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Symbol, D::Error> {
+        parse_symbol(char::deserialize(deserializer)?)
+    }
+}
+
+/// Expressions as their RPN string, for `#[serde(with = "rpn")]`
+///
+/// The derived implementations keep the tree, which is larger but readable
+/// by tools that do not parse formulas.
+pub mod rpn {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(
+        expression: &Expression,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(expression)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Expression, D::Error> {
+        let formula = String::deserialize(deserializer)?;
+        formula
+            .parse()
+            .map_err(|err| D::Error::custom(format_args!("invalid formula {formula:?}: {err:?}")))
+    }
+}
+
+/// Sequence of the elements
+impl<T: Serialize, S: Storage<T>> Serialize for Set<T, S> {
+    fn serialize<Ser: Serializer>(&self, serializer: Ser) -> Result<Ser::Ok, Ser::Error> {
+        let mut seq = serializer.serialize_seq(Some(self.len()))?;
+        for element in self {
+            seq.serialize_element(core::borrow::Borrow::<T>::borrow(&element))?;
+        }
+        seq.end()
+    }
+}
+
+/// Sequence of distinct elements, duplicates being an error
+impl<'de, T: Deserialize<'de>, S: Storage<T>> Deserialize<'de> for Set<T, S> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct SetVisitor<T, S>(PhantomData<(T, S)>);
+
+        impl<'de, T: Deserialize<'de>, S: Storage<T>> Visitor<'de> for SetVisitor<T, S> {
+            type Value = Set<T, S>;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f, "a sequence of distinct elements")
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+                let mut elements = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+                while let Some(element) = seq.next_element()? {
+                    elements.push(element);
+                }
+                Set::try_from(elements).map_err(|_| A::Error::custom("duplicate element in set"))
+            }
+        }
+
+        deserializer.deserialize_seq(SetVisitor(PhantomData))
+    }
+}
+
+/// Map from the letter of each assigned symbol to its value
+impl Serialize for Environment {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.symbols().count()))?;
+        for symbol in self.symbols() {
+            map.serialize_entry(&(symbol as char), &self.get(symbol).unwrap())?;
+        }
+        map.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for Environment {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct EnvironmentVisitor;
+
+        impl<'de> Visitor<'de> for EnvironmentVisitor {
+            type Value = Environment;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f, "a map from letters to booleans")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+                let mut env = Environment::default();
+                while let Some((symbol, value)) = map.next_entry::<char, bool>()? {
+                    let symbol = parse_symbol(symbol)?;
+                    if env.get(symbol).is_some() {
+                        return Err(A::Error::custom(format_args!(
+                            "duplicate symbol {}",
+                            symbol as char
+                        )));
+                    }
+                    env.set(symbol, value);
+                }
+                Ok(env)
+            }
+        }
+
+        deserializer.deserialize_map(EnvironmentVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::{Deserialize, Serialize};
+
+    use crate::expression::{Environment, Expression};
+    use crate::set::{HashedSet, Set, SortedSet};
+
+    #[derive(Serialize, Deserialize)]
+    struct Config {
+        #[serde(with = "super::rpn")]
+        rule: Expression,
+        tree: Expression,
+    }
+
+    fn json<T: Serialize + for<'de> Deserialize<'de>>(value: &T) -> (String, T) {
+        let text = serde_json::to_string(value).unwrap();
+        let back = serde_json::from_str(&text).unwrap();
+        (text, back)
+    }
+
+    fn binary<T: Serialize + for<'de> Deserialize<'de>>(value: &T) -> T {
+        bincode::deserialize(&bincode::serialize(value).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn expression() {
+        let expression: Expression = "AB&C!|".parse().unwrap();
+        let (text, back) = json(&expression);
+        assert_eq!(
+            text,
+            r#"{"Or":[{"And":[{"Var":"A"},{"Var":"B"}]},{"Not":{"Var":"C"}}]}"#
+        );
+        assert_eq!(back.to_string(), "AB&C!|");
+        assert_eq!(binary(&expression).to_string(), "AB&C!|");
+
+        let config = Config {
+            rule: "AB>1=".parse().unwrap(),
+            tree: "A0^".parse().unwrap(),
+        };
+        let (text, back) = json(&config);
+        assert_eq!(
+            text,
+            r#"{"rule":"AB>1=","tree":{"Xor":[{"Var":"A"},{"Val":false}]}}"#
+        );
+        assert_eq!(back.rule.to_string(), "AB>1=");
+        assert_eq!(back.tree.to_string(), "A0^");
+        assert_eq!(binary(&config).rule.to_string(), "AB>1=");
+
+        assert!(serde_json::from_str::<Config>(r#"{"rule":"A&","tree":{"Val":true}}"#).is_err());
+        assert!(serde_json::from_str::<Expression>(r#"{"Var":"a"}"#).is_err());
+    }
+
+    #[test]
+    fn set() {
+        let set: Set<i32> = Set::try_from(vec![3, 1, 2]).unwrap();
+        let (text, back) = json(&set);
+        assert_eq!(text, "[3,1,2]");
+        assert_eq!(back, set);
+        assert_eq!(binary(&set), set);
+
+        let sorted: SortedSet<i32> = Set::try_from(vec![3, 1, 2]).unwrap();
+        assert_eq!(json(&sorted).0, "[1,2,3]");
+        let hashed: HashedSet<String> = serde_json::from_str(r#"["a","b"]"#).unwrap();
+        assert_eq!(binary(&hashed), hashed);
+        let environment: Vec<Set<i32>> = serde_json::from_str("[[0,1],[],[2]]").unwrap();
+        assert_eq!(environment.len(), 3);
+
+        assert!(serde_json::from_str::<Set<i32>>("[1,2,1]").is_err());
+    }
+
+    #[test]
+    fn environment() {
+        let mut env = Environment::default();
+        env.set(b'A', true);
+        env.set(b'C', false);
+        let (text, back) = json(&env);
+        assert_eq!(text, r#"{"A":true,"C":false}"#);
+        assert_eq!(back, env);
+        assert_eq!(binary(&env), env);
+
+        assert!(serde_json::from_str::<Environment>(r#"{"1":true}"#).is_err());
+        assert!(serde_json::from_str::<Environment>(r#"{"A":true,"A":false}"#).is_err());
+    }
+}
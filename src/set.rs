@@ -1,40 +1,93 @@
+use core::borrow::Borrow;
 use core::fmt;
+use core::hash::{Hash, Hasher};
+use core::marker::PhantomData;
+use core::ops::RangeInclusive;
+use std::collections::{BTreeSet, HashSet};
 
-#[derive(Debug, Clone)]
-pub struct Set<T>(Vec<T>);
+use crate::algebra::BooleanAlgebra;
+use crate::expression::{Expression, ExpressionParseError, Symbol};
 
-impl<T> Default for Set<T> {
+pub mod combinatorics;
+pub mod fuzzy;
+pub mod multiset;
+pub mod relation;
+pub mod storage;
+pub mod venn;
+
+pub use combinatorics::Toggle;
+pub use fuzzy::{FuzzySet, TNorm};
+pub use multiset::MultiSet;
+pub use storage::{BitSet, Dense, Discrete, Intervals, SortedVec, Storage, DENSE_UNIVERSE};
+
+/// Set of distinct values held in a storage backend
+///
+/// The default backend is an unordered vector, which only needs `Eq` but
+/// makes every operation quadratic.
+pub struct Set<T, S = Vec<T>>(S, PhantomData<T>);
+
+/// Set with hashed storage
+pub type HashedSet<T> = Set<T, HashSet<T>>;
+
+/// Set with B-tree storage
+pub type OrderedSet<T> = Set<T, BTreeSet<T>>;
+
+/// Set with sorted vector storage and merge-based operations
+pub type SortedSet<T> = Set<T, SortedVec<T>>;
+
+/// Set of small integers with bitset storage
+pub type DenseSet<T> = Set<T, BitSet<T>>;
+
+/// Set of integers stored as disjoint ranges
+pub type IntervalSet<T> = Set<T, Intervals<T>>;
+
+impl<T, S> Set<T, S> {
+    fn wrap(storage: S) -> Self {
+        Self(storage, PhantomData)
+    }
+}
+
+impl<T, S: Default> Default for Set<T, S> {
     fn default() -> Self {
-        Self(Vec::new())
+        Self::wrap(S::default())
+    }
+}
+
+impl<T, S: Clone> Clone for Set<T, S> {
+    fn clone(&self) -> Self {
+        Self::wrap(self.0.clone())
+    }
+}
+
+impl<T, S: fmt::Debug> fmt::Debug for Set<T, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Set").field(&self.0).finish()
     }
 }
 
 impl<T: Clone> Set<&T> {
     pub fn cloned(&self) -> Set<T> {
-        Set(self.0.iter().map(|&e| e.clone()).collect())
+        Set::wrap(self.0.iter().map(|&e| e.clone()).collect())
     }
 }
 
-impl<T: fmt::Display> fmt::Display for Set<T> {
+impl<T: fmt::Display, S: Storage<T>> fmt::Display for Set<T, S> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{{")?;
         let mut elements = self.0.iter();
         if let Some(first) = elements.next() {
-            write!(f, " {first}")?;
+            write!(f, " {}", first.borrow())?;
         }
         for element in elements {
-            write!(f, ", {element}")?;
+            write!(f, ", {}", element.borrow())?;
         }
         write!(f, " }}")
     }
 }
 
-impl<T: Eq> PartialEq for Set<T> {
+impl<T: Eq, S: Storage<T>> PartialEq for Set<T, S> {
     fn eq(&self, other: &Self) -> bool {
-        if self.0.len() != other.0.len() {
-            return false;
-        }
-        self.0.iter().all(|x| other.0.contains(x))
+        self.0.is_equal(&other.0)
     }
 }
 
@@ -53,153 +106,426 @@ impl<T: Eq> PartialEq<Set<T>> for Set<&T> {
     }
 }
 
-impl<T: Eq> Eq for Set<T> {}
+impl<T: Eq, S: Storage<T>> Eq for Set<T, S> {}
 
-fn has_duplicates(mut set: &[impl Eq]) -> bool {
-    while let Some((first, rest)) = set.split_first() {
-        if rest.iter().any(|v| first == v) {
-            return true;
-        }
-        set = rest;
+impl<T, S: Storage<T>> TryFrom<Vec<T>> for Set<T, S> {
+    type Error = Vec<T>;
+
+    fn try_from(vec: Vec<T>) -> Result<Self, Self::Error> {
+        S::from_unique(vec).map(Self::wrap)
     }
-    false
 }
 
-impl<T: Eq> TryFrom<Vec<T>> for Set<T> {
-    type Error = Vec<T>;
+impl<T, S: Storage<T>> From<Set<T, S>> for Vec<T> {
+    fn from(set: Set<T, S>) -> Self {
+        set.0.into_vec()
+    }
+}
 
-    fn try_from(vec: Vec<T>) -> Result<Self, Self::Error> {
-        match has_duplicates(&vec) {
-            true => Err(vec),
-            false => Ok(Self(vec)),
+/// Duplicates are silently merged
+///
+/// # Panics
+/// On values the storage cannot hold, see [`Set::try_extend`].
+impl<T, S: Storage<T>> FromIterator<T> for Set<T, S> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut set = Self::new();
+        set.extend(iter);
+        set
+    }
+}
+
+/// # Panics
+/// On values the storage cannot hold, see [`Set::try_extend`].
+impl<T, S: Storage<T>> Extend<T> for Set<T, S> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        if self.try_extend(iter).is_err() {
+            panic!("Value outside of what the storage can hold");
         }
     }
 }
 
-impl<T> From<Set<T>> for Vec<T> {
-    fn from(set: Set<T>) -> Self {
-        set.0
+impl<T, S: Storage<T>> IntoIterator for Set<T, S> {
+    type Item = T;
+    type IntoIter = std::vec::IntoIter<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_vec().into_iter()
     }
 }
 
-impl<T> Set<T> {
-    pub fn powerset<'a>(&'a self) -> impl Iterator<Item = Set<&'a T>> {
-        (0..1 << self.0.len()).map(|mut i| {
-            Set(self
-                .0
-                .iter()
-                .filter(|_| {
-                    let keep = i & 1 != 0;
-                    i >>= 1;
-                    keep
+impl<'a, T, S: Storage<T>> IntoIterator for &'a Set<T, S> {
+    type Item = S::Item<'a>;
+    type IntoIter = S::Iter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+/// Independent of the iteration order, so equal sets hash the same
+impl<T: Hash, S: Storage<T>> Hash for Set<T, S> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.hash_values(state);
+    }
+}
+
+impl<T, S: Storage<T>> Set<T, S> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn iter(&self) -> S::Iter<'_> {
+        self.0.iter()
+    }
+
+    pub fn contains(&self, value: &T) -> bool {
+        self.0.contains(value)
+    }
+
+    /// Insert a value, `false` if it was already present
+    ///
+    /// # Panics
+    /// On values the storage cannot hold, like negative numbers in a
+    /// [`DenseSet`], see [`Set::try_insert`].
+    pub fn insert(&mut self, value: T) -> bool {
+        self.0.insert(value)
+    }
+
+    /// Insert a value, `false` if it was already present, or the value back
+    /// if the storage cannot hold it
+    pub fn try_insert(&mut self, value: T) -> Result<bool, T> {
+        self.0.try_insert(value)
+    }
+
+    /// Insert every value, stopping at the first one the storage cannot
+    /// hold and handing it back
+    pub fn try_extend(&mut self, values: impl IntoIterator<Item = T>) -> Result<(), T> {
+        for value in values {
+            self.0.try_insert(value)?;
+        }
+        Ok(())
+    }
+
+    /// Remove a value, `false` if it was absent
+    pub fn remove(&mut self, value: &T) -> bool {
+        self.0.remove(value)
+    }
+
+    pub fn is_subset(&self, other: &Self) -> bool {
+        self.0.is_subset(&other.0)
+    }
+
+    pub fn is_superset(&self, other: &Self) -> bool {
+        other.is_subset(self)
+    }
+
+    pub fn is_disjoint(&self, other: &Self) -> bool {
+        self.0.is_disjoint(&other.0)
+    }
+
+    /// Every pair of an element of each set
+    pub fn product<U, R>(&self, other: &Set<U, R>) -> Set<(T, U)>
+    where
+        T: Clone,
+        U: Clone,
+        R: Storage<U>,
+    {
+        Set::wrap(
+            self.iter()
+                .flat_map(|a| {
+                    other
+                        .iter()
+                        .map(move |b| (a.borrow().clone(), b.borrow().clone()))
                 })
-                .collect())
-        })
+                .collect(),
+        )
+    }
+
+    /// View of the set whose negation is its complement in `universe`
+    pub fn within<'a>(&'a self, universe: &'a Self) -> Relative<'a, T, S> {
+        Relative {
+            set: self,
+            universe,
+        }
     }
 }
 
-impl<T: Eq + Clone> Set<T> {
-    pub fn union(&self, other: &Self) -> Self {
-        let mut result = self.clone();
-        for value in &other.0 {
-            if !result.0.contains(value) {
-                result.0.push(value.clone())
+impl<T: Discrete> Set<T, Intervals<T>> {
+    /// Values of some possibly overlapping ranges
+    pub fn from_ranges(ranges: impl IntoIterator<Item = RangeInclusive<T>>) -> Self {
+        let mut set = Self::new();
+        for range in ranges {
+            set.insert_range(range);
+        }
+        set
+    }
+
+    /// Add a range of values, `false` if they were all present
+    pub fn insert_range(&mut self, range: RangeInclusive<T>) -> bool {
+        self.0.insert_range(range)
+    }
+
+    /// Maximal runs of consecutive values, in increasing order
+    pub fn ranges(&self) -> impl Iterator<Item = RangeInclusive<T>> + '_ {
+        self.0.ranges()
+    }
+
+    /// Values of the type missing from the set
+    pub fn complement(&self) -> Self {
+        Self::wrap(self.0.complement())
+    }
+}
+
+/// Set paired with the universe it lives in, see [`Set::within`]
+pub struct Relative<'a, T, S = Vec<T>> {
+    set: &'a Set<T, S>,
+    universe: &'a Set<T, S>,
+}
+
+impl<T: Clone, S: Storage<T>> core::ops::Not for Relative<'_, T, S> {
+    type Output = Set<T, S>;
+
+    fn not(self) -> Self::Output {
+        self.universe.without(self.set)
+    }
+}
+
+macro_rules! impl_set_operator {
+    ($trait:ident, $function:ident, $method:ident) => {
+        impl<T: Clone, S: Storage<T>> core::ops::$trait<&Set<T, S>> for &Set<T, S> {
+            type Output = Set<T, S>;
+
+            fn $function(self, rhs: &Set<T, S>) -> Self::Output {
+                self.$method(rhs)
+            }
+        }
+
+        impl<T: Clone, S: Storage<T>> core::ops::$trait for Set<T, S> {
+            type Output = Set<T, S>;
+
+            fn $function(self, rhs: Self) -> Self::Output {
+                self.$method(&rhs)
             }
         }
-        result
+    };
+}
+
+impl_set_operator!(BitOr, bitor, union);
+impl_set_operator!(BitAnd, bitand, intersection);
+impl_set_operator!(BitXor, bitxor, xunion);
+impl_set_operator!(Sub, sub, without);
+
+impl<T: Clone, S: Storage<T>> Set<T, S> {
+    pub fn union(&self, other: &Self) -> Self {
+        Self::wrap(self.0.union(&other.0))
     }
 
     pub fn intersection(&self, other: &Self) -> Self {
-        Self(
-            self.0
-                .iter()
-                .filter(|x| other.0.contains(x))
-                .cloned()
-                .collect(),
-        )
+        Self::wrap(self.0.intersection(&other.0))
     }
 
     pub fn xunion(&self, other: &Self) -> Self {
-        Self(
-            self.0
-                .iter()
-                .filter(|x| !other.0.contains(x))
-                .chain(other.0.iter().filter(|x| !self.0.contains(x)))
-                .cloned()
-                .collect(),
-        )
+        Self::wrap(self.0.symmetric_difference(&other.0))
     }
 
     pub fn without(&self, other: &Self) -> Self {
-        Self(
-            self.0
-                .iter()
-                .filter(|x| !other.0.contains(x))
-                .cloned()
-                .collect(),
-        )
+        Self::wrap(self.0.difference(&other.0))
+    }
+}
+
+/// Subset of a universe, stored as the elements it has or as those it lacks
+///
+/// Complementing a finite set within an infinite universe is only
+/// representable the second way.
+#[derive(Clone, Debug)]
+pub enum Extent<T, S = Vec<T>> {
+    Finite(Set<T, S>),
+    Cofinite(Set<T, S>),
+}
+
+impl<T: Eq, S: Storage<T>> PartialEq for Extent<T, S> {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Extent::Finite(a), Extent::Finite(b)) => a == b,
+            (Extent::Cofinite(a), Extent::Cofinite(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+impl<T: Eq, S: Storage<T>> Eq for Extent<T, S> {}
+
+impl<T: Clone, S: Storage<T>> Extent<T, S> {
+    pub fn contains(&self, value: &T) -> bool {
+        match self {
+            Extent::Finite(set) => set.contains(value),
+            Extent::Cofinite(set) => !set.contains(value),
+        }
+    }
+
+    pub fn complement(self) -> Self {
+        match self {
+            Extent::Finite(set) => Extent::Cofinite(set),
+            Extent::Cofinite(set) => Extent::Finite(set),
+        }
+    }
+
+    pub fn union(&self, other: &Self) -> Self {
+        use Extent::*;
+        match (self, other) {
+            (Finite(a), Finite(b)) => Finite(a.union(b)),
+            (Cofinite(a), Finite(b)) | (Finite(b), Cofinite(a)) => Cofinite(a.without(b)),
+            (Cofinite(a), Cofinite(b)) => Cofinite(a.intersection(b)),
+        }
+    }
+
+    pub fn intersection(&self, other: &Self) -> Self {
+        use Extent::*;
+        match (self, other) {
+            (Finite(a), Finite(b)) => Finite(a.intersection(b)),
+            (Cofinite(a), Finite(b)) | (Finite(b), Cofinite(a)) => Finite(b.without(a)),
+            (Cofinite(a), Cofinite(b)) => Cofinite(a.union(b)),
+        }
+    }
+
+    pub fn xunion(&self, other: &Self) -> Self {
+        use Extent::*;
+        match (self, other) {
+            (Finite(a), Finite(b)) | (Cofinite(a), Cofinite(b)) => Finite(a.xunion(b)),
+            (Cofinite(a), Finite(b)) | (Finite(a), Cofinite(b)) => Cofinite(a.xunion(b)),
+        }
+    }
+
+    /// Elements of the universe in the extent
+    pub fn within(self, universe: &Set<T, S>) -> Set<T, S> {
+        match self {
+            Extent::Finite(set) => set,
+            Extent::Cofinite(set) => universe.without(&set),
+        }
     }
 }
 
+impl<T: Clone, S: Storage<T> + Clone> BooleanAlgebra for Extent<T, S> {
+    fn constant(value: bool) -> Self {
+        match value {
+            true => Extent::Cofinite(Set::new()),
+            false => Extent::Finite(Set::new()),
+        }
+    }
+
+    fn and(&self, other: &Self) -> Self {
+        self.intersection(other)
+    }
+
+    fn or(&self, other: &Self) -> Self {
+        self.union(other)
+    }
+
+    fn not(&self) -> Self {
+        self.clone().complement()
+    }
+
+    fn xor(&self, other: &Self) -> Self {
+        self.xunion(other)
+    }
+}
+
+/// Domain set formulas are complemented in
+pub enum Universe<'a, T, S = Vec<T>> {
+    /// Union of the environment sets
+    Environment,
+    /// Explicit finite universe, containing every environment set
+    Set(&'a Set<T, S>),
+    /// Possibly infinite universe, containing every environment element
+    Predicate(&'a dyn Fn(&T) -> bool),
+}
+
 #[derive(Debug)]
 pub enum EvaluationError {
     MissingArgument,
     IncompleteComputation,
     UnspecifiedVar,
     UnknownSymbol,
+    OutsideUniverse,
+}
+
+impl From<ExpressionParseError> for EvaluationError {
+    fn from(err: ExpressionParseError) -> Self {
+        match err {
+            ExpressionParseError::UnknownSymbol => EvaluationError::UnknownSymbol,
+            ExpressionParseError::MissingArgument => EvaluationError::MissingArgument,
+            ExpressionParseError::IncompleteComputation => EvaluationError::IncompleteComputation,
+        }
+    }
+}
+
+/// Evaluate with `!` complementing within the union of the environment
+pub fn try_evaluate<T: Clone, S: Storage<T> + Clone>(
+    expression: &str,
+    environment: Vec<Set<T, S>>,
+) -> Result<Set<T, S>, EvaluationError> {
+    match try_evaluate_in(expression, environment, Universe::Environment)? {
+        Extent::Finite(set) => Ok(set),
+        Extent::Cofinite(_) => unreachable!("Finite universes yield finite sets"),
+    }
 }
 
-pub fn try_evaluate<T: Eq + Clone>(
+/// Parse a set formula and evaluate it within a universe, see [`evaluate_in`]
+pub fn try_evaluate_in<T: Clone, S: Storage<T> + Clone>(
     expression: &str,
-    environment: Vec<Set<T>>,
-) -> Result<Set<T>, EvaluationError> {
+    environment: Vec<Set<T, S>>,
+    universe: Universe<T, S>,
+) -> Result<Extent<T, S>, EvaluationError> {
+    let expression: Expression = expression.parse()?;
+    evaluate_in(&expression, &environment, universe)
+}
+
+/// Interpret an expression over sets, `A` being the first one
+///
+/// `!A` is the universe without `A`, `A>B` is `A!B|` and `A=B` is `AB^!`;
+/// `0` and `1` are the empty set and the universe. The result is finite
+/// unless the universe is a predicate.
+pub fn evaluate_in<T: Clone, S: Storage<T> + Clone>(
+    expression: &Expression,
+    environment: &[Set<T, S>],
+    universe: Universe<T, S>,
+) -> Result<Extent<T, S>, EvaluationError> {
     use EvaluationError::*;
-    let mut stack = Vec::<Set<T>>::new();
-    let mut all = Set::default();
-    for set in &environment {
-        all = all.union(set);
-    }
-    for symbol in expression.bytes() {
-        let result = match symbol {
-            b'A'..=b'Z' => {
-                let index = (symbol - b'A') as usize;
-                let set = environment.get(index).ok_or(UnspecifiedVar)?;
-                set.clone()
-            }
-            b'!' => all.without(&stack.pop().ok_or(MissingArgument)?),
-            b'|' => {
-                let b = stack.pop().ok_or(MissingArgument)?;
-                let a = stack.pop().ok_or(MissingArgument)?;
-                a.union(&b)
-            }
-            b'&' => {
-                let b = stack.pop().ok_or(MissingArgument)?;
-                let a = stack.pop().ok_or(MissingArgument)?;
-                a.intersection(&b)
-            }
-            b'^' => {
-                let b = stack.pop().ok_or(MissingArgument)?;
-                let a = stack.pop().ok_or(MissingArgument)?;
-                a.xunion(&b)
-            }
-            b'>' => {
-                let b = stack.pop().ok_or(MissingArgument)?;
-                let a = stack.pop().ok_or(MissingArgument)?;
-                all.without(&a.without(&b))
+
+    let finite = match universe {
+        Universe::Environment => Some(environment.iter().fold(Set::new(), |u, s| u.union(s))),
+        Universe::Set(universe) => {
+            if !environment.iter().all(|set| set.is_subset(universe)) {
+                return Err(OutsideUniverse);
             }
-            b'=' => {
-                let b = stack.pop().ok_or(MissingArgument)?;
-                let a = stack.pop().ok_or(MissingArgument)?;
-                all.without(&a.intersection(&b))
+            Some(universe.clone())
+        }
+        Universe::Predicate(predicate) => {
+            let inside = |set: &Set<T, S>| set.iter().all(|x| predicate(x.borrow()));
+            if !environment.iter().all(inside) {
+                return Err(OutsideUniverse);
             }
-            _ => return Err(UnknownSymbol),
-        };
-        stack.push(result)
-    }
-    if stack.len() != 1 {
-        return Err(IncompleteComputation);
-    }
-    Ok(stack.pop().unwrap())
+            None
+        }
+    };
+
+    let value = |symbol: Symbol| {
+        let set = environment.get((symbol - b'A') as usize)?;
+        Some(Extent::Finite(set.clone()))
+    };
+    let result = expression.interpret(&value).ok_or(UnspecifiedVar)?;
+    Ok(match finite {
+        Some(universe) => Extent::Finite(result.within(&universe)),
+        None => result,
+    })
 }
 
 #[cfg(test)]
@@ -230,7 +556,7 @@ mod tests {
             input: &[&[T]],
             expected: &[T],
         ) {
-            let input: Vec<_> = input
+            let input: Vec<Set<T>> = input
                 .iter()
                 .map(|s| Set::try_from(s.to_vec()).unwrap())
                 .collect();
@@ -245,4 +571,311 @@ mod tests {
 
         check("A!", &[&[0, 1, 2]], &[]);
     }
+
+    #[test]
+    fn equivalence() {
+        // `A=B` holds for the elements in both sets or in neither, within
+        // the union of the environment
+        let environment: Vec<Set<i32>> = [&[1, 2][..], &[2, 3], &[4]]
+            .iter()
+            .map(|s| Set::try_from(s.to_vec()).unwrap())
+            .collect();
+        let output = super::try_evaluate("AB=", environment).unwrap();
+        for (element, expected) in [(1, false), (2, true), (3, false), (4, true)] {
+            assert_eq!(output.contains(&element), expected, "{element}");
+        }
+    }
+
+    #[test]
+    fn universe() {
+        use super::{Extent, Universe};
+
+        let set = |values: &[i32]| -> Set<i32> { values.iter().copied().collect() };
+        let evaluate = |expression, environment: &[&[i32]], universe| {
+            let environment = environment.iter().map(|s| set(s)).collect();
+            super::try_evaluate_in(expression, environment, universe).unwrap()
+        };
+
+        let universe = set(&[0, 1, 2, 3]);
+        let explicit = || Universe::Set(&universe);
+        assert_eq!(
+            evaluate("A!", &[&[1]], explicit()),
+            Extent::Finite(set(&[0, 2, 3]))
+        );
+        assert_eq!(
+            evaluate("A!", &[&[1]], Universe::Environment),
+            Extent::Finite(set(&[]))
+        );
+        assert_eq!(
+            evaluate("AB>", &[&[1, 2], &[2]], explicit()),
+            Extent::Finite(set(&[0, 2, 3]))
+        );
+        assert_eq!(
+            evaluate("AB=", &[&[1, 2], &[2, 3]], explicit()),
+            Extent::Finite(set(&[0, 2]))
+        );
+
+        let even = |x: &i32| x % 2 == 0;
+        let predicate = || Universe::Predicate(&even);
+        let result = evaluate("A!", &[&[2, 4]], predicate());
+        assert_eq!(result, Extent::Cofinite(set(&[2, 4])));
+        assert!(result.contains(&6) && !result.contains(&4));
+        assert_eq!(
+            evaluate("A!B&", &[&[2], &[2, 4]], predicate()),
+            Extent::Finite(set(&[4]))
+        );
+        assert_eq!(
+            evaluate("A!B!|", &[&[2, 4], &[4, 6]], predicate()),
+            Extent::Cofinite(set(&[4]))
+        );
+        assert_eq!(
+            evaluate("AA!|", &[&[2]], predicate()),
+            Extent::Cofinite(set(&[]))
+        );
+
+        let environment = vec![set(&[1, 7])];
+        let outside = super::try_evaluate_in("A!", environment.clone(), explicit());
+        assert!(matches!(
+            outside,
+            Err(super::EvaluationError::OutsideUniverse)
+        ));
+        let outside = super::try_evaluate_in("A!", environment, predicate());
+        assert!(matches!(
+            outside,
+            Err(super::EvaluationError::OutsideUniverse)
+        ));
+    }
+
+    #[test]
+    fn normal_forms() {
+        use super::Universe;
+        use crate::expression::Expression;
+
+        let environment: Vec<Set<i32>> = vec![
+            [0, 1, 2].into_iter().collect(),
+            [2, 3].into_iter().collect(),
+            [1, 3, 4].into_iter().collect(),
+        ];
+        let universe: Set<i32> = (0..6).collect();
+        for formula in ["AB>C=", "AB^C&!", "AB|!C^", "A0|1&B=", "ABC^^"] {
+            let expression: Expression = formula.parse().unwrap();
+            let evaluate = |expression: &Expression| {
+                super::evaluate_in(expression, &environment, Universe::Set(&universe)).unwrap()
+            };
+            let expected = evaluate(&expression);
+            assert_eq!(
+                evaluate(&expression.negation_normal()),
+                expected,
+                "{formula}"
+            );
+            assert_eq!(
+                evaluate(&expression.conjonctive_normal()),
+                expected,
+                "{formula}"
+            );
+        }
+    }
+
+    #[test]
+    fn api() {
+        use super::HashedSet;
+
+        let mut set: Set<i32> = Set::new();
+        assert!(set.is_empty());
+        assert!(set.insert(1));
+        assert!(set.insert(2));
+        assert!(!set.insert(1));
+        assert_eq!(set.len(), 2);
+        assert!(set.contains(&2));
+        assert!(set.remove(&2));
+        assert!(!set.remove(&2));
+        assert!(!set.contains(&2));
+
+        let small: Set<i32> = [1, 2].into_iter().collect();
+        let large: Set<i32> = [3, 2, 1, 2].into_iter().collect();
+        let other: Set<i32> = [4, 5].into_iter().collect();
+        assert_eq!(large.len(), 3);
+        assert!(small.is_subset(&large));
+        assert!(!large.is_subset(&small));
+        assert!(large.is_superset(&small));
+        assert!(small.is_disjoint(&other));
+        assert!(!small.is_disjoint(&large));
+
+        let mut extended = small.clone();
+        extended.extend([2, 3]);
+        assert_eq!(extended, large);
+        assert_eq!((&small).into_iter().count(), 2);
+        assert_eq!(small.iter().copied().sum::<i32>(), 3);
+        let mut values: Vec<i32> = large.clone().into_iter().collect();
+        values.sort();
+        assert_eq!(values, [1, 2, 3]);
+
+        let hashed: HashedSet<Set<i32>> = [small.clone(), [2, 1].into_iter().collect()]
+            .into_iter()
+            .collect();
+        assert_eq!(hashed.len(), 1);
+    }
+
+    #[test]
+    fn operators() {
+        let set = |values: &[i32]| -> Set<i32> { values.iter().copied().collect() };
+        let a = set(&[1, 2, 3]);
+        let b = set(&[3, 4]);
+        assert_eq!(&a | &b, set(&[1, 2, 3, 4]));
+        assert_eq!(&a & &b, set(&[3]));
+        assert_eq!(&a ^ &b, set(&[1, 2, 4]));
+        assert_eq!(&a - &b, set(&[1, 2]));
+        assert_eq!(a.clone() | b.clone(), set(&[1, 2, 3, 4]));
+
+        let universe = set(&[0, 1, 2, 3, 4, 5]);
+        assert_eq!(!a.within(&universe), set(&[0, 4, 5]));
+        assert_eq!(!universe.within(&universe), set(&[]));
+    }
+
+    #[test]
+    fn product() {
+        let a: Set<i32> = [1, 2].into_iter().collect();
+        let b: Set<char> = ['x', 'y', 'z'].into_iter().collect();
+        let product = a.product(&b);
+        assert_eq!(product.len(), 6);
+        assert!(product.contains(&(2, 'y')));
+        assert!(a.product(&Set::<char>::new()).is_empty());
+    }
+
+    #[test]
+    fn backends() {
+        use super::{DenseSet, HashedSet, OrderedSet, SortedSet, Storage};
+
+        fn check<S: Storage<u32> + Clone + std::fmt::Debug>() {
+            let a: Vec<u32> = (0..200).filter(|x| x % 3 == 0).collect();
+            let b: Vec<u32> = (100..300).filter(|x| x % 5 != 0).collect();
+            let expected_a: Set<u32> = Set::try_from(a.clone()).unwrap();
+            let expected_b: Set<u32> = Set::try_from(b.clone()).unwrap();
+            let a: Set<u32, S> = Set::try_from(a).unwrap();
+            let b: Set<u32, S> = Set::try_from(b).unwrap();
+            let same = |set: Set<u32, S>, expected: Set<u32>| {
+                let set = Set::<u32>::try_from(Vec::from(set)).unwrap();
+                assert_eq!(set, expected);
+            };
+            same(a.union(&b), expected_a.union(&expected_b));
+            same(a.intersection(&b), expected_a.intersection(&expected_b));
+            same(a.xunion(&b), expected_a.xunion(&expected_b));
+            same(a.without(&b), expected_a.without(&expected_b));
+            same(b.without(&a), expected_b.without(&expected_a));
+            assert_eq!(a.union(&b), b.union(&a));
+            assert_ne!(a, b);
+            assert!(Set::<u32, S>::try_from(vec![3, 1, 4, 1]).is_err());
+        }
+
+        check::<Vec<u32>>();
+        check::<std::collections::HashSet<u32>>();
+        check::<std::collections::BTreeSet<u32>>();
+        check::<super::SortedVec<u32>>();
+        check::<super::BitSet<u32>>();
+        check::<super::Intervals<u32>>();
+
+        let sorted: SortedSet<u32> = Set::try_from(vec![5, 1, 3]).unwrap();
+        assert_eq!(Vec::from(sorted), [1, 3, 5]);
+        let ordered: OrderedSet<u32> = Set::try_from(vec![5, 1, 3]).unwrap();
+        assert_eq!(ordered.to_string(), "{ 1, 3, 5 }");
+        let dense: DenseSet<u32> = Set::try_from(vec![130, 0, 64, 63]).unwrap();
+        assert_eq!(Vec::from(dense), [0, 63, 64, 130]);
+        assert!(DenseSet::<i32>::try_from(vec![-1]).is_err());
+        assert!(DenseSet::<u64>::try_from(vec![1 << 40]).is_err());
+        let mut dense: DenseSet<i32> = Set::try_from(vec![1]).unwrap();
+        assert_eq!(dense.try_insert(-1), Err(-1));
+        assert_eq!(dense.try_insert(2), Ok(true));
+        assert!(!dense.contains(&-1) && !dense.remove(&i32::MIN));
+        assert_eq!(dense.try_extend([3, -4, 5]), Err(-4));
+        assert_eq!(Vec::from(dense), [1, 2, 3]);
+        let hashed: HashedSet<u32> = Set::try_from(vec![2, 1]).unwrap();
+        assert_eq!(hashed, Set::try_from(vec![1, 2]).unwrap());
+    }
+
+    #[test]
+    #[should_panic]
+    fn collect_outside_dense_universe() {
+        let _: super::DenseSet<i32> = [1, -1].into_iter().collect();
+    }
+
+    #[test]
+    fn intervals() {
+        use core::hash::{Hash, Hasher};
+
+        use super::{IntervalSet, Universe};
+
+        let mut a = IntervalSet::from_ranges([0..=9, 20..=29, 5..=14]);
+        assert!(a.ranges().eq([0..=14, 20..=29]));
+        assert_eq!(a.len(), 25);
+        assert!(a.contains(&14) && !a.contains(&15) && a.contains(&20));
+        assert!(!a.insert_range(3..=7));
+        assert!(a.insert(15));
+        assert!(a.remove(&5));
+        assert!(a.ranges().eq([0..=4, 6..=15, 20..=29]));
+
+        let b = IntervalSet::from_ranges([10..=24, 100..=199]);
+        assert!(a.union(&b).ranges().eq([0..=4, 6..=29, 100..=199]));
+        assert!(a.intersection(&b).ranges().eq([10..=15, 20..=24]));
+        assert!(a.without(&b).ranges().eq([0..=4, 6..=9, 25..=29]));
+        assert!(a
+            .xunion(&b)
+            .ranges()
+            .eq([0..=4, 6..=9, 16..=19, 25..=29, 100..=199]));
+
+        let extremes = IntervalSet::from_ranges([i64::MIN..=-1, 1..=i64::MAX]);
+        assert!(extremes.complement().ranges().eq([0..=0]));
+        assert_eq!(extremes.len(), usize::MAX);
+        assert!(IntervalSet::<u8>::new().complement().ranges().eq([0..=255]));
+
+        // Large ranges stay cheap to evaluate
+        let environment = vec![
+            IntervalSet::from_ranges([0..=999_999_999]),
+            IntervalSet::from_ranges([500_000_000..=1_499_999_999]),
+        ];
+        let result = super::try_evaluate("AB^", environment.clone()).unwrap();
+        assert!(result
+            .ranges()
+            .eq([0..=499_999_999, 1_000_000_000..=1_499_999_999]));
+        let universe = IntervalSet::from_ranges([-1_000..=2_000_000_000]);
+        let result = super::try_evaluate_in("AB|!", environment, Universe::Set(&universe));
+        let Ok(super::Extent::Finite(result)) = result else {
+            panic!("Evaluation failed");
+        };
+        assert!(result
+            .ranges()
+            .eq([-1_000..=-1, 1_500_000_000..=2_000_000_000]));
+
+        // Both wider than `usize`, their sizes saturate
+        let hash = |set: &IntervalSet<i64>| {
+            let mut hasher = std::hash::DefaultHasher::new();
+            set.hash(&mut hasher);
+            hasher.finish()
+        };
+        let holed = IntervalSet::from_ranges([i64::MIN..=-1, 1..=i64::MAX]);
+        let full = IntervalSet::from_ranges([i64::MIN..=i64::MAX]);
+        assert_ne!(holed, full);
+        assert_ne!(hash(&holed), hash(&full));
+        let merged = IntervalSet::from_ranges([i64::MIN..=0, 0..=i64::MAX]);
+        assert_eq!(merged, full);
+        assert_eq!(hash(&merged), hash(&full));
+    }
+
+    #[test]
+    fn evaluate_backends() {
+        use super::{DenseSet, SortedSet};
+
+        let input: Vec<SortedSet<i32>> = vec![
+            Set::try_from(vec![3, 1, 2]).unwrap(),
+            Set::try_from(vec![2, 3, 4]).unwrap(),
+        ];
+        let output = super::try_evaluate("AB^", input).unwrap();
+        assert_eq!(Vec::from(output), [1, 4]);
+
+        let input: Vec<DenseSet<i32>> = vec![
+            Set::try_from(vec![3, 1, 2]).unwrap(),
+            Set::try_from(vec![2, 3, 4]).unwrap(),
+        ];
+        let output = super::try_evaluate("AB&", input).unwrap();
+        assert_eq!(Vec::from(output), [2, 3]);
+    }
 }
@@ -0,0 +1,390 @@
+use super::Set;
+use crate::arithmetic::gray_encode;
+
+/// Largest set whose subsets can be indexed by a `u128`
+pub const MAX_ELEMENTS: usize = u128::BITS as usize;
+
+/// Subset of `elements` whose bit is set in `mask`
+fn subset<T>(elements: &[T], mask: u128) -> Set<&T> {
+    Set::wrap(
+        elements
+            .iter()
+            .enumerate()
+            .filter(|&(i, _)| mask >> i & 1 != 0)
+            .map(|(_, e)| e)
+            .collect(),
+    )
+}
+
+/// Mask of the last subset of `len` elements
+fn full(len: usize) -> u128 {
+    assert!(
+        len <= MAX_ELEMENTS,
+        "Cannot index subsets of {len} elements"
+    );
+    match len {
+        MAX_ELEMENTS => u128::MAX,
+        _ => (1 << len) - 1,
+    }
+}
+
+/// Number of items left between two inclusive indices, saturated
+fn remaining(next: Option<u128>, last: u128) -> (usize, Option<usize>) {
+    let Some(next) = next else {
+        return (0, Some(0));
+    };
+    match usize::try_from(last - next)
+        .ok()
+        .and_then(|n| n.checked_add(1))
+    {
+        Some(n) => (n, Some(n)),
+        None => (usize::MAX, None),
+    }
+}
+
+/// Subsets in binary order, the `i`-th one holding the elements whose bit
+/// is set in `i`, see [`Set::powerset`]
+pub struct Powerset<'a, T> {
+    elements: &'a [T],
+    next: Option<u128>,
+    last: u128,
+}
+
+impl<'a, T> Iterator for Powerset<'a, T> {
+    type Item = Set<&'a T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mask = self.next?;
+        self.next = mask.checked_add(1).filter(|&next| next <= self.last);
+        Some(subset(self.elements, mask))
+    }
+
+    fn nth(&mut self, n: usize) -> Option<Self::Item> {
+        self.next = self
+            .next
+            .and_then(|next| next.checked_add(n as u128))
+            .filter(|&next| next <= self.last);
+        self.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        remaining(self.next, self.last)
+    }
+}
+
+/// Subsets in reflected Gray code order, see [`Set::gray_powerset`]
+pub struct GrayPowerset<'a, T> {
+    indices: Powerset<'a, T>,
+}
+
+impl<'a, T> Iterator for GrayPowerset<'a, T> {
+    type Item = Set<&'a T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let indices = &mut self.indices;
+        let index = indices.next?;
+        indices.next = index.checked_add(1).filter(|&next| next <= indices.last);
+        Some(subset(indices.elements, gray_encode(index)))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.indices.size_hint()
+    }
+}
+
+/// Single element change between two consecutive Gray code subsets
+#[derive(Debug, PartialEq, Eq)]
+pub enum Toggle<'a, T> {
+    Insert(&'a T),
+    Remove(&'a T),
+}
+
+/// Changes walking the whole powerset from the empty set, see
+/// [`Set::gray_toggles`]
+pub struct GrayToggles<'a, T> {
+    elements: &'a [T],
+    current: u128,
+    next: Option<u128>,
+    last: u128,
+}
+
+impl<'a, T> Iterator for GrayToggles<'a, T> {
+    type Item = Toggle<'a, T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let index = self.next?;
+        self.next = index.checked_add(1).filter(|&next| next <= self.last);
+        // Consecutive codes differ on the lowest set bit of the index
+        let bit = index.trailing_zeros() as usize;
+        self.current ^= 1 << bit;
+        let element = &self.elements[bit];
+        Some(match self.current >> bit & 1 != 0 {
+            true => Toggle::Insert(element),
+            false => Toggle::Remove(element),
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        remaining(self.next, self.last)
+    }
+}
+
+/// Subsets of a given size in lexicographic order of positions, see
+/// [`Set::combinations`]
+pub struct Combinations<'a, T> {
+    elements: &'a [T],
+    indices: Option<Vec<usize>>,
+}
+
+impl<'a, T> Iterator for Combinations<'a, T> {
+    type Item = Set<&'a T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let indices = self.indices.as_mut()?;
+        let result = Set::wrap(indices.iter().map(|&i| &self.elements[i]).collect());
+
+        // Bump the rightmost index that has room, then reset those after it
+        let (n, k) = (self.elements.len(), indices.len());
+        match (0..k).rev().find(|&i| indices[i] < n - k + i) {
+            Some(i) => {
+                indices[i] += 1;
+                for j in i + 1..k {
+                    indices[j] = indices[j - 1] + 1;
+                }
+            }
+            None => self.indices = None,
+        }
+        Some(result)
+    }
+}
+
+/// Orderings of the elements in lexicographic order of positions, see
+/// [`Set::permutations`]
+pub struct Permutations<'a, T> {
+    elements: &'a [T],
+    indices: Option<Vec<usize>>,
+}
+
+impl<'a, T> Iterator for Permutations<'a, T> {
+    type Item = Vec<&'a T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let indices = self.indices.as_mut()?;
+        let result = indices.iter().map(|&i| &self.elements[i]).collect();
+
+        // Next permutation: swap the last ascent with its smallest larger
+        // successor, then reverse the tail
+        match (1..indices.len())
+            .rev()
+            .find(|&i| indices[i - 1] < indices[i])
+        {
+            Some(i) => {
+                let j = (i..indices.len())
+                    .rev()
+                    .find(|&j| indices[j] > indices[i - 1])
+                    .unwrap();
+                indices.swap(i - 1, j);
+                indices[i..].reverse();
+            }
+            None => self.indices = None,
+        }
+        Some(result)
+    }
+}
+
+impl<T> Set<T> {
+    /// Every subset, lazily and in binary order
+    ///
+    /// # Panics
+    /// With more than [`MAX_ELEMENTS`] elements.
+    pub fn powerset(&self) -> Powerset<'_, T> {
+        Powerset {
+            elements: &self.0,
+            next: Some(0),
+            last: full(self.0.len()),
+        }
+    }
+
+    /// Every subset, each one differing from the previous by one element
+    ///
+    /// # Panics
+    /// With more than [`MAX_ELEMENTS`] elements.
+    pub fn gray_powerset(&self) -> GrayPowerset<'_, T> {
+        GrayPowerset {
+            indices: self.powerset(),
+        }
+    }
+
+    /// Single element changes visiting every subset in [`Set::gray_powerset`]
+    /// order, starting from the empty set, without allocating
+    ///
+    /// # Panics
+    /// With more than [`MAX_ELEMENTS`] elements.
+    pub fn gray_toggles(&self) -> GrayToggles<'_, T> {
+        let last = full(self.0.len());
+        GrayToggles {
+            elements: &self.0,
+            current: 0,
+            next: Some(1).filter(|&next| next <= last),
+            last,
+        }
+    }
+
+    /// Every subset of `k` elements
+    pub fn combinations(&self, k: usize) -> Combinations<'_, T> {
+        Combinations {
+            elements: &self.0,
+            indices: (k <= self.0.len()).then(|| (0..k).collect()),
+        }
+    }
+
+    /// Every ordering of the elements
+    pub fn permutations(&self) -> Permutations<'_, T> {
+        Permutations {
+            elements: &self.0,
+            indices: Some((0..self.0.len()).collect()),
+        }
+    }
+
+    /// Position of a subset in [`Set::powerset`], `None` if it is not one
+    ///
+    /// # Panics
+    /// With more than [`MAX_ELEMENTS`] elements.
+    pub fn rank<'a>(&self, subset: impl IntoIterator<Item = &'a T>) -> Option<u128>
+    where
+        T: Eq + 'a,
+    {
+        assert!(
+            self.0.len() <= MAX_ELEMENTS,
+            "Cannot rank subsets of {} elements",
+            self.0.len()
+        );
+        subset.into_iter().try_fold(0, |rank, value| {
+            let i = self.0.iter().position(|e| e == value)?;
+            Some(rank | 1 << i)
+        })
+    }
+
+    /// Subset at a position of [`Set::powerset`], `None` past the end
+    ///
+    /// # Panics
+    /// With more than [`MAX_ELEMENTS`] elements.
+    pub fn unrank(&self, rank: u128) -> Option<Set<&T>> {
+        (rank <= full(self.0.len())).then(|| subset(&self.0, rank))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Toggle;
+    use crate::set::Set;
+
+    fn set(elements: &[i32]) -> Set<i32> {
+        Set::try_from(elements.to_vec()).unwrap()
+    }
+
+    #[test]
+    fn large_powerset() {
+        let large: Set<u8> = (0..100).collect();
+        let mut subsets = large.powerset();
+        assert_eq!(subsets.size_hint(), (usize::MAX, None));
+        assert_eq!(
+            subsets.nth(5).unwrap(),
+            Set::try_from(vec![&0, &2]).unwrap()
+        );
+
+        let full: Set<u8> = (0..128).collect();
+        let last = full.unrank(u128::MAX).unwrap();
+        assert_eq!(last.len(), 128);
+        assert_eq!(full.rank(last), Some(u128::MAX));
+        assert!(full.powerset().nth(usize::MAX).is_some());
+
+        let small = set(&[1, 2, 3]);
+        let mut subsets = small.powerset();
+        assert_eq!(subsets.size_hint(), (8, Some(8)));
+        assert!(subsets.nth(7).is_some());
+        assert!(subsets.next().is_none());
+    }
+
+    #[test]
+    #[should_panic]
+    fn too_large_powerset() {
+        let set: Set<u8> = (0..129).collect();
+        set.powerset();
+    }
+
+    #[test]
+    fn gray_powerset() {
+        use crate::arithmetic::gray_code;
+
+        let set = set(&[1, 2, 3, 4, 5]);
+        let subsets: Vec<_> = set.gray_powerset().collect();
+        assert_eq!(subsets.len(), 32);
+        for (i, subset) in subsets.iter().enumerate() {
+            assert_eq!(
+                set.rank(subset.iter().copied()),
+                Some(gray_code(i as u32) as u128)
+            );
+        }
+        for pair in subsets.windows(2) {
+            assert_eq!(pair[0].xunion(&pair[1]).len(), 1);
+        }
+
+        // Replaying the toggles visits the same subsets
+        let mut current = Set::new();
+        let mut replayed = vec![current.clone()];
+        for toggle in set.gray_toggles() {
+            match toggle {
+                Toggle::Insert(e) => assert!(current.insert(e)),
+                Toggle::Remove(e) => assert!(current.remove(&e)),
+            }
+            replayed.push(current.clone());
+        }
+        assert_eq!(replayed, subsets);
+        assert_eq!(Set::<i32>::new().gray_toggles().count(), 0);
+    }
+
+    #[test]
+    fn combinations() {
+        let set = set(&[1, 2, 3, 4]);
+        let pairs: Vec<_> = set.combinations(2).map(|s| s.cloned()).collect();
+        let expected = [[1, 2], [1, 3], [1, 4], [2, 3], [2, 4], [3, 4]];
+        assert!(pairs.into_iter().eq(expected.iter().map(|p| self::set(p))));
+
+        for k in 0..=5 {
+            let expected = set.powerset().filter(|s| s.len() == k).count();
+            assert_eq!(set.combinations(k).count(), expected);
+        }
+        assert_eq!(Set::<i32>::new().combinations(0).count(), 1);
+    }
+
+    #[test]
+    fn permutations() {
+        let set = set(&[1, 2, 3]);
+        let orders: Vec<Vec<i32>> = set
+            .permutations()
+            .map(|p| p.into_iter().copied().collect())
+            .collect();
+        let expected = [
+            [1, 2, 3],
+            [1, 3, 2],
+            [2, 1, 3],
+            [2, 3, 1],
+            [3, 1, 2],
+            [3, 2, 1],
+        ];
+        assert_eq!(orders, expected);
+        assert_eq!(Set::<i32>::new().permutations().count(), 1);
+    }
+
+    #[test]
+    fn rank() {
+        let set = set(&[1, 2, 3, 4]);
+        for (i, subset) in set.powerset().enumerate() {
+            assert_eq!(set.rank(subset.iter().copied()), Some(i as u128));
+            assert_eq!(set.unrank(i as u128), Some(subset));
+        }
+        assert_eq!(set.rank(&[5]), None);
+        assert_eq!(set.unrank(16), None);
+    }
+}
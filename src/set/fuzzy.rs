@@ -0,0 +1,377 @@
+use core::fmt;
+
+use super::{EvaluationError, Set, Storage};
+use crate::algebra::BooleanAlgebra;
+use crate::expression::{Expression, Symbol};
+
+/// Conjunction of membership degrees, disjunctions use the dual conorm
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TNorm {
+    /// Gödel, `min(a, b)`
+    Minimum,
+    /// Goguen, `a * b`
+    Product,
+    /// Łukasiewicz, `max(0, a + b - 1)`
+    Lukasiewicz,
+}
+
+impl TNorm {
+    pub fn norm(self, a: f64, b: f64) -> f64 {
+        match self {
+            TNorm::Minimum => a.min(b),
+            TNorm::Product => a * b,
+            TNorm::Lukasiewicz => (a + b - 1.0).max(0.0),
+        }
+    }
+
+    pub fn conorm(self, a: f64, b: f64) -> f64 {
+        1.0 - self.norm(1.0 - a, 1.0 - b)
+    }
+}
+
+/// Values with a membership degree in `]0, 1]`, absent ones having `0`
+pub struct FuzzySet<T>(Vec<(T, f64)>);
+
+impl<T> Default for FuzzySet<T> {
+    fn default() -> Self {
+        Self(Vec::new())
+    }
+}
+
+impl<T: Clone> Clone for FuzzySet<T> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for FuzzySet<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+/// Values with their degree, `{ 1/0.5, 2/1 }`
+impl<T: fmt::Display> fmt::Display for FuzzySet<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{{")?;
+        let mut members = self.iter();
+        if let Some((value, degree)) = members.next() {
+            write!(f, " {value}/{degree}")?;
+        }
+        for (value, degree) in members {
+            write!(f, ", {value}/{degree}")?;
+        }
+        write!(f, " }}")
+    }
+}
+
+impl<T: Eq> PartialEq for FuzzySet<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.len() == other.0.len() && self.iter().all(|(e, d)| other.degree(e) == d)
+    }
+}
+
+impl<T: Eq> FromIterator<(T, f64)> for FuzzySet<T> {
+    fn from_iter<I: IntoIterator<Item = (T, f64)>>(iter: I) -> Self {
+        let mut set = Self::new();
+        for (value, degree) in iter {
+            set.insert(value, degree);
+        }
+        set
+    }
+}
+
+/// Crisp set, every member having degree `1`
+impl<T: Eq, S: Storage<T>> From<Set<T, S>> for FuzzySet<T> {
+    fn from(set: Set<T, S>) -> Self {
+        Self(set.into_iter().map(|e| (e, 1.0)).collect())
+    }
+}
+
+impl<T> FuzzySet<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of values with a positive degree
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Values with a positive degree, and that degree
+    pub fn iter(&self) -> impl Iterator<Item = (&T, f64)> {
+        self.0.iter().map(|(e, d)| (e, *d))
+    }
+
+    /// Sum of the degrees
+    pub fn cardinality(&self) -> f64 {
+        self.0.iter().map(|&(_, d)| d).sum()
+    }
+
+    /// Values with a degree of at least `alpha`
+    pub fn alpha_cut(&self, alpha: f64) -> Set<&T> {
+        Set::wrap(
+            self.0
+                .iter()
+                .filter(|&&(_, d)| d >= alpha)
+                .map(|(e, _)| e)
+                .collect(),
+        )
+    }
+
+    /// Values with a positive degree
+    pub fn support(&self) -> Set<&T> {
+        Set::wrap(self.0.iter().map(|(e, _)| e).collect())
+    }
+}
+
+impl<T: Eq> FuzzySet<T> {
+    pub fn degree(&self, value: &T) -> f64 {
+        self.0
+            .iter()
+            .find(|(e, _)| e == value)
+            .map_or(0.0, |&(_, d)| d)
+    }
+
+    /// Set the degree of a value, `0` removing it
+    ///
+    /// # Panics
+    /// If the degree is not within `[0, 1]`.
+    pub fn insert(&mut self, value: T, degree: f64) {
+        assert!(
+            (0.0..=1.0).contains(&degree),
+            "Membership degree {degree} outside [0, 1]"
+        );
+        let position = self.0.iter().position(|(e, _)| *e == value);
+        match (position, degree == 0.0) {
+            (Some(i), true) => {
+                self.0.swap_remove(i);
+            }
+            (Some(i), false) => self.0[i].1 = degree,
+            (None, true) => (),
+            (None, false) => self.0.push((value, degree)),
+        }
+    }
+}
+
+impl<T: Eq + Clone> FuzzySet<T> {
+    /// Combine degrees of the values of both sets
+    fn zip(&self, other: &Self, combine: impl Fn(f64, f64) -> f64) -> Self {
+        let ours = self.iter().map(|(e, d)| (e, combine(d, other.degree(e))));
+        let theirs = other
+            .iter()
+            .filter(|(e, _)| self.degree(e) == 0.0)
+            .map(|(e, d)| (e, combine(0.0, d)));
+        ours.chain(theirs).map(|(e, d)| (e.clone(), d)).collect()
+    }
+
+    pub fn union(&self, other: &Self, norm: TNorm) -> Self {
+        self.zip(other, |a, b| norm.conorm(a, b))
+    }
+
+    pub fn intersection(&self, other: &Self, norm: TNorm) -> Self {
+        self.zip(other, |a, b| norm.norm(a, b))
+    }
+}
+
+/// Membership degree of a value
+///
+/// Constants do not know the t-norm, which they pick up from the
+/// variables they are combined with; it does not matter between them.
+#[derive(Clone, Copy)]
+struct Grade {
+    degree: f64,
+    norm: Option<TNorm>,
+}
+
+impl Grade {
+    fn zip(&self, other: &Self, combine: impl Fn(TNorm, f64, f64) -> f64) -> Self {
+        let norm = self.norm.or(other.norm);
+        Self {
+            degree: combine(norm.unwrap_or(TNorm::Minimum), self.degree, other.degree),
+            norm,
+        }
+    }
+}
+
+impl BooleanAlgebra for Grade {
+    fn constant(value: bool) -> Self {
+        Self {
+            degree: value as u8 as f64,
+            norm: None,
+        }
+    }
+
+    fn and(&self, other: &Self) -> Self {
+        self.zip(other, TNorm::norm)
+    }
+
+    fn or(&self, other: &Self) -> Self {
+        self.zip(other, TNorm::conorm)
+    }
+
+    fn not(&self) -> Self {
+        Self {
+            degree: 1.0 - self.degree,
+            norm: self.norm,
+        }
+    }
+}
+
+/// Evaluate a set formula over fuzzy sets, value by value
+///
+/// The universe is the union of the supports of the environment, `!A`
+/// being `1 - A` there. `^`, `>` and `=` expand to `!`, `&` and `|`.
+pub fn try_evaluate<T: Eq + Clone>(
+    expression: &str,
+    environment: Vec<FuzzySet<T>>,
+    norm: TNorm,
+) -> Result<FuzzySet<T>, EvaluationError> {
+    let expression: Expression = expression.parse()?;
+    evaluate(&expression, &environment, norm)
+}
+
+/// Interpret an expression over fuzzy sets, `A` being the first one
+pub fn evaluate<T: Eq + Clone>(
+    expression: &Expression,
+    environment: &[FuzzySet<T>],
+    norm: TNorm,
+) -> Result<FuzzySet<T>, EvaluationError> {
+    if expression
+        .symbols()
+        .any(|symbol| (symbol - b'A') as usize >= environment.len())
+    {
+        return Err(EvaluationError::UnspecifiedVar);
+    }
+
+    let mut universe: Vec<&T> = Vec::new();
+    for set in environment {
+        universe.extend(set.iter().map(|(e, _)| e));
+    }
+    let universe = Set::<&T>::from_iter(universe);
+
+    let mut result = FuzzySet::new();
+    for &element in universe.iter() {
+        let value = |symbol: Symbol| {
+            let set = &environment[(symbol - b'A') as usize];
+            Some(Grade {
+                degree: set.degree(element),
+                norm: Some(norm),
+            })
+        };
+        let grade = expression.interpret(&value).unwrap();
+        result.insert(element.clone(), grade.degree.clamp(0.0, 1.0));
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FuzzySet, TNorm};
+    use crate::set::Set;
+
+    const NORMS: [TNorm; 3] = [TNorm::Minimum, TNorm::Product, TNorm::Lukasiewicz];
+
+    fn fuzzy(members: &[(i32, f64)]) -> FuzzySet<i32> {
+        members.iter().copied().collect()
+    }
+
+    fn assert_close(a: &FuzzySet<i32>, b: &FuzzySet<i32>) {
+        let close = |x: f64, y: f64| (x - y).abs() < 1e-9;
+        assert!(a.iter().all(|(e, d)| close(d, b.degree(e))), "{a} != {b}");
+        assert!(b.iter().all(|(e, d)| close(d, a.degree(e))), "{a} != {b}");
+    }
+
+    #[test]
+    fn api() {
+        let mut set = fuzzy(&[(1, 0.5), (2, 1.0), (3, 0.0)]);
+        assert_eq!(set.len(), 2);
+        assert_eq!(set.degree(&1), 0.5);
+        assert_eq!(set.degree(&3), 0.0);
+        assert_eq!(set.cardinality(), 1.5);
+        assert_eq!(set.to_string(), "{ 1/0.5, 2/1 }");
+        assert_eq!(set.alpha_cut(0.75), Set::try_from(vec![&2]).unwrap());
+        set.insert(1, 0.0);
+        assert_eq!(set.support(), Set::try_from(vec![&2]).unwrap());
+    }
+
+    #[test]
+    #[should_panic]
+    fn degree_out_of_range() {
+        fuzzy(&[(1, 1.5)]);
+    }
+
+    #[test]
+    fn norms() {
+        let a = fuzzy(&[(1, 0.5), (2, 0.8)]);
+        let b = fuzzy(&[(1, 0.4), (3, 1.0)]);
+        let intersection = |norm| a.intersection(&b, norm);
+        let union = |norm| a.union(&b, norm);
+        assert_close(&intersection(TNorm::Minimum), &fuzzy(&[(1, 0.4)]));
+        assert_close(&intersection(TNorm::Product), &fuzzy(&[(1, 0.2)]));
+        assert_close(&intersection(TNorm::Lukasiewicz), &fuzzy(&[]));
+        assert_close(
+            &union(TNorm::Minimum),
+            &fuzzy(&[(1, 0.5), (2, 0.8), (3, 1.0)]),
+        );
+        assert_close(
+            &union(TNorm::Product),
+            &fuzzy(&[(1, 0.7), (2, 0.8), (3, 1.0)]),
+        );
+        assert_close(
+            &union(TNorm::Lukasiewicz),
+            &fuzzy(&[(1, 0.9), (2, 0.8), (3, 1.0)]),
+        );
+    }
+
+    #[test]
+    fn evaluate() {
+        let environment = vec![fuzzy(&[(1, 0.5), (2, 0.8)]), fuzzy(&[(1, 0.4), (3, 1.0)])];
+        let evaluate =
+            |formula: &str, norm| super::try_evaluate(formula, environment.clone(), norm).unwrap();
+
+        for norm in NORMS {
+            assert_close(
+                &evaluate("AB&", norm),
+                &environment[0].intersection(&environment[1], norm),
+            );
+            assert_close(
+                &evaluate("AB|", norm),
+                &environment[0].union(&environment[1], norm),
+            );
+            // De Morgan holds for a t-norm and its dual conorm
+            assert_close(&evaluate("AB&!", norm), &evaluate("A!B!|", norm));
+            assert_close(&evaluate("A1&0|", norm), &environment[0]);
+        }
+        assert_close(
+            &evaluate("A!", TNorm::Minimum),
+            &fuzzy(&[(1, 0.5), (2, 0.2), (3, 1.0)]),
+        );
+        // Only Łukasiewicz keeps the excluded middle
+        assert_close(
+            &evaluate("AA!|", TNorm::Lukasiewicz),
+            &fuzzy(&[(1, 1.0), (2, 1.0), (3, 1.0)]),
+        );
+        assert_close(
+            &evaluate("AA!|", TNorm::Minimum),
+            &fuzzy(&[(1, 0.5), (2, 0.8), (3, 1.0)]),
+        );
+
+        // Crisp sets evaluate as they do with `set::try_evaluate`
+        let sets = [[0, 1, 2], [2, 3, 4]].map(|s| Set::try_from(s.to_vec()).unwrap());
+        for formula in ["AB|", "AB&", "AB^", "A!B|", "AB>", "AB="] {
+            let expected = crate::set::try_evaluate::<i32, Vec<i32>>(formula, sets.to_vec());
+            let crisp: Vec<_> = sets.iter().cloned().map(FuzzySet::from).collect();
+            for norm in NORMS {
+                let result = super::try_evaluate(formula, crisp.clone(), norm).unwrap();
+                assert_close(&result, &FuzzySet::from(expected.as_ref().unwrap().clone()));
+            }
+        }
+
+        assert!(super::try_evaluate("AB|", vec![fuzzy(&[])], TNorm::Minimum).is_err());
+    }
+}
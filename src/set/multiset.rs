@@ -0,0 +1,336 @@
+use core::fmt;
+
+use super::{EvaluationError, Set, Storage};
+use crate::algebra::BooleanAlgebra;
+use crate::expression::{Expression, Symbol};
+
+/// Bag of values, each one held a positive number of times
+///
+/// Like the default [`Set`] backend it only needs `Eq`, at the price of
+/// quadratic operations.
+pub struct MultiSet<T>(Vec<(T, usize)>);
+
+impl<T> Default for MultiSet<T> {
+    fn default() -> Self {
+        Self(Vec::new())
+    }
+}
+
+impl<T: Clone> Clone for MultiSet<T> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for MultiSet<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+/// Every copy is listed, `{ 1, 1, 2 }`
+impl<T: fmt::Display> fmt::Display for MultiSet<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{{")?;
+        let mut copies = self.iter().flat_map(|(e, n)| core::iter::repeat_n(e, n));
+        if let Some(first) = copies.next() {
+            write!(f, " {first}")?;
+        }
+        for copy in copies {
+            write!(f, ", {copy}")?;
+        }
+        write!(f, " }}")
+    }
+}
+
+impl<T: Eq> PartialEq for MultiSet<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.len() == other.0.len() && self.iter().all(|(e, n)| other.count(e) == n)
+    }
+}
+
+impl<T: Eq> Eq for MultiSet<T> {}
+
+impl<T: Eq> FromIterator<T> for MultiSet<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut bag = Self::new();
+        bag.extend(iter);
+        bag
+    }
+}
+
+impl<T: Eq> Extend<T> for MultiSet<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for value in iter {
+            self.insert(value);
+        }
+    }
+}
+
+impl<T: Eq, S: Storage<T>> From<Set<T, S>> for MultiSet<T> {
+    fn from(set: Set<T, S>) -> Self {
+        Self(set.into_iter().map(|e| (e, 1)).collect())
+    }
+}
+
+impl<T> MultiSet<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of values, counting every copy
+    pub fn len(&self) -> usize {
+        self.0.iter().map(|&(_, n)| n).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Distinct values with their multiplicity
+    pub fn iter(&self) -> impl Iterator<Item = (&T, usize)> {
+        self.0.iter().map(|(e, n)| (e, *n))
+    }
+}
+
+impl<T: Eq> MultiSet<T> {
+    /// Number of copies of a value
+    pub fn count(&self, value: &T) -> usize {
+        self.0
+            .iter()
+            .find(|(e, _)| e == value)
+            .map_or(0, |&(_, n)| n)
+    }
+
+    pub fn insert(&mut self, value: T) {
+        self.insert_many(value, 1)
+    }
+
+    pub fn insert_many(&mut self, value: T, copies: usize) {
+        if copies == 0 {
+            return;
+        }
+        match self.0.iter_mut().find(|(e, _)| *e == value) {
+            Some((_, n)) => *n += copies,
+            None => self.0.push((value, copies)),
+        }
+    }
+
+    /// Remove one copy of a value, `false` if there was none
+    pub fn remove(&mut self, value: &T) -> bool {
+        let Some(i) = self.0.iter().position(|(e, _)| e == value) else {
+            return false;
+        };
+        self.0[i].1 -= 1;
+        if self.0[i].1 == 0 {
+            self.0.swap_remove(i);
+        }
+        true
+    }
+
+    /// Whether every value has at most as many copies in `other`
+    pub fn is_subset(&self, other: &Self) -> bool {
+        self.iter().all(|(e, n)| n <= other.count(e))
+    }
+
+    /// Distinct values, forgetting multiplicities
+    pub fn support(&self) -> Set<&T> {
+        Set::wrap(self.0.iter().map(|(e, _)| e).collect())
+    }
+}
+
+impl<T: Eq + Clone> MultiSet<T> {
+    /// Combine multiplicities of the values of both bags
+    fn zip(&self, other: &Self, combine: impl Fn(usize, usize) -> usize) -> Self {
+        let ours = self.iter().map(|(e, n)| (e, combine(n, other.count(e))));
+        let theirs = other
+            .iter()
+            .filter(|(e, _)| self.count(e) == 0)
+            .map(|(e, n)| (e, combine(0, n)));
+        Self(
+            ours.chain(theirs)
+                .filter(|&(_, n)| n != 0)
+                .map(|(e, n)| (e.clone(), n))
+                .collect(),
+        )
+    }
+
+    /// Largest multiplicity of each value
+    pub fn union(&self, other: &Self) -> Self {
+        self.zip(other, usize::max)
+    }
+
+    /// Smallest multiplicity of each value
+    pub fn intersection(&self, other: &Self) -> Self {
+        self.zip(other, usize::min)
+    }
+
+    /// Copies left after removing those of `other`
+    pub fn difference(&self, other: &Self) -> Self {
+        self.zip(other, usize::saturating_sub)
+    }
+
+    /// Copies in exactly one of the bags, `|a - b|` of each value
+    pub fn symmetric_difference(&self, other: &Self) -> Self {
+        self.zip(other, usize::abs_diff)
+    }
+
+    /// Every copy of both bags
+    pub fn sum(&self, other: &Self) -> Self {
+        self.zip(other, |a, b| a + b)
+    }
+}
+
+/// Multiplicity of a value within its multiplicity in the universe
+///
+/// Constants do not know that bound, which they pick up from the
+/// variables they are combined with.
+#[derive(Clone, Copy)]
+struct Multiplicity {
+    count: usize,
+    bound: Option<usize>,
+}
+
+impl Multiplicity {
+    fn new(count: usize, bound: Option<usize>) -> Self {
+        Self {
+            count: bound.map_or(count, |bound| count.min(bound)),
+            bound,
+        }
+    }
+
+    fn zip(&self, other: &Self, combine: impl Fn(usize, usize) -> usize) -> Self {
+        let bound = self.bound.or(other.bound);
+        let (a, b) = (Self::new(self.count, bound), Self::new(other.count, bound));
+        Self::new(combine(a.count, b.count), bound)
+    }
+}
+
+impl BooleanAlgebra for Multiplicity {
+    fn constant(value: bool) -> Self {
+        match value {
+            true => Self::new(usize::MAX, None),
+            false => Self::new(0, None),
+        }
+    }
+
+    fn and(&self, other: &Self) -> Self {
+        self.zip(other, usize::min)
+    }
+
+    fn or(&self, other: &Self) -> Self {
+        self.zip(other, usize::max)
+    }
+
+    fn not(&self) -> Self {
+        Self::new(self.bound.unwrap_or(usize::MAX) - self.count, self.bound)
+    }
+
+    fn xor(&self, other: &Self) -> Self {
+        self.zip(other, usize::abs_diff)
+    }
+}
+
+/// Evaluate a set formula over bags, value by value
+///
+/// The universe is the union of the environment, so `!A` holds the copies
+/// of the universe missing from `A` and `^` is the symmetric difference.
+/// Bags only form a lattice, so identities like `A | !A = 1` do not hold.
+pub fn try_evaluate<T: Eq + Clone>(
+    expression: &str,
+    environment: Vec<MultiSet<T>>,
+) -> Result<MultiSet<T>, EvaluationError> {
+    let expression: Expression = expression.parse()?;
+    evaluate(&expression, &environment)
+}
+
+/// Interpret an expression over bags, `A` being the first one
+pub fn evaluate<T: Eq + Clone>(
+    expression: &Expression,
+    environment: &[MultiSet<T>],
+) -> Result<MultiSet<T>, EvaluationError> {
+    if expression
+        .symbols()
+        .any(|symbol| (symbol - b'A') as usize >= environment.len())
+    {
+        return Err(EvaluationError::UnspecifiedVar);
+    }
+
+    let universe = environment.iter().fold(MultiSet::new(), |u, b| u.union(b));
+    let mut result = MultiSet::new();
+    for (element, bound) in universe.iter() {
+        let value = |symbol: Symbol| {
+            let bag = &environment[(symbol - b'A') as usize];
+            Some(Multiplicity::new(bag.count(element), Some(bound)))
+        };
+        let multiplicity = expression.interpret(&value).unwrap();
+        result.insert_many(element.clone(), multiplicity.count.min(bound));
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MultiSet;
+
+    fn bag(values: &[i32]) -> MultiSet<i32> {
+        values.iter().copied().collect()
+    }
+
+    #[test]
+    fn api() {
+        let mut a = bag(&[1, 1, 2]);
+        assert_eq!(a.len(), 3);
+        assert_eq!(a.count(&1), 2);
+        assert_eq!(a.count(&3), 0);
+        assert_eq!(a.to_string(), "{ 1, 1, 2 }");
+        assert!(a.remove(&1));
+        assert!(!a.remove(&3));
+        assert_eq!(a, bag(&[2, 1]));
+        assert!(a.is_subset(&bag(&[1, 2, 2])));
+        assert!(!bag(&[1, 1]).is_subset(&a));
+        assert_eq!(bag(&[3, 3, 1]).support().len(), 2);
+    }
+
+    #[test]
+    fn operations() {
+        let a = bag(&[1, 1, 1, 2, 3]);
+        let b = bag(&[1, 2, 2, 4]);
+        assert_eq!(a.union(&b), bag(&[1, 1, 1, 2, 2, 3, 4]));
+        assert_eq!(a.intersection(&b), bag(&[1, 2]));
+        assert_eq!(a.difference(&b), bag(&[1, 1, 3]));
+        assert_eq!(a.symmetric_difference(&b), bag(&[1, 1, 2, 3, 4]));
+        assert_eq!(a.sum(&b), bag(&[1, 1, 1, 1, 2, 2, 2, 3, 4]));
+    }
+
+    #[test]
+    fn evaluate() {
+        let check = |formula: &str, expected: &[i32]| {
+            let environment = vec![bag(&[1, 1, 1, 2, 3]), bag(&[1, 2, 2, 4])];
+            let result = super::try_evaluate(formula, environment).unwrap();
+            assert_eq!(result, bag(expected), "{formula}");
+        };
+        check("AB|", &[1, 1, 1, 2, 2, 3, 4]);
+        check("AB&", &[1, 2]);
+        check("AB!&", &[1, 1, 3]);
+        check("AB^", &[1, 1, 2, 3, 4]);
+        check("A!", &[2, 4]);
+        check("A1&", &[1, 1, 1, 2, 3]);
+        check("B0|!", &[1, 1, 3]);
+        check("1", &[1, 1, 1, 2, 2, 3, 4]);
+        // Bags are not complemented: `A | !A` misses copies
+        check("AA!|", &[1, 1, 1, 2, 3, 4]);
+
+        // Plain sets evaluate as they do with `set::try_evaluate`
+        let sets = [[0, 1, 2], [2, 3, 4]].map(|s| crate::set::Set::try_from(s.to_vec()).unwrap());
+        for formula in ["AB|", "AB&", "AB^", "A!B|", "AB>", "AB="] {
+            let expected = crate::set::try_evaluate::<i32, Vec<i32>>(formula, sets.to_vec());
+            let bags = sets.iter().cloned().map(MultiSet::from).collect();
+            let result = super::try_evaluate(formula, bags).unwrap();
+            assert_eq!(result, MultiSet::from(expected.unwrap()), "{formula}");
+        }
+
+        assert!(super::try_evaluate("AB|", vec![bag(&[1])]).is_err());
+        assert!(super::try_evaluate("AB|", vec![MultiSet::<i32>::new()]).is_err());
+    }
+}
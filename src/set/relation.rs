@@ -0,0 +1,287 @@
+use core::hash::Hash;
+use std::collections::{HashMap, HashSet};
+
+use super::Set;
+
+/// Binary relation between two sets, holding the pairs `(x, y)` where `x`
+/// relates to `y`
+impl<T: Eq + Clone, U: Eq + Clone> Set<(T, U)> {
+    /// Values having an image
+    pub fn domain(&self) -> Set<T> {
+        self.iter().map(|(x, _)| x.clone()).collect()
+    }
+
+    /// Values being an image
+    pub fn range(&self) -> Set<U> {
+        self.iter().map(|(_, y)| y.clone()).collect()
+    }
+
+    /// Values `x` relates to
+    pub fn image(&self, x: &T) -> Set<&U> {
+        self.iter()
+            .filter(|(a, _)| a == x)
+            .map(|(_, y)| y)
+            .collect()
+    }
+
+    /// Values relating to `y`
+    pub fn preimage(&self, y: &U) -> Set<&T> {
+        self.iter()
+            .filter(|(_, b)| b == y)
+            .map(|(x, _)| x)
+            .collect()
+    }
+
+    /// Pairs swapped
+    pub fn inverse(&self) -> Set<(U, T)> {
+        Set::wrap(self.iter().map(|(x, y)| (y.clone(), x.clone())).collect())
+    }
+
+    /// Images of each value, in the order of the pairs
+    fn images(&self) -> HashMap<&T, Vec<&U>>
+    where
+        T: Hash,
+    {
+        let mut images: HashMap<&T, Vec<&U>> = HashMap::new();
+        for (x, y) in self {
+            images.entry(x).or_default().push(y);
+        }
+        images
+    }
+
+    /// `self` followed by `other`, relating `x` to `z` when `x` relates to
+    /// some `y` relating to `z`
+    ///
+    /// Linear in the number of pairs of both relations and of the result,
+    /// `other` being indexed by its first component.
+    pub fn compose<V: Eq + Hash + Clone>(&self, other: &Set<(U, V)>) -> Set<(T, V)>
+    where
+        T: Hash,
+        U: Hash,
+    {
+        let images = other.images();
+        let mut seen = HashSet::new();
+        Set::wrap(
+            self.iter()
+                .flat_map(|(x, y)| {
+                    let zs = images.get(y).map_or(&[][..], Vec::as_slice);
+                    zs.iter().map(move |&z| (x, z))
+                })
+                .filter(|&pair| seen.insert(pair))
+                .map(|(x, z)| (x.clone(), z.clone()))
+                .collect(),
+        )
+    }
+
+    /// Whether every value of `domain`, and only those, has exactly one
+    /// image
+    pub fn is_function(&self, domain: &Set<T>) -> bool {
+        self.iter().all(|(x, _)| domain.contains(x))
+            && domain.iter().all(|x| self.image(x).len() == 1)
+    }
+
+    /// Whether no two values share an image
+    pub fn is_injective(&self) -> bool {
+        self.iter().all(|(_, y)| self.preimage(y).len() == 1)
+    }
+
+    /// Whether every value of `codomain` is an image
+    pub fn is_surjective(&self, codomain: &Set<U>) -> bool {
+        codomain.iter().all(|y| self.iter().any(|(_, b)| b == y))
+    }
+}
+
+/// Relation on a single set
+impl<T: Eq + Clone> Set<(T, T)> {
+    /// Values of either side of a pair
+    pub fn field(&self) -> Set<T> {
+        self.domain().union(&self.range())
+    }
+
+    pub fn is_reflexive(&self, universe: &Set<T>) -> bool {
+        universe
+            .iter()
+            .all(|x| self.contains(&(x.clone(), x.clone())))
+    }
+
+    pub fn is_symmetric(&self) -> bool {
+        self.iter()
+            .all(|(x, y)| self.contains(&(y.clone(), x.clone())))
+    }
+
+    /// Whether only equal values relate both ways
+    pub fn is_antisymmetric(&self) -> bool {
+        self.iter()
+            .all(|(x, y)| x == y || !self.contains(&(y.clone(), x.clone())))
+    }
+
+    pub fn is_transitive(&self) -> bool
+    where
+        T: Hash,
+    {
+        let pairs: HashSet<&(T, T)> = self.iter().collect();
+        self.compose(self).iter().all(|pair| pairs.contains(pair))
+    }
+
+    pub fn is_equivalence(&self, universe: &Set<T>) -> bool
+    where
+        T: Hash,
+    {
+        self.is_reflexive(universe) && self.is_symmetric() && self.is_transitive()
+    }
+
+    pub fn is_partial_order(&self, universe: &Set<T>) -> bool
+    where
+        T: Hash,
+    {
+        self.is_reflexive(universe) && self.is_antisymmetric() && self.is_transitive()
+    }
+
+    /// Smallest reflexive relation on `universe` containing this one
+    pub fn reflexive_closure(&self, universe: &Set<T>) -> Self {
+        let identity = universe.iter().map(|x| (x.clone(), x.clone())).collect();
+        self.union(&identity)
+    }
+
+    /// Smallest symmetric relation containing this one
+    pub fn symmetric_closure(&self) -> Self {
+        self.union(&self.inverse())
+    }
+
+    /// Smallest transitive relation containing this one
+    ///
+    /// Walks the values reachable from each value of the domain, in
+    /// `O(domain * pairs)`.
+    pub fn transitive_closure(&self) -> Self
+    where
+        T: Hash,
+    {
+        let images = self.images();
+        let mut closure = Vec::new();
+        let mut sources = HashSet::new();
+        for (x, _) in self {
+            if !sources.insert(x) {
+                continue;
+            }
+            let mut reached = HashSet::new();
+            let mut stack = images[x].clone();
+            while let Some(y) = stack.pop() {
+                if reached.insert(y) {
+                    closure.push((x.clone(), y.clone()));
+                    stack.extend(images.get(y).into_iter().flatten());
+                }
+            }
+        }
+        Set::wrap(closure)
+    }
+
+    /// Classes of an equivalence relation on `universe`, `None` if it is
+    /// not one
+    pub fn partition(&self, universe: &Set<T>) -> Option<Vec<Set<T>>>
+    where
+        T: Hash,
+    {
+        if !self.is_equivalence(universe) || !self.field().is_subset(universe) {
+            return None;
+        }
+        let mut classes: Vec<Set<T>> = Vec::new();
+        for x in universe {
+            if !classes.iter().any(|class| class.contains(x)) {
+                classes.push(self.image(x).cloned());
+            }
+        }
+        Some(classes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::set::Set;
+
+    fn relation(pairs: &[(i32, i32)]) -> Set<(i32, i32)> {
+        pairs.iter().copied().collect()
+    }
+
+    fn set(values: &[i32]) -> Set<i32> {
+        values.iter().copied().collect()
+    }
+
+    #[test]
+    fn operations() {
+        let r = relation(&[(1, 2), (2, 3), (3, 3)]);
+        assert_eq!(r.inverse(), relation(&[(2, 1), (3, 2), (3, 3)]));
+        assert_eq!(r.compose(&r), relation(&[(1, 3), (2, 3), (3, 3)]));
+        assert_eq!(r.domain(), set(&[1, 2, 3]));
+        assert_eq!(r.range(), set(&[2, 3]));
+        assert_eq!(r.image(&3), Set::try_from(vec![&3]).unwrap());
+
+        let names: Set<(i32, &str)> = [(1, "one"), (2, "two")].into_iter().collect();
+        let lengths: Set<(&str, usize)> = [("one", 3), ("two", 3)].into_iter().collect();
+        let composed: Set<(i32, usize)> = [(1, 3), (2, 3)].into_iter().collect();
+        assert_eq!(names.compose(&lengths), composed);
+    }
+
+    #[test]
+    fn closures() {
+        let r = relation(&[(1, 2), (2, 3), (3, 4)]);
+        let universe = set(&[1, 2, 3, 4]);
+        assert_eq!(
+            r.transitive_closure(),
+            relation(&[(1, 2), (1, 3), (1, 4), (2, 3), (2, 4), (3, 4)])
+        );
+        assert_eq!(
+            r.symmetric_closure(),
+            relation(&[(1, 2), (2, 1), (2, 3), (3, 2), (3, 4), (4, 3)])
+        );
+        let reflexive = r.reflexive_closure(&universe);
+        assert_eq!(reflexive.len(), 7);
+        assert!(reflexive.is_reflexive(&universe));
+        assert!(!r.is_reflexive(&universe));
+
+        let order = r.transitive_closure().reflexive_closure(&universe);
+        assert!(order.is_partial_order(&universe));
+        assert!(!order.is_equivalence(&universe));
+        let equivalence = r.symmetric_closure().transitive_closure();
+        assert!(equivalence.is_equivalence(&universe));
+        assert!(!equivalence.is_antisymmetric());
+
+        // Long chains stay cheap to close
+        let chain: Set<(u32, u32)> = (0..200).map(|x| (x, x + 1)).collect();
+        let closure = chain.transitive_closure();
+        assert_eq!(closure.len(), 200 * 201 / 2);
+        assert!(closure.is_transitive());
+        assert_eq!(closure.compose(&chain).len(), 199 * 200 / 2);
+    }
+
+    #[test]
+    fn functions() {
+        let domain = set(&[1, 2, 3]);
+        let square = relation(&[(1, 1), (2, 4), (3, 9)]);
+        assert!(square.is_function(&domain));
+        assert!(square.is_injective());
+        assert!(square.is_surjective(&set(&[1, 4, 9])));
+        assert!(!square.is_surjective(&set(&[1, 2, 4, 9])));
+        assert!(!square.is_function(&set(&[1, 2, 3, 4])));
+        assert!(!square.is_function(&set(&[1, 2])));
+
+        let parity = relation(&[(1, 1), (2, 0), (3, 1)]);
+        assert!(parity.is_function(&domain));
+        assert!(!parity.is_injective());
+        assert!(!relation(&[(1, 1), (1, 2)]).is_function(&set(&[1])));
+    }
+
+    #[test]
+    fn partition() {
+        let universe = set(&[1, 2, 3, 4, 5]);
+        let same_parity: Set<(i32, i32)> = universe
+            .product(&universe)
+            .into_iter()
+            .filter(|(x, y)| x % 2 == y % 2)
+            .collect();
+        assert_eq!(
+            same_parity.partition(&universe),
+            Some(vec![set(&[1, 3, 5]), set(&[2, 4])])
+        );
+        assert_eq!(relation(&[(1, 2)]).partition(&universe), None);
+    }
+}
@@ -0,0 +1,971 @@
+use core::borrow::Borrow;
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use core::marker::PhantomData;
+use core::ops::RangeInclusive;
+use std::collections::{BTreeSet, HashSet};
+use std::hash::DefaultHasher;
+
+/// Container backing a [`Set`](super::Set)
+///
+/// Only the membership primitives are required, set operations default to
+/// `contains` and `insert` loops and are overridden where the container
+/// allows better.
+pub trait Storage<T>: Default {
+    /// Element yielded by iteration, a reference unless the storage does
+    /// not hold its elements
+    type Item<'a>: Borrow<T>
+    where
+        Self: 'a,
+        T: 'a;
+
+    type Iter<'a>: Iterator<Item = Self::Item<'a>>
+    where
+        Self: 'a,
+        T: 'a;
+
+    /// Storage for these values, or them back if some are duplicated or
+    /// cannot be held
+    fn from_unique(values: Vec<T>) -> Result<Self, Vec<T>>;
+
+    fn into_vec(self) -> Vec<T>;
+
+    fn iter(&self) -> Self::Iter<'_>;
+
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn contains(&self, value: &T) -> bool;
+
+    /// Insert a value, `false` if it was already present
+    fn insert(&mut self, value: T) -> bool;
+
+    /// Insert a value, `false` if it was already present, or the value back
+    /// if the storage cannot hold it
+    fn try_insert(&mut self, value: T) -> Result<bool, T> {
+        Ok(self.insert(value))
+    }
+
+    /// Remove a value, `false` if it was absent
+    fn remove(&mut self, value: &T) -> bool;
+
+    fn is_subset(&self, other: &Self) -> bool {
+        self.len() <= other.len() && self.iter().all(|x| other.contains(x.borrow()))
+    }
+
+    /// Whether both hold the same values
+    fn is_equal(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.is_subset(other)
+    }
+
+    /// Hash of the values independent of their order, agreeing with
+    /// `is_equal`
+    fn hash_values<H: Hasher>(&self, state: &mut H)
+    where
+        T: Hash,
+    {
+        let combined = self
+            .iter()
+            .map(|value| {
+                let mut hasher = DefaultHasher::new();
+                value.borrow().hash(&mut hasher);
+                hasher.finish()
+            })
+            .fold(0u64, u64::wrapping_add);
+        state.write_usize(self.len());
+        state.write_u64(combined);
+    }
+
+    fn is_disjoint(&self, other: &Self) -> bool {
+        let (small, large) = match self.len() <= other.len() {
+            true => (self, other),
+            false => (other, self),
+        };
+        !small.iter().any(|x| large.contains(x.borrow()))
+    }
+
+    fn union(&self, other: &Self) -> Self
+    where
+        T: Clone,
+    {
+        let mut result = Self::default();
+        for value in self.iter().chain(other.iter()) {
+            result.insert(value.borrow().clone());
+        }
+        result
+    }
+
+    fn intersection(&self, other: &Self) -> Self
+    where
+        T: Clone,
+    {
+        let mut result = Self::default();
+        for value in self.iter().filter(|x| other.contains(x.borrow())) {
+            result.insert(value.borrow().clone());
+        }
+        result
+    }
+
+    fn symmetric_difference(&self, other: &Self) -> Self
+    where
+        T: Clone,
+    {
+        let mut result = self.difference(other);
+        for value in other.iter().filter(|x| !self.contains(x.borrow())) {
+            result.insert(value.borrow().clone());
+        }
+        result
+    }
+
+    fn difference(&self, other: &Self) -> Self
+    where
+        T: Clone,
+    {
+        let mut result = Self::default();
+        for value in self.iter().filter(|x| !other.contains(x.borrow())) {
+            result.insert(value.borrow().clone());
+        }
+        result
+    }
+}
+
+fn has_duplicates(mut set: &[impl Eq]) -> bool {
+    while let Some((first, rest)) = set.split_first() {
+        if rest.iter().any(|v| first == v) {
+            return true;
+        }
+        set = rest;
+    }
+    false
+}
+
+/// Unordered vector, the fallback for types that are only `Eq`
+impl<T: Eq> Storage<T> for Vec<T> {
+    type Item<'a>
+        = &'a T
+    where
+        T: 'a;
+    type Iter<'a>
+        = core::slice::Iter<'a, T>
+    where
+        T: 'a;
+
+    fn from_unique(values: Vec<T>) -> Result<Self, Vec<T>> {
+        match has_duplicates(&values) {
+            true => Err(values),
+            false => Ok(values),
+        }
+    }
+
+    fn into_vec(self) -> Vec<T> {
+        self
+    }
+
+    fn iter(&self) -> Self::Iter<'_> {
+        self.as_slice().iter()
+    }
+
+    fn len(&self) -> usize {
+        Vec::len(self)
+    }
+
+    fn contains(&self, value: &T) -> bool {
+        self.as_slice().contains(value)
+    }
+
+    fn insert(&mut self, value: T) -> bool {
+        let absent = !Storage::contains(self, &value);
+        if absent {
+            self.push(value);
+        }
+        absent
+    }
+
+    fn remove(&mut self, value: &T) -> bool {
+        match self.as_slice().iter().position(|x| x == value) {
+            Some(index) => {
+                Vec::remove(self, index);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn union(&self, other: &Self) -> Self
+    where
+        T: Clone,
+    {
+        let mut result = self.clone();
+        for value in other {
+            if !self.as_slice().contains(value) {
+                result.push(value.clone())
+            }
+        }
+        result
+    }
+
+    fn intersection(&self, other: &Self) -> Self
+    where
+        T: Clone,
+    {
+        self.as_slice()
+            .iter()
+            .filter(|x| other.as_slice().contains(x))
+            .cloned()
+            .collect()
+    }
+
+    fn symmetric_difference(&self, other: &Self) -> Self
+    where
+        T: Clone,
+    {
+        self.as_slice()
+            .iter()
+            .filter(|x| !other.as_slice().contains(x))
+            .chain(
+                other
+                    .as_slice()
+                    .iter()
+                    .filter(|x| !self.as_slice().contains(x)),
+            )
+            .cloned()
+            .collect()
+    }
+
+    fn difference(&self, other: &Self) -> Self
+    where
+        T: Clone,
+    {
+        self.as_slice()
+            .iter()
+            .filter(|x| !other.as_slice().contains(x))
+            .cloned()
+            .collect()
+    }
+}
+
+impl<T: Hash + Eq> Storage<T> for HashSet<T> {
+    type Item<'a>
+        = &'a T
+    where
+        T: 'a;
+    type Iter<'a>
+        = std::collections::hash_set::Iter<'a, T>
+    where
+        T: 'a;
+
+    fn from_unique(values: Vec<T>) -> Result<Self, Vec<T>> {
+        let mut seen = HashSet::with_capacity(values.len());
+        if !values.iter().all(|value| seen.insert(value)) {
+            return Err(values);
+        }
+        drop(seen);
+        Ok(values.into_iter().collect())
+    }
+
+    fn into_vec(self) -> Vec<T> {
+        self.into_iter().collect()
+    }
+
+    fn iter(&self) -> Self::Iter<'_> {
+        HashSet::iter(self)
+    }
+
+    fn len(&self) -> usize {
+        HashSet::len(self)
+    }
+
+    fn contains(&self, value: &T) -> bool {
+        HashSet::contains(self, value)
+    }
+
+    fn insert(&mut self, value: T) -> bool {
+        HashSet::insert(self, value)
+    }
+
+    fn remove(&mut self, value: &T) -> bool {
+        HashSet::remove(self, value)
+    }
+
+    fn union(&self, other: &Self) -> Self
+    where
+        T: Clone,
+    {
+        HashSet::union(self, other).cloned().collect()
+    }
+
+    fn intersection(&self, other: &Self) -> Self
+    where
+        T: Clone,
+    {
+        HashSet::intersection(self, other).cloned().collect()
+    }
+
+    fn symmetric_difference(&self, other: &Self) -> Self
+    where
+        T: Clone,
+    {
+        HashSet::symmetric_difference(self, other)
+            .cloned()
+            .collect()
+    }
+
+    fn difference(&self, other: &Self) -> Self
+    where
+        T: Clone,
+    {
+        HashSet::difference(self, other).cloned().collect()
+    }
+}
+
+impl<T: Ord> Storage<T> for BTreeSet<T> {
+    type Item<'a>
+        = &'a T
+    where
+        T: 'a;
+    type Iter<'a>
+        = std::collections::btree_set::Iter<'a, T>
+    where
+        T: 'a;
+
+    fn from_unique(values: Vec<T>) -> Result<Self, Vec<T>> {
+        let mut seen = BTreeSet::new();
+        if !values.iter().all(|value| seen.insert(value)) {
+            return Err(values);
+        }
+        drop(seen);
+        Ok(values.into_iter().collect())
+    }
+
+    fn into_vec(self) -> Vec<T> {
+        self.into_iter().collect()
+    }
+
+    fn iter(&self) -> Self::Iter<'_> {
+        BTreeSet::iter(self)
+    }
+
+    fn len(&self) -> usize {
+        BTreeSet::len(self)
+    }
+
+    fn contains(&self, value: &T) -> bool {
+        BTreeSet::contains(self, value)
+    }
+
+    fn insert(&mut self, value: T) -> bool {
+        BTreeSet::insert(self, value)
+    }
+
+    fn remove(&mut self, value: &T) -> bool {
+        BTreeSet::remove(self, value)
+    }
+
+    fn union(&self, other: &Self) -> Self
+    where
+        T: Clone,
+    {
+        BTreeSet::union(self, other).cloned().collect()
+    }
+
+    fn intersection(&self, other: &Self) -> Self
+    where
+        T: Clone,
+    {
+        BTreeSet::intersection(self, other).cloned().collect()
+    }
+
+    fn symmetric_difference(&self, other: &Self) -> Self
+    where
+        T: Clone,
+    {
+        BTreeSet::symmetric_difference(self, other)
+            .cloned()
+            .collect()
+    }
+
+    fn difference(&self, other: &Self) -> Self
+    where
+        T: Clone,
+    {
+        BTreeSet::difference(self, other).cloned().collect()
+    }
+}
+
+/// Vector kept sorted, with linear merge-based set operations
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct SortedVec<T>(Vec<T>);
+
+impl<T> Default for SortedVec<T> {
+    fn default() -> Self {
+        Self(Vec::new())
+    }
+}
+
+impl<T: Ord> SortedVec<T> {
+    pub fn as_slice(&self) -> &[T] {
+        &self.0
+    }
+
+    /// Walk both sorted sequences at once, keeping elements according to
+    /// which sides they appear in
+    fn merge(&self, other: &Self, left: bool, both: bool, right: bool) -> Self
+    where
+        T: Clone,
+    {
+        use core::cmp::Ordering::*;
+
+        let mut result = Vec::new();
+        let (mut a, mut b) = (self.0.iter().peekable(), other.0.iter().peekable());
+        loop {
+            let (value, keep) = match (a.peek(), b.peek()) {
+                (None, None) => break,
+                (Some(_), None) => (a.next(), left),
+                (None, Some(_)) => (b.next(), right),
+                (Some(x), Some(y)) => match x.cmp(y) {
+                    Less => (a.next(), left),
+                    Greater => (b.next(), right),
+                    Equal => {
+                        b.next();
+                        (a.next(), both)
+                    }
+                },
+            };
+            if keep {
+                result.extend(value.cloned());
+            }
+        }
+        Self(result)
+    }
+}
+
+impl<T: Ord> Storage<T> for SortedVec<T> {
+    type Item<'a>
+        = &'a T
+    where
+        T: 'a;
+    type Iter<'a>
+        = core::slice::Iter<'a, T>
+    where
+        T: 'a;
+
+    /// Duplicates are found once sorted, in which order values are returned
+    fn from_unique(mut values: Vec<T>) -> Result<Self, Vec<T>> {
+        values.sort_unstable();
+        match values.windows(2).any(|w| w[0] == w[1]) {
+            true => Err(values),
+            false => Ok(Self(values)),
+        }
+    }
+
+    fn into_vec(self) -> Vec<T> {
+        self.0
+    }
+
+    fn iter(&self) -> Self::Iter<'_> {
+        self.0.iter()
+    }
+
+    fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    fn contains(&self, value: &T) -> bool {
+        self.0.binary_search(value).is_ok()
+    }
+
+    fn insert(&mut self, value: T) -> bool {
+        match self.0.binary_search(&value) {
+            Ok(_) => false,
+            Err(index) => {
+                self.0.insert(index, value);
+                true
+            }
+        }
+    }
+
+    fn remove(&mut self, value: &T) -> bool {
+        match self.0.binary_search(value) {
+            Ok(index) => {
+                self.0.remove(index);
+                true
+            }
+            Err(_) => false,
+        }
+    }
+
+    fn union(&self, other: &Self) -> Self
+    where
+        T: Clone,
+    {
+        self.merge(other, true, true, true)
+    }
+
+    fn intersection(&self, other: &Self) -> Self
+    where
+        T: Clone,
+    {
+        self.merge(other, false, true, false)
+    }
+
+    fn symmetric_difference(&self, other: &Self) -> Self
+    where
+        T: Clone,
+    {
+        self.merge(other, true, false, true)
+    }
+
+    fn difference(&self, other: &Self) -> Self
+    where
+        T: Clone,
+    {
+        self.merge(other, true, false, false)
+    }
+}
+
+/// Number of values of a dense universe, from index 0, bounding bitsets to
+/// 2 MiB
+pub const DENSE_UNIVERSE: usize = 1 << 24;
+
+/// Values of a small universe, identified by their index in it
+pub trait Dense: Copy {
+    /// Index below `DENSE_UNIVERSE`, `None` for values outside of the
+    /// universe
+    fn index(self) -> Option<usize>;
+
+    fn from_index(index: usize) -> Self;
+}
+
+macro_rules! impl_dense {
+    ($($type:ty),*) => {
+        $(
+            impl Dense for $type {
+                fn index(self) -> Option<usize> {
+                    usize::try_from(self).ok().filter(|&index| index < DENSE_UNIVERSE)
+                }
+
+                fn from_index(index: usize) -> Self {
+                    Self::try_from(index).expect("Index outside of the type's range")
+                }
+            }
+        )*
+    };
+}
+
+impl_dense!(u8, u16, u32, u64, usize, i8, i16, i32, i64, isize);
+
+/// One bit per value of the universe, with word-parallel set operations
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct BitSet<T> {
+    words: Vec<u64>,
+    marker: PhantomData<T>,
+}
+
+impl<T> Default for BitSet<T> {
+    fn default() -> Self {
+        Self {
+            words: Vec::new(),
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<T> BitSet<T> {
+    fn zip(&self, other: &Self, operation: impl Fn(u64, u64) -> u64) -> Self {
+        let length = self.words.len().max(other.words.len());
+        let word = |words: &[u64], i| words.get(i).copied().unwrap_or(0);
+        let mut words: Vec<u64> = (0..length)
+            .map(|i| operation(word(&self.words, i), word(&other.words, i)))
+            .collect();
+        while words.last() == Some(&0) {
+            words.pop();
+        }
+        Self {
+            words,
+            marker: PhantomData,
+        }
+    }
+}
+
+pub struct BitSetIter<'a, T> {
+    words: &'a [u64],
+    index: usize,
+    word: u64,
+    marker: PhantomData<T>,
+}
+
+impl<T: Dense> Iterator for BitSetIter<'_, T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.word == 0 {
+            self.index += 1;
+            self.word = *self.words.get(self.index)?;
+        }
+        let bit = self.word.trailing_zeros() as usize;
+        self.word &= self.word - 1;
+        Some(T::from_index(self.index * 64 + bit))
+    }
+}
+
+impl<T: Dense> Storage<T> for BitSet<T> {
+    type Item<'a>
+        = T
+    where
+        T: 'a;
+    type Iter<'a>
+        = BitSetIter<'a, T>
+    where
+        T: 'a;
+
+    fn from_unique(values: Vec<T>) -> Result<Self, Vec<T>> {
+        let mut set = Self::default();
+        if !values
+            .iter()
+            .all(|&value| matches!(set.try_insert(value), Ok(true)))
+        {
+            return Err(values);
+        }
+        Ok(set)
+    }
+
+    fn into_vec(self) -> Vec<T> {
+        self.iter().collect()
+    }
+
+    fn iter(&self) -> Self::Iter<'_> {
+        BitSetIter {
+            words: &self.words,
+            index: 0,
+            word: self.words.first().copied().unwrap_or(0),
+            marker: PhantomData,
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.words.iter().map(|w| w.count_ones() as usize).sum()
+    }
+
+    fn contains(&self, value: &T) -> bool {
+        value.index().is_some_and(|index| {
+            self.words
+                .get(index / 64)
+                .is_some_and(|word| word >> (index % 64) & 1 != 0)
+        })
+    }
+
+    /// Panics on values outside of the dense universe
+    fn insert(&mut self, value: T) -> bool {
+        match self.try_insert(value) {
+            Ok(absent) => absent,
+            Err(_) => panic!("Value outside of a dense universe"),
+        }
+    }
+
+    fn try_insert(&mut self, value: T) -> Result<bool, T> {
+        let index = value.index().ok_or(value)?;
+        if self.words.len() <= index / 64 {
+            self.words.resize(index / 64 + 1, 0);
+        }
+        let word = &mut self.words[index / 64];
+        let absent = *word >> (index % 64) & 1 == 0;
+        *word |= 1 << (index % 64);
+        Ok(absent)
+    }
+
+    fn remove(&mut self, value: &T) -> bool {
+        let present = self.contains(value);
+        if let (true, Some(index)) = (present, value.index()) {
+            self.words[index / 64] &= !(1 << (index % 64));
+            while self.words.last() == Some(&0) {
+                self.words.pop();
+            }
+        }
+        present
+    }
+
+    fn union(&self, other: &Self) -> Self {
+        self.zip(other, |a, b| a | b)
+    }
+
+    fn intersection(&self, other: &Self) -> Self {
+        self.zip(other, |a, b| a & b)
+    }
+
+    fn symmetric_difference(&self, other: &Self) -> Self {
+        self.zip(other, |a, b| a ^ b)
+    }
+
+    fn difference(&self, other: &Self) -> Self {
+        self.zip(other, |a, b| a & !b)
+    }
+}
+
+/// Integers whose sets are stored as runs of consecutive values
+pub trait Discrete: Copy + Ord {
+    const MIN: Self;
+    const MAX: Self;
+
+    /// Position among the values of the type, preserving their order
+    fn offset(self) -> u64;
+
+    fn from_offset(offset: u64) -> Self;
+}
+
+macro_rules! impl_discrete {
+    ($($unsigned:ty),* ; $($signed:ty),*) => {
+        $(
+            impl Discrete for $unsigned {
+                const MIN: Self = <$unsigned>::MIN;
+                const MAX: Self = <$unsigned>::MAX;
+
+                fn offset(self) -> u64 {
+                    self as u64
+                }
+
+                fn from_offset(offset: u64) -> Self {
+                    offset as Self
+                }
+            }
+        )*
+        $(
+            impl Discrete for $signed {
+                const MIN: Self = <$signed>::MIN;
+                const MAX: Self = <$signed>::MAX;
+
+                /// Flipping the sign bit orders negative values first
+                fn offset(self) -> u64 {
+                    self as i64 as u64 ^ 1 << 63
+                }
+
+                fn from_offset(offset: u64) -> Self {
+                    (offset ^ 1 << 63) as i64 as Self
+                }
+            }
+        )*
+    };
+}
+
+impl_discrete!(u8, u16, u32, u64, usize ; i8, i16, i32, i64, isize);
+
+/// Sorted disjoint ranges of values, with set operations linear in their
+/// number rather than in that of the values
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct Intervals<T> {
+    /// Half-open ranges of offsets, separated by at least one value
+    ranges: Vec<(u128, u128)>,
+    marker: PhantomData<T>,
+}
+
+impl<T> Default for Intervals<T> {
+    fn default() -> Self {
+        Self {
+            ranges: Vec::new(),
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<T: Discrete + fmt::Debug> fmt::Debug for Intervals<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.ranges()).finish()
+    }
+}
+
+impl<T: Discrete> Intervals<T> {
+    fn bounds(range: RangeInclusive<T>) -> Option<(u128, u128)> {
+        let (start, end) = range.into_inner();
+        (start <= end).then(|| (start.offset() as u128, end.offset() as u128 + 1))
+    }
+
+    fn from_bounds(ranges: Vec<(u128, u128)>) -> Self {
+        Self {
+            ranges,
+            marker: PhantomData,
+        }
+    }
+
+    /// Every value of the type
+    pub fn full() -> Self {
+        Self::from_bounds(Vec::from_iter(Self::bounds(T::MIN..=T::MAX)))
+    }
+
+    /// Maximal runs of consecutive values, in increasing order
+    pub fn ranges(&self) -> impl Iterator<Item = RangeInclusive<T>> + '_ {
+        self.ranges
+            .iter()
+            .map(|&(start, end)| T::from_offset(start as u64)..=T::from_offset((end - 1) as u64))
+    }
+
+    /// Add a range of values, `false` if they were all present
+    pub fn insert_range(&mut self, range: RangeInclusive<T>) -> bool {
+        let other = Self::from_bounds(Vec::from_iter(Self::bounds(range)));
+        let union = self.merge(&other, |a, b| a || b);
+        let changed = union != *self;
+        *self = union;
+        changed
+    }
+
+    /// Values of the type missing from the set
+    pub fn complement(&self) -> Self {
+        Self::full().merge(self, |a, b| a && !b)
+    }
+
+    /// Sweep the bounds of both sets at once, keeping values according to
+    /// which sides they appear in
+    fn merge(&self, other: &Self, keep: impl Fn(bool, bool) -> bool) -> Self {
+        let flatten = |set: &Self| -> Vec<u128> {
+            set.ranges
+                .iter()
+                .flat_map(|&(start, end)| [start, end])
+                .collect()
+        };
+        let (a, b) = (flatten(self), flatten(other));
+        let (mut i, mut j) = (0, 0);
+        let mut ranges = Vec::new();
+        let mut start = None;
+        while i < a.len() || j < b.len() {
+            let bound = match (a.get(i), b.get(j)) {
+                (Some(&x), Some(&y)) => x.min(y),
+                (Some(&x), None) | (None, Some(&x)) => x,
+                (None, None) => unreachable!(),
+            };
+            i += (a.get(i) == Some(&bound)) as usize;
+            j += (b.get(j) == Some(&bound)) as usize;
+            // Past an odd number of bounds means inside a range
+            match (start, keep(i % 2 == 1, j % 2 == 1)) {
+                (None, true) => start = Some(bound),
+                (Some(first), false) => {
+                    ranges.push((first, bound));
+                    start = None;
+                }
+                _ => (),
+            }
+        }
+        Self::from_bounds(ranges)
+    }
+}
+
+pub struct IntervalsIter<'a, T> {
+    ranges: core::slice::Iter<'a, (u128, u128)>,
+    current: core::ops::Range<u128>,
+    marker: PhantomData<T>,
+}
+
+impl<T: Discrete> Iterator for IntervalsIter<'_, T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.current.is_empty() {
+            let &(start, end) = self.ranges.next()?;
+            self.current = start..end;
+        }
+        self.current
+            .next()
+            .map(|offset| T::from_offset(offset as u64))
+    }
+}
+
+impl<T: Discrete> Storage<T> for Intervals<T> {
+    type Item<'a>
+        = T
+    where
+        T: 'a;
+    type Iter<'a>
+        = IntervalsIter<'a, T>
+    where
+        T: 'a;
+
+    /// Duplicates are found once sorted, in which order values are returned
+    fn from_unique(mut values: Vec<T>) -> Result<Self, Vec<T>> {
+        values.sort_unstable();
+        if values.windows(2).any(|w| w[0] == w[1]) {
+            return Err(values);
+        }
+        let mut ranges: Vec<(u128, u128)> = Vec::new();
+        for value in values {
+            let offset = value.offset() as u128;
+            match ranges.last_mut() {
+                Some((_, end)) if *end == offset => *end += 1,
+                _ => ranges.push((offset, offset + 1)),
+            }
+        }
+        Ok(Self::from_bounds(ranges))
+    }
+
+    fn into_vec(self) -> Vec<T> {
+        self.iter().collect()
+    }
+
+    fn iter(&self) -> Self::Iter<'_> {
+        IntervalsIter {
+            ranges: self.ranges.iter(),
+            current: 0..0,
+            marker: PhantomData,
+        }
+    }
+
+    /// Saturates at `usize::MAX`
+    fn len(&self) -> usize {
+        let len: u128 = self.ranges.iter().map(|(start, end)| end - start).sum();
+        usize::try_from(len).unwrap_or(usize::MAX)
+    }
+
+    fn contains(&self, value: &T) -> bool {
+        let offset = value.offset() as u128;
+        let i = self.ranges.partition_point(|&(_, end)| end <= offset);
+        self.ranges
+            .get(i)
+            .is_some_and(|&(start, _)| start <= offset)
+    }
+
+    fn insert(&mut self, value: T) -> bool {
+        self.insert_range(value..=value)
+    }
+
+    fn remove(&mut self, value: &T) -> bool {
+        let present = self.contains(value);
+        if present {
+            let single = Self::from_bounds(Vec::from_iter(Self::bounds(*value..=*value)));
+            *self = self.merge(&single, |a, b| a && !b);
+        }
+        present
+    }
+
+    fn is_subset(&self, other: &Self) -> bool {
+        self.merge(other, |a, b| a && !b).ranges.is_empty()
+    }
+
+    /// Ranges are normalized, so equal sets have the same ones, whatever
+    /// their size
+    fn is_equal(&self, other: &Self) -> bool {
+        self.ranges == other.ranges
+    }
+
+    fn hash_values<H: Hasher>(&self, state: &mut H) {
+        self.ranges.hash(state);
+    }
+
+    fn is_disjoint(&self, other: &Self) -> bool {
+        self.merge(other, |a, b| a && b).ranges.is_empty()
+    }
+
+    fn union(&self, other: &Self) -> Self {
+        self.merge(other, |a, b| a || b)
+    }
+
+    fn intersection(&self, other: &Self) -> Self {
+        self.merge(other, |a, b| a && b)
+    }
+
+    fn symmetric_difference(&self, other: &Self) -> Self {
+        self.merge(other, |a, b| a != b)
+    }
+
+    fn difference(&self, other: &Self) -> Self {
+        self.merge(other, |a, b| a && !b)
+    }
+}
@@ -0,0 +1,293 @@
+use core::fmt::Write;
+
+use crate::arithmetic::gray_code;
+use crate::expression::{Environment, Expression, Symbol};
+
+/// Whether the formula is the empty set whatever its sets
+pub fn is_always_empty(expression: &Expression) -> bool {
+    !expression.sat()
+}
+
+/// Whether the formula is the whole universe whatever its sets
+pub fn is_always_universe(expression: &Expression) -> bool {
+    !Expression::not(expression.clone()).sat()
+}
+
+/// Whether both formulas are the same set whatever their sets
+pub fn are_equivalent(a: &Expression, b: &Expression) -> bool {
+    !Expression::xor(a.clone(), b.clone()).sat()
+}
+
+/// Every region of the Venn diagram of the formula's sets, as whether each
+/// set contains it, the first set varying the slowest
+///
+/// A formula without sets has the universe as single region.
+pub fn regions(expression: &Expression) -> impl Iterator<Item = Environment> {
+    let symbols: Vec<Symbol> = expression.symbols().collect();
+    (0..1u64 << symbols.len()).map(move |index| {
+        let mut region = Environment::default();
+        for (i, &symbol) in symbols.iter().rev().enumerate() {
+            region.set(symbol, index >> i & 1 != 0);
+        }
+        region
+    })
+}
+
+/// Regions of the Venn diagram contained in the formula
+pub fn covered(expression: &Expression) -> impl Iterator<Item = Environment> + '_ {
+    regions(expression).filter(|&region| expression.eval(region))
+}
+
+/// Largest number of sets a diagram is drawn for
+pub const MAX_DIAGRAM_SETS: usize = 4;
+
+/// Karnaugh map of the regions, `#` for covered and `.` for the others,
+/// rows and columns in Gray code order so neighbours differ by one set
+///
+/// ```text
+/// A\BC 00 01 11 10
+///    0  .  #  #  .
+///    1  #  #  #  #
+/// ```
+pub fn text(expression: &Expression) -> Option<String> {
+    let symbols: Vec<Symbol> = expression.symbols().collect();
+    if symbols.len() > MAX_DIAGRAM_SETS {
+        return None;
+    }
+    let (rows, columns) = symbols.split_at(symbols.len() / 2);
+    let name = |symbols: &[Symbol]| String::from_utf8(symbols.to_vec()).unwrap();
+    let label = format!("{}\\{}", name(rows), name(columns));
+    let width = columns.len().max(1);
+
+    let mut text = label.clone();
+    for column in 0..1 << columns.len() {
+        let code = gray_code(column);
+        write!(text, " {code:0width$b}").unwrap();
+    }
+    for row in 0..1 << rows.len() {
+        let row = gray_code(row);
+        let code = match rows.len() {
+            0 => String::new(),
+            n => format!("{row:0n$b}"),
+        };
+        write!(text, "\n{code:>0$}", label.len()).unwrap();
+        for column in 0..1 << columns.len() {
+            let column = gray_code(column);
+            let mut region = Environment::default();
+            for (symbols, code) in [(rows, row), (columns, column)] {
+                for (i, &symbol) in symbols.iter().enumerate() {
+                    region.set(symbol, code >> (symbols.len() - 1 - i) & 1 != 0);
+                }
+            }
+            let cell = match expression.eval(region) {
+                true => '#',
+                false => '.',
+            };
+            write!(text, " {cell:>width$}").unwrap();
+        }
+    }
+    Some(text)
+}
+
+/// Shape of a set in the SVG diagram and where its name goes
+struct Outline {
+    shape: &'static str,
+    label: (u32, u32),
+}
+
+/// Circles up to three sets, the classic ellipses for four
+const OUTLINES: [&[Outline]; MAX_DIAGRAM_SETS] = [
+    &[Outline {
+        shape: r#"<circle cx="200" cy="200" r="120"/>"#,
+        label: (200, 65),
+    }],
+    &[
+        Outline {
+            shape: r#"<circle cx="160" cy="200" r="110"/>"#,
+            label: (100, 80),
+        },
+        Outline {
+            shape: r#"<circle cx="240" cy="200" r="110"/>"#,
+            label: (300, 80),
+        },
+    ],
+    &[
+        Outline {
+            shape: r#"<circle cx="200" cy="160" r="100"/>"#,
+            label: (200, 45),
+        },
+        Outline {
+            shape: r#"<circle cx="160" cy="230" r="100"/>"#,
+            label: (55, 340),
+        },
+        Outline {
+            shape: r#"<circle cx="240" cy="230" r="100"/>"#,
+            label: (345, 340),
+        },
+    ],
+    &[
+        Outline {
+            shape: r#"<ellipse cx="140" cy="240" rx="144" ry="90" transform="rotate(40 140 240)"/>"#,
+            label: (30, 110),
+        },
+        Outline {
+            shape: r#"<ellipse cx="180" cy="200" rx="144" ry="90" transform="rotate(40 180 200)"/>"#,
+            label: (115, 45),
+        },
+        Outline {
+            shape: r#"<ellipse cx="217.6" cy="200" rx="144" ry="90" transform="rotate(-40 217.6 200)"/>"#,
+            label: (285, 45),
+        },
+        Outline {
+            shape: r#"<ellipse cx="257.6" cy="240" rx="144" ry="90" transform="rotate(-40 257.6 240)"/>"#,
+            label: (370, 110),
+        },
+    ],
+];
+
+/// Venn diagram with the covered regions filled, inside a box standing for
+/// the universe
+pub fn svg(expression: &Expression) -> Option<String> {
+    let symbols: Vec<Symbol> = expression.symbols().collect();
+    if symbols.len() > MAX_DIAGRAM_SETS {
+        return None;
+    }
+    let outlines = match symbols.len() {
+        0 => &[][..],
+        n => OUTLINES[n - 1],
+    };
+
+    let mut svg = String::from(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="400" height="400" viewBox="0 0 400 400">"#,
+    );
+    svg.push_str("\n<defs>");
+    for (i, outline) in outlines.iter().enumerate() {
+        let shape = outline.shape;
+        write!(svg, "\n<clipPath id=\"in{i}\">{shape}</clipPath>").unwrap();
+        write!(
+            svg,
+            "\n<mask id=\"out{i}\"><rect width=\"400\" height=\"400\" fill=\"white\"/>\
+             <g fill=\"black\">{shape}</g></mask>"
+        )
+        .unwrap();
+    }
+    svg.push_str("\n</defs>");
+
+    // A region is the box clipped to the sets containing it, with the
+    // other sets masked out
+    for region in covered(expression) {
+        let mut groups = String::new();
+        for (i, &symbol) in symbols.iter().enumerate() {
+            match region.get(symbol).unwrap() {
+                true => write!(groups, "<g clip-path=\"url(#in{i})\">").unwrap(),
+                false => write!(groups, "<g mask=\"url(#out{i})\">").unwrap(),
+            }
+        }
+        let close = "</g>".repeat(symbols.len());
+        write!(
+            svg,
+            "\n<g class=\"region\">{groups}<rect width=\"400\" height=\"400\" fill=\"#8cb4e0\"/>{close}</g>"
+        )
+        .unwrap();
+    }
+
+    svg.push_str("\n<rect width=\"400\" height=\"400\" fill=\"none\" stroke=\"black\"/>");
+    svg.push_str("\n<g fill=\"none\" stroke=\"black\">");
+    for outline in outlines {
+        write!(svg, "\n{}", outline.shape).unwrap();
+    }
+    svg.push_str("\n</g>");
+    for (outline, &symbol) in outlines.iter().zip(&symbols) {
+        let (x, y) = outline.label;
+        let name = symbol as char;
+        write!(
+            svg,
+            "\n<text x=\"{x}\" y=\"{y}\" text-anchor=\"middle\" font-size=\"24\">{name}</text>"
+        )
+        .unwrap();
+    }
+    svg.push_str("\n</svg>\n");
+    Some(svg)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::expression::Expression;
+
+    fn parse(formula: &str) -> Expression {
+        formula.parse().unwrap()
+    }
+
+    #[test]
+    fn analysis() {
+        assert!(super::is_always_empty(&parse("AA!&")));
+        assert!(super::is_always_empty(&parse("AB&A!&")));
+        assert!(!super::is_always_empty(&parse("AB&")));
+        assert!(super::is_always_universe(&parse("AA!|")));
+        assert!(super::is_always_universe(&parse("AB&A>")));
+        assert!(!super::is_always_universe(&parse("AB|")));
+
+        // De Morgan and distributivity hold for sets
+        assert!(super::are_equivalent(&parse("AB|!"), &parse("A!B!&")));
+        assert!(super::are_equivalent(&parse("AB|C&"), &parse("AC&BC&|")));
+        assert!(super::are_equivalent(&parse("AB^"), &parse("AB|AB&!&")));
+        assert!(!super::are_equivalent(&parse("AB|"), &parse("AB^")));
+    }
+
+    #[test]
+    fn regions() {
+        let expression = parse("AB&C|");
+        assert_eq!(super::regions(&expression).count(), 8);
+        let covered: Vec<Vec<bool>> = super::covered(&expression)
+            .map(|region| region.values().collect())
+            .collect();
+        let expected = [
+            [false, false, true],
+            [false, true, true],
+            [true, false, true],
+            [true, true, false],
+            [true, true, true],
+        ];
+        assert_eq!(covered, expected);
+        assert_eq!(super::covered(&parse("1")).count(), 1);
+        assert_eq!(super::covered(&parse("0")).count(), 0);
+    }
+
+    #[test]
+    fn text() {
+        assert_eq!(
+            super::text(&parse("AB&C|")).unwrap(),
+            "A\\BC 00 01 11 10\n   0  .  #  #  .\n   1  .  #  #  #"
+        );
+        assert_eq!(
+            super::text(&parse("AB^")).unwrap(),
+            "A\\B 0 1\n  0 . #\n  1 # ."
+        );
+        assert_eq!(super::text(&parse("A!")).unwrap(), "\\A 0 1\n   # .");
+        assert_eq!(
+            super::text(&parse("AB&CD&|")).unwrap(),
+            "AB\\CD 00 01 11 10\n   00  .  .  #  .\n   01  .  .  #  .\n   11  #  #  #  #\n   10  .  .  #  ."
+        );
+        assert_eq!(super::text(&parse("ABCDE||||")), None);
+    }
+
+    #[test]
+    fn svg() {
+        for (formula, regions) in [
+            ("1", 1),
+            ("A", 1),
+            ("AB^", 2),
+            ("AB|C&", 3),
+            ("ABCD&&&!", 15),
+        ] {
+            let svg = super::svg(&parse(formula)).unwrap();
+            assert!(svg.starts_with("<svg") && svg.ends_with("</svg>\n"));
+            assert_eq!(
+                svg.matches("class=\"region\"").count(),
+                regions,
+                "{formula}"
+            );
+        }
+        assert!(super::svg(&parse("ABCDE||||")).is_none());
+    }
+}
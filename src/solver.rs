@@ -0,0 +1,839 @@
+use crate::cnf::{Class, Clause, Cnf, Literal, Variable};
+use crate::expression::Expression;
+use crate::proof::Proof;
+
+/// Solve with the fastest algorithm applicable to the formula's class
+pub fn solve(cnf: &Cnf) -> Option<Vec<bool>> {
+    match cnf.class() {
+        Class::TwoSat => two_sat(cnf),
+        Class::Horn => horn(cnf),
+        Class::DualHorn => dual_horn(cnf),
+        Class::General => Solver::from(cnf.clone()).solve(),
+    }
+}
+
+/// Linear time 2-SAT through the strongly connected components of the
+/// implication graph
+pub fn two_sat(cnf: &Cnf) -> Option<Vec<bool>> {
+    debug_assert_eq!(cnf.class(), Class::TwoSat);
+    let nodes = 2 * cnf.variables() as usize;
+    let mut graph = vec![Vec::new(); nodes];
+    let mut transposed = vec![Vec::new(); nodes];
+    let mut implies = |a: Literal, b: Literal| {
+        graph[a.index()].push(b.index());
+        transposed[b.index()].push(a.index());
+    };
+    for clause in cnf.clauses() {
+        match clause[..] {
+            [] => return None,
+            [a] => implies(!a, a),
+            [a, b] => {
+                implies(!a, b);
+                implies(!b, a);
+            }
+            _ => unreachable!("Clause has more than two literals"),
+        }
+    }
+
+    // Kosaraju: components come out in topological order
+    let mut order = Vec::with_capacity(nodes);
+    let mut visited = vec![false; nodes];
+    for root in 0..nodes {
+        if visited[root] {
+            continue;
+        }
+        visited[root] = true;
+        let mut stack = vec![(root, 0)];
+        while let Some((node, edge)) = stack.last_mut() {
+            match graph[*node].get(*edge) {
+                Some(&next) => {
+                    *edge += 1;
+                    if !visited[next] {
+                        visited[next] = true;
+                        stack.push((next, 0));
+                    }
+                }
+                None => {
+                    order.push(*node);
+                    stack.pop();
+                }
+            }
+        }
+    }
+    let mut component = vec![usize::MAX; nodes];
+    let mut count = 0;
+    for &root in order.iter().rev() {
+        if component[root] != usize::MAX {
+            continue;
+        }
+        component[root] = count;
+        let mut stack = vec![root];
+        while let Some(node) = stack.pop() {
+            for &next in &transposed[node] {
+                if component[next] == usize::MAX {
+                    component[next] = count;
+                    stack.push(next);
+                }
+            }
+        }
+        count += 1;
+    }
+
+    (0..cnf.variables())
+        .map(|variable| {
+            let positive = component[Literal::positive(variable).index()];
+            let negative = component[Literal::negative(variable).index()];
+            (positive != negative).then_some(positive > negative)
+        })
+        .collect()
+}
+
+/// Linear time Horn-SAT through unit propagation from the all false
+/// assignment
+pub fn horn(cnf: &Cnf) -> Option<Vec<bool>> {
+    debug_assert_eq!(cnf.class(), Class::Horn);
+    let mut model = vec![false; cnf.variables() as usize];
+    // Number of negative literals of each clause not yet falsified
+    let mut pending: Vec<usize> = Vec::with_capacity(cnf.clauses().len());
+    let mut watchers = vec![Vec::new(); cnf.variables() as usize];
+    let mut queue = Vec::new();
+    for (index, clause) in cnf.clauses().iter().enumerate() {
+        let negatives = clause.iter().filter(|l| l.is_negative());
+        for literal in negatives.clone() {
+            watchers[literal.variable() as usize].push(index);
+        }
+        pending.push(negatives.count());
+        if pending[index] == 0 {
+            queue.push(index);
+        }
+    }
+
+    while let Some(index) = queue.pop() {
+        // A fully falsified goal clause cannot be satisfied
+        let head = cnf.clauses()[index].iter().find(|l| !l.is_negative())?;
+        let variable = head.variable() as usize;
+        if model[variable] {
+            continue;
+        }
+        model[variable] = true;
+        for &index in &watchers[variable] {
+            pending[index] -= 1;
+            if pending[index] == 0 {
+                queue.push(index);
+            }
+        }
+    }
+    Some(model)
+}
+
+/// Horn-SAT on the formula with every literal flipped
+pub fn dual_horn(cnf: &Cnf) -> Option<Vec<bool>> {
+    debug_assert_eq!(cnf.class(), Class::DualHorn);
+    let mut flipped = Cnf::with_variables(cnf.variables());
+    for clause in cnf.clauses() {
+        flipped.add_clause(clause.iter().map(|&l| !l));
+    }
+    let model = horn(&flipped)?;
+    Some(model.into_iter().map(|value| !value).collect())
+}
+
+/// General backtracking search with unit propagation, without the clause
+/// learning of [`Solver`] that [`solve`] relies on
+pub fn dpll(cnf: &Cnf) -> Option<Vec<bool>> {
+    let mut search = Dpll::new(cnf.clauses(), cnf.variables());
+    search.search().then(|| search.model())
+}
+
+/// Refutation of an unsatisfiable formula, `None` if it is satisfiable
+///
+/// Every failed branch of the search yields the negation of its decisions
+/// as a lemma, which unit propagation derives from the previous ones.
+pub fn refute(cnf: &Cnf) -> Option<Proof> {
+    let mut search = Dpll::new(cnf.clauses(), cnf.variables());
+    search.lemmas = Some(Vec::new());
+    match search.search() {
+        true => None,
+        false => search.lemmas.map(Proof::from_lemmas),
+    }
+}
+
+/// Incremental conflict-driven clause learning solver, keeping its clauses,
+/// everything it learned and its top-level assignments between queries
+///
+/// Clauses live in nested scopes: each scope has an activation variable,
+/// assumed while it is open, that its clauses and the clauses learned from
+/// them are disabled through once `pop` closes it.
+#[derive(Clone, Debug, Default)]
+pub struct Solver {
+    /// Clauses as added, in the open scopes
+    clauses: Vec<Clause>,
+    variables: u32,
+    /// Number of clauses and activation literal of each open scope
+    scopes: Vec<(usize, Literal)>,
+    /// Engine variable of each variable, activation variables living in
+    /// between
+    internal: Vec<Variable>,
+    engine: Cdcl,
+}
+
+impl Solver {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn variables(&self) -> u32 {
+        self.variables
+    }
+
+    pub fn clauses(&self) -> &[Clause] {
+        &self.clauses
+    }
+
+    pub fn fresh_variable(&mut self) -> Variable {
+        self.variables += 1;
+        self.variables - 1
+    }
+
+    /// Engine literal of a literal, allocating the variables up to it
+    fn internal(&mut self, literal: Literal) -> Literal {
+        let variable = literal.variable() as usize;
+        while self.internal.len() <= variable {
+            let next = self.engine.new_variable();
+            self.internal.push(next);
+        }
+        Literal::new(self.internal[variable], literal.value())
+    }
+
+    /// Add a clause, sorting and deduplicating its literals
+    pub fn add_clause(&mut self, clause: impl IntoIterator<Item = Literal>) {
+        let mut clause: Clause = clause.into_iter().collect();
+        clause.sort_unstable();
+        clause.dedup();
+        if let Some(max) = clause.iter().map(|l| l.variable() + 1).max() {
+            self.variables = self.variables.max(max);
+        }
+        let mut internal: Clause = clause.iter().map(|&l| self.internal(l)).collect();
+        if let Some(&(_, activation)) = self.scopes.last() {
+            internal.push(!activation);
+        }
+        self.engine.add_clause(internal);
+        self.clauses.push(clause);
+    }
+
+    pub fn add_cnf(&mut self, cnf: &Cnf) {
+        self.variables = self.variables.max(cnf.variables());
+        for clause in cnf.clauses() {
+            self.add_clause(clause.iter().copied());
+        }
+    }
+
+    pub fn add_expression(&mut self, expression: &Expression) {
+        self.add_cnf(&Cnf::from(expression));
+    }
+
+    /// Open a scope
+    pub fn push(&mut self) {
+        let activation = Literal::positive(self.engine.new_variable());
+        self.scopes.push((self.clauses.len(), activation));
+    }
+
+    /// Close the innermost scope, `false` if there is none
+    pub fn pop(&mut self) -> bool {
+        match self.scopes.pop() {
+            Some((length, activation)) => {
+                self.clauses.truncate(length);
+                self.engine.add_clause(vec![!activation]);
+                self.engine.simplify();
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn solve(&mut self) -> Option<Vec<bool>> {
+        self.solve_with_assumptions(&[])
+    }
+
+    /// Solve with some literals temporarily forced true
+    pub fn solve_with_assumptions(&mut self, assumptions: &[Literal]) -> Option<Vec<bool>> {
+        let variables = assumptions
+            .iter()
+            .map(|l| l.variable() + 1)
+            .fold(self.variables, u32::max);
+        if variables > 0 {
+            self.internal(Literal::positive(variables - 1));
+        }
+        let assumptions: Vec<Literal> = assumptions.iter().map(|&l| self.internal(l)).collect();
+        let assumptions: Vec<Literal> = self
+            .scopes
+            .iter()
+            .map(|&(_, activation)| activation)
+            .chain(assumptions)
+            .collect();
+        let model = self.engine.solve(&assumptions)?;
+        Some(
+            (0..variables as usize)
+                .map(|variable| model[self.internal[variable] as usize])
+                .collect(),
+        )
+    }
+}
+
+impl From<Cnf> for Solver {
+    fn from(cnf: Cnf) -> Self {
+        let mut solver = Self::new();
+        solver.add_cnf(&cnf);
+        solver
+    }
+}
+
+fn literal_value(assignment: &[Option<bool>], literal: Literal) -> Option<bool> {
+    assignment[literal.variable() as usize].map(|v| v == literal.value())
+}
+
+/// Clause learning search state, clauses of two or more literals being
+/// watched through their first two
+#[derive(Clone, Debug, Default)]
+struct Cdcl {
+    clauses: Vec<Clause>,
+    /// Clauses watching each literal, visited when it becomes false
+    watches: Vec<Vec<usize>>,
+    assignment: Vec<Option<bool>>,
+    level: Vec<usize>,
+    /// Clause that implied each assigned variable, `None` for decisions
+    reason: Vec<Option<usize>>,
+    /// Value each variable is decided to, the last it had
+    phase: Vec<bool>,
+    activity: Vec<f64>,
+    increment: f64,
+    trail: Vec<Literal>,
+    /// Trail length at the start of each decision level
+    levels: Vec<usize>,
+    /// Trail position of the next assignment to propagate
+    head: usize,
+    /// Top-level conflict, unsatisfiable under any assumption
+    inconsistent: bool,
+}
+
+impl Cdcl {
+    fn new_variable(&mut self) -> Variable {
+        let variable = self.assignment.len() as Variable;
+        self.assignment.push(None);
+        self.level.push(0);
+        self.reason.push(None);
+        self.phase.push(false);
+        self.activity.push(0.0);
+        self.watches.extend([Vec::new(), Vec::new()]);
+        variable
+    }
+
+    fn value(&self, literal: Literal) -> Option<bool> {
+        literal_value(&self.assignment, literal)
+    }
+
+    fn decision_level(&self) -> usize {
+        self.levels.len()
+    }
+
+    fn assign(&mut self, literal: Literal, reason: Option<usize>) {
+        let variable = literal.variable() as usize;
+        self.assignment[variable] = Some(literal.value());
+        self.level[variable] = self.decision_level();
+        self.reason[variable] = reason;
+        self.trail.push(literal);
+    }
+
+    fn backtrack(&mut self, level: usize) {
+        if level >= self.decision_level() {
+            return;
+        }
+        let length = self.levels[level];
+        for literal in self.trail.drain(length..) {
+            let variable = literal.variable() as usize;
+            self.assignment[variable] = None;
+            self.phase[variable] = literal.value();
+        }
+        self.levels.truncate(level);
+        self.head = length;
+    }
+
+    fn attach(&mut self, clause: Clause) -> usize {
+        let index = self.clauses.len();
+        self.watches[clause[0].index()].push(index);
+        self.watches[clause[1].index()].push(index);
+        self.clauses.push(clause);
+        index
+    }
+
+    /// Add a clause at the top level, dropping its literals false there
+    fn add_clause(&mut self, mut clause: Clause) {
+        self.backtrack(0);
+        clause.sort_unstable();
+        clause.dedup();
+        let tautology = clause.windows(2).any(|w| w[0] == !w[1]);
+        if self.inconsistent || tautology || clause.iter().any(|&l| self.value(l) == Some(true)) {
+            return;
+        }
+        clause.retain(|&l| self.value(l).is_none());
+        match clause[..] {
+            [] => self.inconsistent = true,
+            [literal] => {
+                self.assign(literal, None);
+                self.inconsistent = self.propagate().is_some();
+            }
+            _ => {
+                self.attach(clause);
+            }
+        }
+    }
+
+    /// Drop the clauses satisfied at the top level
+    fn simplify(&mut self) {
+        self.backtrack(0);
+        if self.inconsistent {
+            return;
+        }
+        let clauses = std::mem::take(&mut self.clauses);
+        self.watches.iter_mut().for_each(Vec::clear);
+        self.reason.iter_mut().for_each(|reason| *reason = None);
+        for clause in clauses {
+            if !clause.iter().any(|&l| self.value(l) == Some(true)) {
+                self.attach(clause);
+            }
+        }
+    }
+
+    /// Assign implied literals until fixpoint, the conflicting clause if any
+    fn propagate(&mut self) -> Option<usize> {
+        while self.head < self.trail.len() {
+            let falsified = !self.trail[self.head];
+            self.head += 1;
+            let mut watchers = std::mem::take(&mut self.watches[falsified.index()]);
+            let mut i = 0;
+            while i < watchers.len() {
+                let index = watchers[i];
+                let clause = &mut self.clauses[index];
+                if clause[0] == falsified {
+                    clause.swap(0, 1);
+                }
+                let first = clause[0];
+                if literal_value(&self.assignment, first) == Some(true) {
+                    i += 1;
+                    continue;
+                }
+                let replacement = (2..clause.len())
+                    .find(|&k| literal_value(&self.assignment, clause[k]) != Some(false));
+                if let Some(k) = replacement {
+                    clause.swap(1, k);
+                    self.watches[clause[1].index()].push(index);
+                    watchers.swap_remove(i);
+                    continue;
+                }
+                if self.value(first) == Some(false) {
+                    self.watches[falsified.index()] = watchers;
+                    return Some(index);
+                }
+                self.assign(first, Some(index));
+                i += 1;
+            }
+            self.watches[falsified.index()] = watchers;
+        }
+        None
+    }
+
+    fn bump(&mut self, variable: usize) {
+        self.activity[variable] += self.increment;
+        if self.activity[variable] > 1e100 {
+            self.activity.iter_mut().for_each(|a| *a *= 1e-100);
+            self.increment *= 1e-100;
+        }
+    }
+
+    /// First unique implication point clause of a conflict, its asserting
+    /// literal first, and the level to backjump to
+    fn analyze(&mut self, conflict: usize) -> (Clause, usize) {
+        let mut seen = vec![false; self.assignment.len()];
+        let mut learned = vec![Literal::positive(0)];
+        let mut pending = 0;
+        let mut clause = conflict;
+        let mut index = self.trail.len();
+        let mut pivot = None;
+        loop {
+            for k in 0..self.clauses[clause].len() {
+                let literal = self.clauses[clause][k];
+                let variable = literal.variable() as usize;
+                if Some(literal) == pivot || seen[variable] || self.level[variable] == 0 {
+                    continue;
+                }
+                seen[variable] = true;
+                self.bump(variable);
+                match self.level[variable] == self.decision_level() {
+                    true => pending += 1,
+                    false => learned.push(literal),
+                }
+            }
+            let literal = loop {
+                index -= 1;
+                if seen[self.trail[index].variable() as usize] {
+                    break self.trail[index];
+                }
+            };
+            seen[literal.variable() as usize] = false;
+            pending -= 1;
+            if pending == 0 {
+                learned[0] = !literal;
+                break;
+            }
+            clause = self.reason[literal.variable() as usize].unwrap();
+            pivot = Some(literal);
+        }
+        self.increment *= 1.05;
+
+        // Watch the literal of the highest level after the asserting one
+        let level = |literal: &Literal| self.level[literal.variable() as usize];
+        let backjump = match (1..learned.len()).max_by_key(|&k| level(&learned[k])) {
+            Some(k) => {
+                learned.swap(1, k);
+                level(&learned[1])
+            }
+            None => 0,
+        };
+        (learned, backjump)
+    }
+
+    /// Unassigned variable of highest activity
+    fn branch(&self) -> Option<usize> {
+        (0..self.assignment.len())
+            .filter(|&variable| self.assignment[variable].is_none())
+            .max_by(|&a, &b| {
+                self.activity[a]
+                    .total_cmp(&self.activity[b])
+                    .then(b.cmp(&a))
+            })
+    }
+
+    /// Model under the assumptions, each decided on a level of its own,
+    /// returning to the top level with what was learned kept
+    fn solve(&mut self, assumptions: &[Literal]) -> Option<Vec<bool>> {
+        if self.increment == 0.0 {
+            self.increment = 1.0;
+        }
+        self.backtrack(0);
+        if self.inconsistent || self.propagate().is_some() {
+            self.inconsistent = true;
+            return None;
+        }
+        let model = loop {
+            if let Some(conflict) = self.propagate() {
+                if self.decision_level() == 0 {
+                    self.inconsistent = true;
+                    break None;
+                }
+                let (learned, level) = self.analyze(conflict);
+                self.backtrack(level);
+                match learned.len() {
+                    1 => self.assign(learned[0], None),
+                    _ => {
+                        let asserting = learned[0];
+                        let index = self.attach(learned);
+                        self.assign(asserting, Some(index));
+                    }
+                }
+                continue;
+            }
+            let literal = match assumptions.get(self.decision_level()) {
+                Some(&assumption) => match self.value(assumption) {
+                    // Already implied, its level left empty
+                    Some(true) => {
+                        self.levels.push(self.trail.len());
+                        continue;
+                    }
+                    Some(false) => break None,
+                    None => assumption,
+                },
+                None => match self.branch() {
+                    Some(variable) => Literal::new(variable as Variable, self.phase[variable]),
+                    None => {
+                        break Some(self.assignment.iter().map(|v| v.unwrap()).collect());
+                    }
+                },
+            };
+            self.levels.push(self.trail.len());
+            self.assign(literal, None);
+        };
+        self.backtrack(0);
+        model
+    }
+}
+
+struct Dpll<'a> {
+    clauses: &'a [Clause],
+    assignment: Vec<Option<bool>>,
+    trail: Vec<Literal>,
+    decisions: Vec<Literal>,
+    lemmas: Option<Vec<Clause>>,
+}
+
+impl<'a> Dpll<'a> {
+    fn new(clauses: &'a [Clause], variables: u32) -> Self {
+        Self {
+            clauses,
+            assignment: vec![None; variables as usize],
+            trail: Vec::new(),
+            decisions: Vec::new(),
+            lemmas: None,
+        }
+    }
+
+    fn value(&self, literal: Literal) -> Option<bool> {
+        self.assignment[literal.variable() as usize].map(|v| v == literal.value())
+    }
+
+    fn assign(&mut self, literal: Literal) {
+        self.assignment[literal.variable() as usize] = Some(literal.value());
+        self.trail.push(literal);
+    }
+
+    fn backtrack(&mut self, length: usize) {
+        for literal in self.trail.drain(length..) {
+            self.assignment[literal.variable() as usize] = None;
+        }
+    }
+
+    /// Assign unit clauses until fixpoint, `false` on conflict
+    fn propagate(&mut self) -> bool {
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for clause in self.clauses {
+                let mut unassigned = None;
+                let mut count = 0;
+                for &literal in clause {
+                    match self.value(literal) {
+                        Some(true) => {
+                            count = usize::MAX;
+                            break;
+                        }
+                        Some(false) => {}
+                        None => {
+                            unassigned = Some(literal);
+                            count += 1;
+                        }
+                    }
+                }
+                match (count, unassigned) {
+                    (0, _) => return false,
+                    (1, Some(literal)) => {
+                        self.assign(literal);
+                        changed = true;
+                    }
+                    _ => {}
+                }
+            }
+        }
+        true
+    }
+
+    /// First unassigned literal of an unsatisfied clause
+    fn branch(&self) -> Option<Literal> {
+        self.clauses
+            .iter()
+            .filter(|clause| !clause.iter().any(|&l| self.value(l) == Some(true)))
+            .find_map(|clause| clause.iter().copied().find(|&l| self.value(l).is_none()))
+    }
+
+    fn search(&mut self) -> bool {
+        let mark = self.trail.len();
+        if self.propagate() {
+            let Some(literal) = self.branch() else {
+                return true;
+            };
+            for literal in [literal, !literal] {
+                let decision = self.trail.len();
+                self.assign(literal);
+                self.decisions.push(literal);
+                if self.search() {
+                    return true;
+                }
+                self.decisions.pop();
+                self.backtrack(decision);
+            }
+        }
+        if let Some(lemmas) = &mut self.lemmas {
+            lemmas.push(self.decisions.iter().map(|&d| !d).collect());
+        }
+        self.backtrack(mark);
+        false
+    }
+
+    fn model(&self) -> Vec<bool> {
+        self.assignment.iter().map(|v| v.unwrap_or(false)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cnf::{Class, Cnf};
+    use crate::expression::Expression;
+
+    /// Every solver must agree with the truth table
+    fn check(formula: &str, class: Class) {
+        let expression: Expression = formula.parse().unwrap();
+        let cnf = Cnf::from(&expression);
+        assert_eq!(cnf.class(), class, "{formula}");
+        let expected = expression.envs().any(|env| expression.eval(env));
+        let solvers = [super::solve, super::dpll];
+        for solver in solvers {
+            let model = solver(&cnf);
+            assert_eq!(model.is_some(), expected, "{formula}");
+            if let Some(model) = model {
+                assert!(cnf.eval(&model), "{formula}");
+            }
+        }
+    }
+
+    #[test]
+    fn incremental() {
+        use super::Solver;
+        use crate::cnf::Literal;
+
+        let [a, b, c] = [b'A', b'B', b'C'].map(Literal::symbol);
+        let mut solver = Solver::new();
+        solver.add_expression(&"AB|BC|&".parse().unwrap());
+        assert!(solver.solve().is_some());
+
+        let model = solver.solve_with_assumptions(&[!b]).unwrap();
+        assert_eq!(model, [true, false, true]);
+        assert!(solver.solve_with_assumptions(&[!a, !b]).is_none());
+        assert!(solver.solve_with_assumptions(&[a, !a]).is_none());
+        assert!(solver.solve_with_assumptions(&[!a, !c]).is_some());
+
+        solver.push();
+        solver.add_clause([!b]);
+        assert!(solver.solve_with_assumptions(&[!c]).is_none());
+        solver.push();
+        solver.add_clause([!a]);
+        assert!(solver.solve().is_none());
+        assert!(solver.pop());
+        assert!(solver.solve().is_some());
+        assert!(solver.pop());
+        assert!(!solver.pop());
+        assert!(solver.solve_with_assumptions(&[!c]).is_some());
+
+        let fresh = Literal::positive(solver.fresh_variable());
+        solver.add_clause([!fresh, !a]);
+        assert_eq!(solver.solve_with_assumptions(&[fresh, !b]), None);
+    }
+
+    #[test]
+    fn incremental_random() {
+        use super::Solver;
+        use crate::cnf::Literal;
+
+        let mut state = 0x9e37_79b9_7f4a_7c15_u64;
+        let mut random = move |bound: u32| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            (state >> 32) as u32 % bound
+        };
+        for _ in 0..100 {
+            let mut solver = Solver::new();
+            for _ in 0..8 {
+                solver.fresh_variable();
+            }
+            // Clauses of each open scope, checked from scratch with DPLL
+            let mut scopes = vec![Vec::new()];
+            for _ in 0..30 {
+                match random(6) {
+                    0 => {
+                        solver.push();
+                        scopes.push(Vec::new());
+                    }
+                    1 if scopes.len() > 1 => {
+                        assert!(solver.pop());
+                        scopes.pop();
+                    }
+                    _ => {
+                        let clause: Vec<Literal> = (0..3)
+                            .map(|_| Literal::new(random(8), random(2) == 1))
+                            .collect();
+                        solver.add_clause(clause.iter().copied());
+                        scopes.last_mut().unwrap().push(clause);
+                    }
+                }
+                let assumptions: Vec<Literal> = (0..random(3))
+                    .map(|_| Literal::new(random(8), random(2) == 1))
+                    .collect();
+                let mut cnf = Cnf::with_variables(8);
+                for clause in scopes.iter().flatten() {
+                    cnf.add_clause(clause.iter().copied());
+                }
+                for &assumption in &assumptions {
+                    cnf.add_clause([assumption]);
+                }
+                let model = solver.solve_with_assumptions(&assumptions);
+                assert_eq!(model.is_some(), super::dpll(&cnf).is_some());
+                if let Some(model) = model {
+                    assert!(cnf.eval(&model));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn learned_clauses_kept() {
+        use super::Solver;
+        use crate::cnf::Literal;
+
+        // Five pigeons in four holes, behind an assumption
+        let mut solver = Solver::new();
+        let enable = Literal::positive(solver.fresh_variable());
+        let hole = |pigeon: u32, hole: u32| Literal::positive(1 + pigeon * 4 + hole);
+        for pigeon in 0..5 {
+            solver.add_clause((0..4).map(|h| hole(pigeon, h)).chain([!enable]));
+            for other in 0..pigeon {
+                for h in 0..4 {
+                    solver.add_clause([!hole(pigeon, h), !hole(other, h)]);
+                }
+            }
+        }
+        let loaded = solver.engine.clauses.len();
+        assert!(solver.solve_with_assumptions(&[enable]).is_none());
+        let learned = solver.engine.clauses.len();
+        assert!(learned > loaded);
+        assert_eq!(solver.solve().map(|model| model[0]), Some(false));
+        assert!(solver.engine.clauses.len() >= learned);
+    }
+
+    #[test]
+    fn two_sat() {
+        check("AB|A!B|&A!B!|&", Class::TwoSat);
+        check("AB|A!B|&AB!|&A!B!|&", Class::TwoSat);
+        check("AB>BC>&CA!>&", Class::TwoSat);
+        check("AB>BC>&CA!>&A&", Class::TwoSat);
+        check("AA!&", Class::TwoSat);
+        check("0", Class::TwoSat);
+    }
+
+    #[test]
+    fn horn() {
+        check("AB&C>AB!C!||&", Class::Horn);
+        check("AB&C>A!B!C!||&A&B&", Class::Horn);
+        check("AB&C>A&B&C!&", Class::Horn);
+    }
+
+    #[test]
+    fn dual_horn() {
+        check("ABC||A!BC||&AB!C||&", Class::DualHorn);
+        check("ABC||A!&B!&C!&", Class::DualHorn);
+    }
+
+    #[test]
+    fn general() {
+        check("ABC^^", Class::General);
+        check("ABC||A!B!C!||&AB&!&", Class::General);
+        check("ABCD^^^ABCD&&&|AB!|&", Class::General);
+        check("ABC||A!B!C!||&AB=&BC=&", Class::General);
+    }
+}